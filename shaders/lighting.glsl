@@ -0,0 +1,23 @@
+// Blinn-Phong lighting, computed in view space like the default object material.
+
+// The diffuse and specular factors of a point light for a surface point, with its unit normal.
+vec2 blinn_phong(vec3 normal, vec3 pos, vec3 light_pos, float shininess) {
+  vec3 lightDir = normalize(light_pos - pos);
+
+  float lambertian = max(dot(lightDir, normal), 0.0);
+  float specular = 0.0;
+
+  if(lambertian > 0.0) {
+    vec3 viewDir = normalize(-pos);
+    vec3 halfDir = normalize(lightDir + viewDir);
+    float specAngle = max(dot(halfDir, normal), 0.0);
+    specular = pow(specAngle, shininess);
+  }
+
+  return vec2(lambertian, specular);
+}
+
+// The color of a surface lit with the factors of blinn_phong, a third of it being ambient.
+vec3 shade(vec3 color, vec3 spec_color, vec2 light) {
+  return color / 3.0 + light.x * color / 3.0 + light.y * spec_color / 3.0;
+}
@@ -0,0 +1,187 @@
+use crate::camera::camera::Camera;
+use crate::context::context::Context;
+use crate::light::Light;
+use crate::resource::effect::{Effect, ShaderAttribute, ShaderUniform};
+use crate::resource::material::Material;
+use crate::resource::mesh::Mesh;
+use crate::resource::vertex_index::VERTEX_INDEX_TYPE;
+use crate::scene::object::ObjectData;
+use crate::{ignore, verify};
+use nalgebra::{Isometry3, Matrix3, Matrix4, Point3, Point4, Vector3};
+
+/// A material drawing objects with a flat, unlit color used to identify them.
+///
+/// The color is set with `set_id` or `set_color` before each draw and ignores the object own
+/// color.
+pub struct IdMaterial {
+    effect: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    color: ShaderUniform<Point4<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    id_color: Point4<f32>,
+}
+
+impl IdMaterial {
+    pub fn new() -> IdMaterial {
+        let mut effect = Effect::new_from_str(ID_VERTEX_SRC, ID_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        effect.use_program();
+
+        IdMaterial {
+            pos: effect.get_attrib("position").unwrap(),
+            color: effect.get_uniform("color").unwrap(),
+            transform: effect.get_uniform("transform").unwrap(),
+            scale: effect.get_uniform("scale").unwrap(),
+            view: effect.get_uniform("view").unwrap(),
+            proj: effect.get_uniform("proj").unwrap(),
+            id_color: Point4::origin(),
+            effect,
+        }
+    }
+
+    /// Sets the identifier written by the next draws. Only the 24 lower bits are used.
+    pub fn set_id(&mut self, id: u32) {
+        let color = id_to_color(id);
+        self.id_color = Point4::new(color.x, color.y, color.z, 1.0);
+    }
+
+    /// Sets the raw RGBA color written by the next draws.
+    pub fn set_color(&mut self, color: Point4<f32>) {
+        self.id_color = color;
+    }
+}
+
+/// Encodes an identifier into an 8-bit per channel color.
+pub fn id_to_color(id: u32) -> Point3<f32> {
+    Point3::new(
+        (id & 0xff) as f32 / 255.0,
+        ((id >> 8) & 0xff) as f32 / 255.0,
+        ((id >> 16) & 0xff) as f32 / 255.0,
+    )
+}
+
+/// Decodes an identifier from an RGB pixel written with `id_to_color`.
+pub fn color_to_id(rgb: &[u8]) -> u32 {
+    rgb[0] as u32 | ((rgb[1] as u32) << 8) | ((rgb[2] as u32) << 16)
+}
+
+impl Material for IdMaterial {
+    fn render(
+        &mut self,
+        pass: usize,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        camera: &mut dyn Camera,
+        _: &Light,
+        data: &ObjectData,
+        mesh: &mut Mesh,
+    ) {
+        let ctxt = Context::get();
+        self.effect.use_program();
+        self.pos.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        let formated_transform = transform.to_homogeneous();
+        let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
+
+        self.transform.upload(&formated_transform);
+        self.scale.upload(&formated_scale);
+        self.color.upload(&self.id_color);
+
+        mesh.bind_coords(&mut self.pos);
+        mesh.bind_faces();
+
+        if data.surface_rendering_active() {
+            if data.backface_culling_enabled() {
+                verify!(ctxt.enable(Context::CULL_FACE));
+            } else {
+                verify!(ctxt.disable(Context::CULL_FACE));
+            }
+
+            let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+            verify!(ctxt.draw_elements(
+                Context::TRIANGLES,
+                mesh.num_pts() as i32,
+                VERTEX_INDEX_TYPE,
+                0
+            ));
+        }
+
+        if data.lines_width() != 0.0 {
+            verify!(ctxt.disable(Context::CULL_FACE));
+            ignore!(ctxt.line_width(data.lines_width()));
+
+            if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::LINE)) {
+                verify!(ctxt.draw_elements(
+                    Context::TRIANGLES,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            } else {
+                mesh.bind_edges();
+                verify!(ctxt.draw_elements(
+                    Context::LINES,
+                    mesh.num_pts() as i32 * 2,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            }
+            ctxt.line_width(1.0);
+        }
+
+        if data.points_size() != 0.0 {
+            verify!(ctxt.disable(Context::CULL_FACE));
+            ctxt.point_size(data.points_size());
+            if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::POINT)) {
+                verify!(ctxt.draw_elements(
+                    Context::TRIANGLES,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            } else {
+                verify!(ctxt.draw_elements(
+                    Context::POINTS,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            }
+            ctxt.point_size(1.0);
+        }
+
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+        mesh.unbind();
+        self.pos.disable();
+    }
+}
+
+static ID_VERTEX_SRC: &str = "#version 100
+attribute vec3 position;
+uniform mat4 proj, view, transform;
+uniform mat3 scale;
+
+void main() {
+    gl_Position = proj * view * transform * vec4(scale * position, 1.0);
+}
+";
+
+static ID_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+uniform vec4 color;
+
+void main() {
+    gl_FragColor = color;
+}
+";
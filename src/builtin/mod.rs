@@ -1,4 +1,6 @@
 pub mod object_material;
 pub mod uvs_material;
 pub mod normals_material;
-pub mod planar_object_material;
\ No newline at end of file
+pub mod planar_object_material;
+pub mod id_material;
+pub mod shader_material;
\ No newline at end of file
@@ -22,7 +22,8 @@ pub struct NormalsMaterial {
 impl NormalsMaterial {
     /// Creates a new NormalsMaterial.
     pub fn new() -> NormalsMaterial {
-        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
@@ -5,9 +5,11 @@ use crate::resource::vertex_index::VERTEX_INDEX_TYPE;
 use crate::resource::material::Material;
 use crate::resource::effect::{Effect, ShaderAttribute, ShaderUniform};
 use crate::resource::mesh::Mesh;
+use crate::resource::shader_diagnostics::ShaderError;
 use crate::scene::object::ObjectData;
 use crate::{ignore, verify};
 use nalgebra::{Isometry3, Matrix3, Matrix4, Point2, Point3, Vector3};
+use std::path::Path;
 
 pub struct ObjectMaterial {
     effect: Effect,
@@ -16,6 +18,9 @@ pub struct ObjectMaterial {
     tex_coord: ShaderAttribute<Point2<f32>>,
     light: ShaderUniform<Point3<f32>>,
     color: ShaderUniform<Point3<f32>>,
+    specular_color: ShaderUniform<Point3<f32>>,
+    shininess: ShaderUniform<f32>,
+    emissive: ShaderUniform<Point3<f32>>,
     transform: ShaderUniform<Matrix4<f32>>,
     scale: ShaderUniform<Matrix3<f32>>,
     ntransform: ShaderUniform<Matrix3<f32>>,
@@ -25,23 +30,39 @@ pub struct ObjectMaterial {
 
 impl ObjectMaterial {
     pub fn new() -> ObjectMaterial {
-        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC);
+        let effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
+        ObjectMaterial::with_effect(effect).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, with shaders read from files instead of the ones built into the crate.
+    ///
+    /// Given `shaders/default.vert` and `shaders/default.frag`, this lets the built-in shaders be
+    /// edited while the program runs with `Window::set_hot_reload`. Objects use it once given to
+    /// `SceneNode::set_material`.
+    pub fn new_from_files(vshader: &Path, fshader: &Path) -> Result<ObjectMaterial, ShaderError> {
+        Effect::new(vshader, fshader).and_then(ObjectMaterial::with_effect)
+    }
 
+    fn with_effect(mut effect: Effect) -> Result<ObjectMaterial, ShaderError> {
         effect.use_program();
 
-        ObjectMaterial {
-            pos: effect.get_attrib("position").unwrap(),
-            normal: effect.get_attrib("normal").unwrap(),
-            tex_coord: effect.get_attrib("tex_coord").unwrap(),
-            light: effect.get_uniform("light_position").unwrap(),
-            color: effect.get_uniform("color").unwrap(),
-            transform: effect.get_uniform("transform").unwrap(),
-            scale: effect.get_uniform("scale").unwrap(),
-            ntransform: effect.get_uniform("ntransform").unwrap(),
-            view: effect.get_uniform("view").unwrap(),
-            proj: effect.get_uniform("proj").unwrap(),
+        Ok(ObjectMaterial {
+            pos: effect.require_attrib("position")?,
+            normal: effect.require_attrib("normal")?,
+            tex_coord: effect.require_attrib("tex_coord")?,
+            light: effect.require_uniform("light_position")?,
+            color: effect.require_uniform("color")?,
+            specular_color: effect.require_uniform("specular_color")?,
+            shininess: effect.require_uniform("shininess")?,
+            emissive: effect.require_uniform("emissive")?,
+            transform: effect.require_uniform("transform")?,
+            scale: effect.require_uniform("scale")?,
+            ntransform: effect.require_uniform("ntransform")?,
+            view: effect.require_uniform("view")?,
+            proj: effect.require_uniform("proj")?,
             effect,
-        }
+        })
     }
 
     fn activate(&mut self) {
@@ -81,6 +102,11 @@ impl Material for ObjectMaterial {
 
         self.light.upload(&pos);
 
+        let parameters = data.material_parameters();
+        self.specular_color.upload(&parameters.specular_color);
+        self.shininess.upload(&parameters.shininess);
+        self.emissive.upload(&parameters.emissive);
+
         let formated_transform = transform.to_homogeneous();
         let formated_ntransform = transform.rotation.to_rotation_matrix().into_inner();
         let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
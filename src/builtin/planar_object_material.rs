@@ -21,7 +21,8 @@ pub struct PlanarObjectMaterial {
 
 impl PlanarObjectMaterial {
     pub fn new() -> PlanarObjectMaterial {
-        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC);
+        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         effect.use_program();
 
@@ -0,0 +1,477 @@
+//! A material defined by its shaders only, with per-object uniforms.
+
+use crate::camera::camera::Camera;
+use crate::context::context::{ActiveVariable, Context};
+use crate::light::Light;
+use crate::resource::effect::{Effect, ShaderAttribute, ShaderUniform};
+use crate::resource::gl_primitive::GLPrimitive;
+use crate::resource::material::Material;
+use crate::resource::mesh::Mesh;
+use crate::resource::shader_diagnostics::ShaderError;
+use crate::resource::texture_manager::TextureManager;
+use crate::resource::uniform_value::{UniformType, UniformValue};
+use crate::resource::vertex_index::VERTEX_INDEX_TYPE;
+use crate::scene::object::ObjectData;
+use crate::{ignore, verify};
+use nalgebra::{Isometry3, Matrix2, Matrix3, Matrix4, Point2, Point3, Vector2, Vector3, Vector4};
+use std::collections::HashSet;
+use std::path::Path;
+
+// The uniforms set by the material itself, named like in the shaders of `ObjectMaterial`.
+const STANDARD_UNIFORMS: [&str; 8] = [
+    "transform",
+    "ntransform",
+    "scale",
+    "view",
+    "proj",
+    "light_position",
+    "color",
+    "tex",
+];
+
+// The attributes bound to the buffers of the meshes.
+const STANDARD_ATTRIBUTES: [&str; 3] = ["position", "normal", "tex_coord"];
+
+/// A material whose uniforms are found in its shaders instead of being declared in Rust.
+///
+/// The uniforms and attributes named like in the shaders of the default material are set by the
+/// material: the `position`, `normal` and `tex_coord` attributes, and the `transform`,
+/// `ntransform`, `scale`, `view`, `proj`, `light_position`, `color` and `tex` uniforms. The
+/// shaders may use any of them.
+///
+/// Every other `float`, `int`, `vec2`, `vec3`, `vec4`, `mat2`, `mat3`, `mat4` or `sampler2D`
+/// uniform is a parameter, read from the `MaterialParameters` of each object: the uniforms set
+/// with `SceneNode::set_uniform`, the textures, and the fields like `shininess`. Objects without a
+/// value use the default of the parameter, zero or a white texture unless set with
+/// `set_default`.
+///
+/// Uniforms and attributes that cannot be set, and parameters given values of the wrong type, are
+/// reported by `warnings`.
+pub struct ShaderMaterial {
+    effect: Effect,
+    pos: Option<ShaderAttribute<Point3<f32>>>,
+    normal: Option<ShaderAttribute<Vector3<f32>>>,
+    tex_coord: Option<ShaderAttribute<Point2<f32>>>,
+    light: ShaderUniform<Point3<f32>>,
+    color: ShaderUniform<Point3<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    ntransform: ShaderUniform<Matrix3<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    tex: ShaderUniform<i32>,
+    parameters: Vec<Parameter>,
+    // The parameters objects gave a value of the wrong type to, reported once.
+    mismatches: HashSet<String>,
+    warnings: Vec<String>,
+}
+
+// A uniform set per object.
+struct Parameter {
+    name: String,
+    default: UniformValue,
+    uniform: ParameterUniform,
+}
+
+enum ParameterUniform {
+    Float(ShaderUniform<f32>),
+    Int(ShaderUniform<i32>),
+    Vec2(ShaderUniform<Vector2<f32>>),
+    Vec3(ShaderUniform<Vector3<f32>>),
+    Vec4(ShaderUniform<Vector4<f32>>),
+    Mat2(ShaderUniform<Matrix2<f32>>),
+    Mat3(ShaderUniform<Matrix3<f32>>),
+    Mat4(ShaderUniform<Matrix4<f32>>),
+    // The sampler, and the texture unit it reads from.
+    Texture(ShaderUniform<i32>, u32),
+}
+
+impl ParameterUniform {
+    // Uploads `value`, unless it does not have the type of the uniform.
+    fn upload(&mut self, value: &UniformValue) -> bool {
+        match (self, value) {
+            (ParameterUniform::Float(u), UniformValue::Float(v)) => u.upload(v),
+            (ParameterUniform::Int(u), UniformValue::Int(v)) => u.upload(v),
+            (ParameterUniform::Vec2(u), UniformValue::Vec2(v)) => u.upload(v),
+            (ParameterUniform::Vec3(u), UniformValue::Vec3(v)) => u.upload(v),
+            (ParameterUniform::Vec4(u), UniformValue::Vec4(v)) => u.upload(v),
+            (ParameterUniform::Mat2(u), UniformValue::Mat2(v)) => u.upload(v),
+            (ParameterUniform::Mat3(u), UniformValue::Mat3(v)) => u.upload(v),
+            (ParameterUniform::Mat4(u), UniformValue::Mat4(v)) => u.upload(v),
+            (ParameterUniform::Texture(u, unit), UniformValue::Texture(texture)) => {
+                let ctxt = Context::get();
+                verify!(ctxt.active_texture(Context::TEXTURE0 + *unit));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&**texture)));
+                u.upload(&(*unit as i32));
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+impl ShaderMaterial {
+    /// Compiles a material from the GLSL code of its shaders, preprocessed like with
+    /// `Effect::new_from_str`.
+    pub fn new(vshader: &str, fshader: &str) -> Result<ShaderMaterial, ShaderError> {
+        Effect::new_from_str(vshader, fshader).map(ShaderMaterial::from_effect)
+    }
+
+    /// Like `new`, with shaders read from files.
+    ///
+    /// The shaders are reloaded when the files change while hot reloading is enabled, but the
+    /// parameters are the ones of the shaders the material was created with.
+    pub fn new_from_files(vshader: &Path, fshader: &Path) -> Result<ShaderMaterial, ShaderError> {
+        Effect::new(vshader, fshader).map(ShaderMaterial::from_effect)
+    }
+
+    /// A material using the uniforms and attributes of `effect`.
+    pub fn from_effect(mut effect: Effect) -> ShaderMaterial {
+        effect.use_program();
+
+        let mut warnings = Vec::new();
+
+        for attribute in effect.active_attributes() {
+            if !STANDARD_ATTRIBUTES.contains(&attribute.name.as_str()) {
+                warnings.push(format!(
+                    "Unsupported shader attribute, it will not be bound: {}",
+                    attribute.name
+                ));
+            }
+        }
+
+        let uniforms = effect.active_uniforms();
+        let mut next_unit = 1;
+        let parameters = uniforms
+            .iter()
+            .filter(|u| !STANDARD_UNIFORMS.contains(&u.name.as_str()))
+            .filter_map(|u| parameter(&effect, u, &mut next_unit, &mut warnings))
+            .collect();
+        let w = &mut warnings;
+
+        ShaderMaterial {
+            pos: effect.get_attrib("position"),
+            normal: effect.get_attrib("normal"),
+            tex_coord: effect.get_attrib("tex_coord"),
+            light: standard(&effect, &uniforms, "light_position", UniformType::Vec3, w),
+            color: standard(&effect, &uniforms, "color", UniformType::Vec3, w),
+            transform: standard(&effect, &uniforms, "transform", UniformType::Mat4, w),
+            scale: standard(&effect, &uniforms, "scale", UniformType::Mat3, w),
+            ntransform: standard(&effect, &uniforms, "ntransform", UniformType::Mat3, w),
+            view: standard(&effect, &uniforms, "view", UniformType::Mat4, w),
+            proj: standard(&effect, &uniforms, "proj", UniformType::Mat4, w),
+            tex: standard(&effect, &uniforms, "tex", UniformType::Texture, w),
+            parameters,
+            mismatches: HashSet::new(),
+            warnings,
+            effect,
+        }
+    }
+
+    /// The problems found in the shaders when the material was created, followed by the
+    /// parameters objects gave a value of the wrong type to since then, once per parameter.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The names and types of the uniforms set per object.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, UniformType)> + '_ {
+        self.parameters
+            .iter()
+            .map(|p| (p.name.as_str(), p.default.uniform_type()))
+    }
+
+    /// Sets the value of the parameter `name` for the objects that do not set it.
+    pub fn set_default<V: Into<UniformValue>>(
+        &mut self,
+        name: &str,
+        value: V,
+    ) -> Result<(), String> {
+        let value = value.into();
+        let parameter = self
+            .parameters
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("The shaders have no parameter named {}", name))?;
+
+        if parameter.default.uniform_type() != value.uniform_type() {
+            return Err(format!(
+                "The parameter {} is a {:?}, not a {:?}",
+                name,
+                parameter.default.uniform_type(),
+                value.uniform_type()
+            ));
+        }
+
+        parameter.default = value;
+        Ok(())
+    }
+
+    fn activate(&mut self) {
+        self.effect.use_program();
+
+        if let Some(pos) = &mut self.pos {
+            pos.enable();
+        }
+        if let Some(normal) = &mut self.normal {
+            normal.enable();
+        }
+        if let Some(tex_coord) = &mut self.tex_coord {
+            tex_coord.enable();
+        }
+    }
+
+    fn deactivate(&mut self) {
+        if let Some(pos) = &mut self.pos {
+            pos.disable();
+        }
+        if let Some(normal) = &mut self.normal {
+            normal.disable();
+        }
+        if let Some(tex_coord) = &mut self.tex_coord {
+            tex_coord.disable();
+        }
+    }
+
+    fn upload_parameters(&mut self, data: &ObjectData) {
+        for parameter in &mut self.parameters {
+            let value = data.material_parameters().uniform(&parameter.name);
+            let value = value.as_ref().unwrap_or(&parameter.default);
+
+            if !parameter.uniform.upload(value) {
+                if self.mismatches.insert(parameter.name.clone()) {
+                    self.warnings.push(format!(
+                        "The uniform {} is a {:?}, but an object set it to a {:?}",
+                        parameter.name,
+                        parameter.default.uniform_type(),
+                        value.uniform_type()
+                    ));
+                }
+
+                let _ = parameter.uniform.upload(&parameter.default);
+            }
+        }
+
+        verify!(Context::get().active_texture(Context::TEXTURE0));
+    }
+}
+
+// The standard uniform `name`. Unused if the shaders do not declare it, or declare it with another
+// type, as uploading it would fail.
+fn standard<T: GLPrimitive>(
+    effect: &Effect,
+    uniforms: &[ActiveVariable],
+    name: &str,
+    expected: UniformType,
+    warnings: &mut Vec<String>,
+) -> ShaderUniform<T> {
+    let declared = uniforms.iter().find(|u| u.name == name);
+    let valid = |u: &ActiveVariable| UniformType::from_gl(u.type_) == Some(expected) && u.size == 1;
+
+    match declared {
+        Some(u) if valid(u) => {
+            if let Some(uniform) = effect.get_uniform(name) {
+                return uniform;
+            }
+        }
+        Some(_) => warnings.push(format!(
+            "The uniform {} must be a {:?} to be set",
+            name, expected
+        )),
+        None => {}
+    }
+
+    ShaderUniform::unused()
+}
+
+// The parameter set through the uniform `variable`, if it has a supported type. Samplers are
+// given the next texture unit, the unit 0 being the one of `tex`.
+fn parameter(
+    effect: &Effect,
+    variable: &ActiveVariable,
+    next_unit: &mut u32,
+    warnings: &mut Vec<String>,
+) -> Option<Parameter> {
+    let uniform_type = UniformType::from_gl(variable.type_).filter(|_| variable.size == 1);
+    let uniform_type = match uniform_type {
+        Some(uniform_type) => uniform_type,
+        None => {
+            warnings.push(format!(
+                "Unsupported shader uniform, it will not be set: {}",
+                variable.name
+            ));
+            return None;
+        }
+    };
+
+    let name = variable.name.clone();
+    let (uniform, default) = match uniform_type {
+        UniformType::Float => (
+            ParameterUniform::Float(effect.get_uniform(&name)?),
+            UniformValue::Float(0.0),
+        ),
+        UniformType::Int => (
+            ParameterUniform::Int(effect.get_uniform(&name)?),
+            UniformValue::Int(0),
+        ),
+        UniformType::Vec2 => (
+            ParameterUniform::Vec2(effect.get_uniform(&name)?),
+            UniformValue::Vec2(Vector2::zeros()),
+        ),
+        UniformType::Vec3 => (
+            ParameterUniform::Vec3(effect.get_uniform(&name)?),
+            UniformValue::Vec3(Vector3::zeros()),
+        ),
+        UniformType::Vec4 => (
+            ParameterUniform::Vec4(effect.get_uniform(&name)?),
+            UniformValue::Vec4(Vector4::zeros()),
+        ),
+        UniformType::Mat2 => (
+            ParameterUniform::Mat2(effect.get_uniform(&name)?),
+            UniformValue::Mat2(Matrix2::zeros()),
+        ),
+        UniformType::Mat3 => (
+            ParameterUniform::Mat3(effect.get_uniform(&name)?),
+            UniformValue::Mat3(Matrix3::zeros()),
+        ),
+        UniformType::Mat4 => (
+            ParameterUniform::Mat4(effect.get_uniform(&name)?),
+            UniformValue::Mat4(Matrix4::zeros()),
+        ),
+        UniformType::Texture => {
+            let unit = *next_unit;
+            *next_unit += 1;
+            let texture = TextureManager::get_global_manager(|tm| tm.get_default());
+            (
+                ParameterUniform::Texture(effect.get_uniform(&name)?, unit),
+                UniformValue::Texture(texture),
+            )
+        }
+    };
+
+    Some(Parameter {
+        name,
+        default,
+        uniform,
+    })
+}
+
+impl Material for ShaderMaterial {
+    fn render(
+        &mut self,
+        pass: usize,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        camera: &mut dyn Camera,
+        light: &Light,
+        data: &ObjectData,
+        mesh: &mut Mesh,
+    ) {
+        let ctxt = Context::get();
+        self.activate();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        let pos = match *light {
+            Light::Absolute(ref p) => *p,
+            Light::StickToCamera => camera.eye(),
+        };
+
+        self.light.upload(&pos);
+
+        let formated_transform = transform.to_homogeneous();
+        let formated_ntransform = transform.rotation.to_rotation_matrix().into_inner();
+        let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
+
+        self.transform.upload(&formated_transform);
+        self.ntransform.upload(&formated_ntransform);
+        self.scale.upload(&formated_scale);
+        self.upload_parameters(data);
+
+        if let Some(pos) = &mut self.pos {
+            mesh.bind_coords(pos);
+        }
+        if let Some(normal) = &mut self.normal {
+            mesh.bind_normals(normal);
+        }
+        if let Some(tex_coord) = &mut self.tex_coord {
+            mesh.bind_uvs(tex_coord);
+        }
+        mesh.bind_faces();
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(data.texture())));
+        self.tex.upload(&0);
+
+        if data.surface_rendering_active() {
+            self.color.upload(data.color());
+
+            if data.backface_culling_enabled() {
+                verify!(ctxt.enable(Context::CULL_FACE));
+            } else {
+                verify!(ctxt.disable(Context::CULL_FACE));
+            }
+
+            let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+            verify!(ctxt.draw_elements(
+                Context::TRIANGLES,
+                mesh.num_pts() as i32,
+                VERTEX_INDEX_TYPE,
+                0
+            ));
+        }
+
+        if data.lines_width() != 0.0 {
+            self.color
+                .upload(data.lines_color().unwrap_or(data.color()));
+
+            verify!(ctxt.disable(Context::CULL_FACE));
+            ignore!(ctxt.line_width(data.lines_width()));
+
+            if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::LINE)) {
+                verify!(ctxt.draw_elements(
+                    Context::TRIANGLES,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            } else {
+                mesh.bind_edges();
+                verify!(ctxt.draw_elements(
+                    Context::LINES,
+                    mesh.num_pts() as i32 * 2,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            }
+            ctxt.line_width(1.0);
+        }
+
+        if data.points_size() != 0.0 {
+            self.color.upload(data.color());
+
+            verify!(ctxt.disable(Context::CULL_FACE));
+            ctxt.point_size(data.points_size());
+            if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::POINT)) {
+                verify!(ctxt.draw_elements(
+                    Context::TRIANGLES,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            } else {
+                verify!(ctxt.draw_elements(
+                    Context::POINTS,
+                    mesh.num_pts() as i32,
+                    VERTEX_INDEX_TYPE,
+                    0
+                ));
+            }
+            ctxt.point_size(1.0);
+        }
+
+        mesh.unbind();
+        self.deactivate();
+    }
+}
@@ -20,7 +20,8 @@ pub struct UvsMaterial {
 
 impl UvsMaterial {
     pub fn new() -> UvsMaterial {
-        let mut shader = Effect::new_from_str(UVS_VERTEX_SRC, UVS_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(UVS_VERTEX_SRC, UVS_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
@@ -1,5 +1,6 @@
 use crate::camera::camera::Camera;
-use crate::event::window_event::{Action, Key, Modifiers, MouseButton, WindowEvent};
+use crate::event::input_map::{Binding, InputMap};
+use crate::event::window_event::{Key, Modifiers, MouseButton, WindowEvent};
 use crate::resource::effect::ShaderUniform;
 use crate::window::canvas::Canvas;
 use nalgebra::{self, Isometry3, Matrix4, Perspective3, Point3, Unit, UnitQuaternion, Vector2, Vector3};
@@ -18,11 +19,7 @@ pub struct ArcBall {
     min_pitch: f32,
     max_pitch: f32,
     dist_step: f32,
-    rotate_button: Option<MouseButton>,
-    rotate_modifiers: Option<Modifiers>,
-    drag_button: Option<MouseButton>,
-    drag_modifiers: Option<Modifiers>,
-    reset_key: Option<Key>,
+    input_map: InputMap,
 
     projection: Perspective3<f32>,
     view: Matrix4<f32>,
@@ -58,11 +55,7 @@ impl ArcBall {
             min_pitch: 0.01,
             max_pitch: std::f32::consts::PI - 0.01,
             dist_step: 1.01,
-            rotate_button: Some(MouseButton::Button1),
-            rotate_modifiers: None,
-            drag_button: Some(MouseButton::Button2),
-            drag_modifiers: None,
-            reset_key: Some(Key::Return),
+            input_map: ArcBall::default_input_map(),
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
             view: nalgebra::zero(),
             proj: nalgebra::zero(),
@@ -87,6 +80,19 @@ impl ArcBall {
         self.update_projviews();
     }
 
+    /// The vertical field of view, in radians.
+    pub fn fov(&self) -> f32 {
+        self.projection.fovy()
+    }
+
+    /// Moves the camera so that the given sphere fills the view, keeping the view direction.
+    pub fn frame_sphere(&mut self, center: Point3<f32>, radius: f32) {
+        let dist = radius / (self.fov() / 2.0).sin();
+
+        self.at = center;
+        self.set_dist(dist * 1.1);
+    }
+
     pub fn yaw(&self) -> f32 {
         self.yaw
     }
@@ -191,44 +197,67 @@ impl ArcBall {
         }
     }
 
+    /// The bindings used by default: `orbit` with the left mouse button, `pan` with the right
+    /// mouse button, and `reset_view` with the return key.
+    pub fn default_input_map() -> InputMap {
+        let mut input_map = InputMap::new();
+        input_map.bind("orbit", Binding::mouse_button(MouseButton::Button1));
+        input_map.bind("pan", Binding::mouse_button(MouseButton::Button2));
+        input_map.bind("reset_view", Binding::key(Key::Return));
+        input_map
+    }
+
+    /// The bindings of the `orbit`, `pan` and `reset_view` actions of this camera.
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.input_map = input_map
+    }
+
     pub fn rotate_button(&self) -> Option<MouseButton> {
-        self.rotate_button
+        self.input_map.mouse_button("orbit")
     }
 
     pub fn rebind_rotate_button(&mut self, new_button: Option<MouseButton>) {
-        self.rotate_button = new_button;
+        self.input_map.rebind_mouse_button("orbit", new_button)
     }
 
     pub fn rotate_modifiers(&self) -> Option<Modifiers> {
-        self.rotate_modifiers
+        self.input_map.modifiers("orbit")
     }
 
     pub fn set_rotate_modifiers(&mut self, modifiers: Option<Modifiers>) {
-        self.rotate_modifiers = modifiers
+        self.input_map.set_modifiers("orbit", modifiers)
     }
 
     pub fn drag_modifiers(&self) -> Option<Modifiers> {
-        self.drag_modifiers
+        self.input_map.modifiers("pan")
     }
 
     pub fn set_drag_modifiers(&mut self, modifiers: Option<Modifiers>) {
-        self.drag_modifiers = modifiers
+        self.input_map.set_modifiers("pan", modifiers)
     }
 
     pub fn drag_button(&self) -> Option<MouseButton> {
-        self.drag_button
+        self.input_map.mouse_button("pan")
     }
 
     pub fn rebind_drag_button(&mut self, new_button: Option<MouseButton>) {
-        self.drag_button = new_button;
+        self.input_map.rebind_mouse_button("pan", new_button)
     }
 
     pub fn reset_key(&self) -> Option<Key> {
-        self.reset_key
+        self.input_map.key("reset_view")
     }
 
     pub fn rebind_reset_key(&mut self, new_key: Option<Key>) {
-        self.reset_key = new_key;
+        self.input_map.rebind_key("reset_view", new_key)
     }
 
     fn handle_left_button_displacement(&mut self, dpos: &Vector2<f32>) {
@@ -305,36 +334,30 @@ impl Camera for ArcBall {
         self.coord_system.rotation_to_y_up.inverse() * Point3::new(px, py, pz)
     }
 
-    fn handle_event(&mut self, canvas: &Canvas, event: &WindowEvent) {
+    fn handle_event(&mut self, _: &Canvas, event: &WindowEvent) {
+        let was_resetting = self.input_map.is_pressed("reset_view");
+        self.input_map.handle_event(event);
+
         match *event {
-            WindowEvent::CursorPos(x, y, modifiers) => {
+            WindowEvent::CursorPos(x, y, _) => {
                 let curr_pos = Vector2::new(x as f32, y as f32);
 
-                if let Some(rotate_button) = self.rotate_button {
-                    if canvas.get_mouse_button(rotate_button) == Action::Press
-                        && self
-                            .rotate_modifiers
-                            .map(|m| m == modifiers)
-                            .unwrap_or(true)
-                    {
-                        let dpos = curr_pos - self.last_cursor_pos;
-                        self.handle_left_button_displacement(&dpos)
-                    }
+                if self.input_map.is_pressed("orbit") {
+                    let dpos = curr_pos - self.last_cursor_pos;
+                    self.handle_left_button_displacement(&dpos)
                 }
 
-                if let Some(drag_button) = self.drag_button {
-                    if canvas.get_mouse_button(drag_button) == Action::Press
-                        && self.drag_modifiers.map(|m| m == modifiers).unwrap_or(true)
-                    {
-                        let dpos = curr_pos - self.last_cursor_pos;
-                        let dpos_norm = dpos.component_div(&self.last_framebuffer_size);
-                        self.handle_right_button_displacement(&dpos_norm)
-                    }
+                if self.input_map.is_pressed("pan") {
+                    let dpos = curr_pos - self.last_cursor_pos;
+                    let dpos_norm = dpos.component_div(&self.last_framebuffer_size);
+                    self.handle_right_button_displacement(&dpos_norm)
                 }
 
                 self.last_cursor_pos = curr_pos;
             }
-            WindowEvent::Key(key, Action::Press, _) if Some(key) == self.reset_key => {
+            WindowEvent::Key(..) | WindowEvent::MouseButton(..)
+                if !was_resetting && self.input_map.is_pressed("reset_view") =>
+            {
                 self.at = Point3::origin();
                 self.update_projviews();
             }
@@ -367,7 +390,13 @@ impl Camera for ArcBall {
         self.inverse_proj_view
     }
 
-    fn update(&mut self, _: &Canvas) {}
+    fn update(&mut self, _: &Canvas) {
+        self.input_map.new_frame();
+    }
+
+    fn frame_sphere(&mut self, center: Point3<f32>, radius: f32) {
+        ArcBall::frame_sphere(self, center, radius)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
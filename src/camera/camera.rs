@@ -1,3 +1,4 @@
+use crate::camera::frustum::Frustum;
 use crate::event::window_event::WindowEvent;
 use crate::resource::effect::ShaderUniform;
 use crate::window::canvas::Canvas;
@@ -11,8 +12,18 @@ pub trait Camera {
     fn inverse_transformation(&self) -> Matrix4<f32>;
     fn clip_planes(&self) -> (f32, f32);
 
+    /// The world-space region visible by this camera.
+    fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.transformation())
+    }
+
     fn update(&mut self, canvas: &Canvas);
 
+    /// Moves the camera so that the given sphere fills the view, keeping the view direction.
+    ///
+    /// Does nothing by default, for cameras that cannot be moved this way.
+    fn frame_sphere(&mut self, _center: Point3<f32>, _radius: f32) {}
+
     fn upload(
         &self,
         pass: usize,
@@ -1,5 +1,6 @@
 use crate::camera::camera::Camera;
-use crate::event::window_event::{Action, Key, MouseButton, WindowEvent};
+use crate::event::input_map::{Binding, InputMap};
+use crate::event::window_event::{Key, MouseButton, WindowEvent};
 use crate::resource::effect::ShaderUniform;
 use crate::window::canvas::Canvas;
 use nalgebra::{
@@ -18,12 +19,7 @@ pub struct FirstPerson {
     yaw_step: f32,
     pitch_step: f32,
     move_step: f32,
-    rotate_button: Option<MouseButton>,
-    drag_button: Option<MouseButton>,
-    up_key: Option<Key>,
-    down_key: Option<Key>,
-    left_key: Option<Key>,
-    right_key: Option<Key>,
+    input_map: InputMap,
 
     projection: Perspective3<f32>,
     proj: Matrix4<f32>,
@@ -53,12 +49,7 @@ impl FirstPerson {
             yaw_step: 0.005,
             pitch_step: 0.005,
             move_step: 0.5,
-            rotate_button: Some(MouseButton::Button1),
-            drag_button: Some(MouseButton::Button2),
-            up_key: Some(Key::Up),
-            down_key: Some(Key::Down),
-            left_key: Some(Key::Left),
-            right_key: Some(Key::Right),
+            input_map: FirstPerson::default_input_map(),
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
             proj: nalgebra::zero(),
             view: nalgebra::zero(),
@@ -117,6 +108,14 @@ impl FirstPerson {
         self.update_projviews();
     }
 
+    /// Moves the camera so that the given sphere fills the view, keeping the view direction.
+    pub fn frame_sphere(&mut self, center: Point3<f32>, radius: f32) {
+        let dist = radius / (self.projection.fovy() / 2.0).sin();
+        let dir = self.at() - self.eye;
+
+        self.look_at(center - dir * dist * 1.1, center);
+    }
+
     pub fn at(&self) -> Point3<f32> {
         let view_eye = self.coord_system.rotation_to_y_up * self.eye;
         let ax = view_eye.x + self.yaw.cos() * self.pitch.sin();
@@ -136,59 +135,86 @@ impl FirstPerson {
         }
     }
 
+    /// The bindings used by default: `look` with the left mouse button, `pan` with the right
+    /// mouse button, and `move_forward`, `move_backward`, `move_left`, `move_right` with the
+    /// arrow keys.
+    pub fn default_input_map() -> InputMap {
+        let mut input_map = InputMap::new();
+        input_map.bind("look", Binding::mouse_button(MouseButton::Button1));
+        input_map.bind("pan", Binding::mouse_button(MouseButton::Button2));
+        input_map.bind("move_forward", Binding::key(Key::Up));
+        input_map.bind("move_backward", Binding::key(Key::Down));
+        input_map.bind("move_left", Binding::key(Key::Left));
+        input_map.bind("move_right", Binding::key(Key::Right));
+        input_map
+    }
+
+    /// The bindings of the actions of this camera, see `default_input_map`.
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.input_map = input_map
+    }
+
     pub fn rotate_button(&self) -> Option<MouseButton> {
-        self.rotate_button
+        self.input_map.mouse_button("look")
     }
 
     pub fn rebind_rotate_button(&mut self, new_button: Option<MouseButton>) {
-        self.rotate_button = new_button;
+        self.input_map.rebind_mouse_button("look", new_button)
     }
 
     pub fn drag_button(&self) -> Option<MouseButton> {
-        self.drag_button
+        self.input_map.mouse_button("pan")
     }
 
     pub fn rebind_drag_button(&mut self, new_button: Option<MouseButton>) {
-        self.drag_button = new_button;
+        self.input_map.rebind_mouse_button("pan", new_button)
     }
 
     pub fn up_key(&self) -> Option<Key> {
-        self.up_key
+        self.input_map.key("move_forward")
     }
 
     pub fn down_key(&self) -> Option<Key> {
-        self.down_key
+        self.input_map.key("move_backward")
     }
 
     pub fn left_key(&self) -> Option<Key> {
-        self.left_key
+        self.input_map.key("move_left")
     }
 
     pub fn right_key(&self) -> Option<Key> {
-        self.right_key
+        self.input_map.key("move_right")
     }
 
     pub fn rebind_up_key(&mut self, new_key: Option<Key>) {
-        self.up_key = new_key;
+        self.input_map.rebind_key("move_forward", new_key)
     }
 
     pub fn rebind_down_key(&mut self, new_key: Option<Key>) {
-        self.down_key = new_key;
+        self.input_map.rebind_key("move_backward", new_key)
     }
 
     pub fn rebind_left_key(&mut self, new_key: Option<Key>) {
-        self.left_key = new_key;
+        self.input_map.rebind_key("move_left", new_key)
     }
 
     pub fn rebind_right_key(&mut self, new_key: Option<Key>) {
-        self.right_key = new_key;
+        self.input_map.rebind_key("move_right", new_key)
     }
 
     pub fn unbind_movement_keys(&mut self) {
-        self.up_key = None;
-        self.down_key = None;
-        self.left_key = None;
-        self.right_key = None;
+        self.input_map.unbind("move_forward");
+        self.input_map.unbind("move_backward");
+        self.input_map.unbind("move_left");
+        self.input_map.unbind("move_right");
     }
 
     #[doc(hidden)]
@@ -316,23 +342,21 @@ impl Camera for FirstPerson {
         Isometry3::look_at_rh(&self.eye, &self.at(), &self.coord_system.up_axis)
     }
 
-    fn handle_event(&mut self, canvas: &Canvas, event: &WindowEvent) {
+    fn handle_event(&mut self, _: &Canvas, event: &WindowEvent) {
+        self.input_map.handle_event(event);
+
         match *event {
             WindowEvent::CursorPos(x, y, _) => {
                 let curr_pos = Vector2::new(x as f32, y as f32);
 
-                if let Some(rotate_button) = self.rotate_button {
-                    if canvas.get_mouse_button(rotate_button) == Action::Press {
-                        let dpos = curr_pos - self.last_cursor_pos;
-                        self.handle_left_button_displacement(&dpos)
-                    }
+                if self.input_map.is_pressed("look") {
+                    let dpos = curr_pos - self.last_cursor_pos;
+                    self.handle_left_button_displacement(&dpos)
                 }
 
-                if let Some(drag_button) = self.drag_button {
-                    if canvas.get_mouse_button(drag_button) == Action::Press {
-                        let dpos = curr_pos - self.last_cursor_pos;
-                        self.handle_right_button_displacement(&dpos)
-                    }
+                if self.input_map.is_pressed("pan") {
+                    let dpos = curr_pos - self.last_cursor_pos;
+                    self.handle_right_button_displacement(&dpos)
                 }
 
                 self.last_cursor_pos = curr_pos;
@@ -369,23 +393,20 @@ impl Camera for FirstPerson {
         view.upload(&self.view);
     }
 
-    fn update(&mut self, canvas: &Canvas) {
-        let up = check_optional_key_state(canvas, self.up_key, Action::Press);
-        let down = check_optional_key_state(canvas, self.down_key, Action::Press);
-        let right = check_optional_key_state(canvas, self.right_key, Action::Press);
-        let left = check_optional_key_state(canvas, self.left_key, Action::Press);
+    fn update(&mut self, _: &Canvas) {
+        let up = self.input_map.is_pressed("move_forward");
+        let down = self.input_map.is_pressed("move_backward");
+        let right = self.input_map.is_pressed("move_right");
+        let left = self.input_map.is_pressed("move_left");
         let dir = self.move_dir(up, down, right, left);
 
         let move_amount = dir * self.move_step;
         self.translate_mut(&Translation3::from(move_amount));
+        self.input_map.new_frame();
     }
-}
 
-fn check_optional_key_state(canvas: &Canvas, key: Option<Key>, key_state: Action) -> bool {
-    if let Some(actual_key) = key {
-        canvas.get_key(actual_key) == key_state
-    } else {
-        false
+    fn frame_sphere(&mut self, center: Point3<f32>, radius: f32) {
+        FirstPerson::frame_sphere(self, center, radius)
     }
 }
 
@@ -3,8 +3,11 @@ use std::f32;
 use nalgebra::{self as na, Isometry3, Matrix4, Perspective3, Point2, Point3, Vector2, Vector3};
 
 use crate::camera::camera::Camera;
+use crate::camera::first_person::FirstPerson;
+use crate::camera::frustum::Frustum;
 use crate::context::context::Context;
-use crate::event::window_event::{Action, Key, MouseButton, WindowEvent};
+use crate::event::input_map::InputMap;
+use crate::event::window_event::WindowEvent;
 use crate::resource::effect::ShaderUniform;
 use crate::verify;
 use crate::window::canvas::Canvas;
@@ -20,6 +23,7 @@ pub struct FirstPersonStereo {
     yaw_step: f32,
     pitch_step: f32,
     move_step: f32,
+    input_map: InputMap,
     projection: Perspective3<f32>,
     view_left: Matrix4<f32>,
     view_right: Matrix4<f32>,
@@ -52,6 +56,7 @@ impl FirstPersonStereo {
             yaw_step: 0.005,
             pitch_step: 0.005,
             move_step: 0.5,
+            input_map: FirstPerson::default_input_map(),
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
             proj_view: na::zero(),
             inverse_proj_view: na::zero(),
@@ -172,6 +177,15 @@ impl FirstPersonStereo {
         self.update_restrictions();
         self.update_projviews();
     }
+
+    /// The bindings of the actions of this camera, see `FirstPerson::default_input_map`.
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
 }
 
 impl Camera for FirstPersonStereo {
@@ -183,17 +197,19 @@ impl Camera for FirstPersonStereo {
         Isometry3::look_at_rh(&self.eye, &self.at(), &Vector3::y())
     }
 
-    fn handle_event(&mut self, canvas: &Canvas, event: &WindowEvent) {
+    fn handle_event(&mut self, _: &Canvas, event: &WindowEvent) {
+        self.input_map.handle_event(event);
+
         match *event {
             WindowEvent::CursorPos(x, y, _) => {
                 let curr_pos = Point2::new(x as f32, y as f32);
 
-                if canvas.get_mouse_button(MouseButton::Button1) == Action::Press {
+                if self.input_map.is_pressed("look") {
                     let dpos = curr_pos - self.last_cursor_pos;
                     self.handle_left_button_displacement(&dpos)
                 }
 
-                if canvas.get_mouse_button(MouseButton::Button2) == Action::Press {
+                if self.input_map.is_pressed("pan") {
                     let dpos = curr_pos - self.last_cursor_pos;
                     self.handle_right_button_displacement(&dpos)
                 }
@@ -217,31 +233,39 @@ impl Camera for FirstPersonStereo {
         self.proj_view
     }
 
+    /// The region visible by either eye, as each eye looks at `at` from its own location.
+    fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.proj * self.view_left))
+            .union(&Frustum::from_matrix(&(self.proj * self.view_right)))
+    }
+
     fn inverse_transformation(&self) -> Matrix4<f32> {
         self.inverse_proj_view
     }
 
-    fn update(&mut self, canvas: &Canvas) {
+    fn update(&mut self, _: &Canvas) {
         let t = self.view_transform();
         let front = t * Vector3::z();
         let right = t * Vector3::x();
 
-        if canvas.get_key(Key::Up) == Action::Press {
+        if self.input_map.is_pressed("move_forward") {
             self.eye += front * self.move_step
         }
 
-        if canvas.get_key(Key::Down) == Action::Press {
+        if self.input_map.is_pressed("move_backward") {
             self.eye += front * (-self.move_step)
         }
 
-        if canvas.get_key(Key::Right) == Action::Press {
+        if self.input_map.is_pressed("move_right") {
             self.eye += right * (-self.move_step)
         }
 
-        if canvas.get_key(Key::Left) == Action::Press {
+        if self.input_map.is_pressed("move_left") {
             self.eye += right * self.move_step
         }
 
+        self.input_map.new_frame();
+
         self.update_eyes_location();
         self.update_restrictions();
         self.update_projviews();
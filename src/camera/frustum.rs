@@ -0,0 +1,74 @@
+//! View frustum used to discard objects that cannot be seen by a camera.
+
+use nalgebra::{Matrix4, Vector4};
+use ncollide3d::bounding_volume::{BoundingSphere, AABB};
+
+/// The six clipping planes of a camera, in world space, or the union of several such regions
+/// for cameras rendering several views.
+#[derive(Clone, Debug)]
+pub struct Frustum {
+    // The six planes of each region. Each plane is `(n, d)` with `n` pointing inside the region
+    // and `|n| = 1`.
+    regions: Vec<[Vector4<f32>; 6]>,
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from a projection-view matrix, e.g., `Camera::transformation`.
+    pub fn from_matrix(m: &Matrix4<f32>) -> Frustum {
+        let row = |i: usize| m.row(i).transpose();
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let mut planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2];
+
+        for plane in planes.iter_mut() {
+            let norm = plane.xyz().norm();
+
+            if norm != 0.0 {
+                *plane /= norm;
+            }
+        }
+
+        Frustum {
+            regions: vec![planes],
+        }
+    }
+
+    /// The region visible from either `self` or `other`, e.g., by the two eyes of a stereo
+    /// camera.
+    pub fn union(mut self, other: &Frustum) -> Frustum {
+        self.regions.extend_from_slice(&other.regions);
+        self
+    }
+
+    /// Returns `false` if the sphere is guaranteed to be outside of the frustum.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere<f32>) -> bool {
+        let center = sphere.center().to_homogeneous();
+
+        self.regions.iter().any(|planes| {
+            planes
+                .iter()
+                .all(|plane| plane.dot(&center) >= -sphere.radius())
+        })
+    }
+
+    /// Returns `false` if the box is guaranteed to be outside of the frustum.
+    pub fn intersects_aabb(&self, aabb: &AABB<f32>) -> bool {
+        self.regions.iter().any(|planes| {
+            planes
+                .iter()
+                .all(|plane| plane.dot(&furthest_corner(aabb, plane)) >= 0.0)
+        })
+    }
+}
+
+// The corner of the box the furthest along the plane normal.
+fn furthest_corner(aabb: &AABB<f32>, plane: &Vector4<f32>) -> Vector4<f32> {
+    let coord = |i: usize| {
+        if plane[i] >= 0.0 {
+            aabb.maxs[i]
+        } else {
+            aabb.mins[i]
+        }
+    };
+
+    Vector4::new(coord(0), coord(1), coord(2), 1.0)
+}
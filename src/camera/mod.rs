@@ -2,4 +2,5 @@ pub mod arc_ball;
 pub mod camera;
 pub mod first_person;
 pub mod first_person_stereo;
-pub mod fixed_view;
\ No newline at end of file
+pub mod fixed_view;
+pub mod frustum;
\ No newline at end of file
@@ -0,0 +1,170 @@
+use crate::cli::{self, Options};
+use crate::loader::glb::{self, GlbMesh, GlbNode};
+use crate::loader::model::{self, ModelPart};
+use crate::loader::mtl::MtlMaterial;
+use crate::loader::obj::{self, ObjGroup};
+use crate::loader::{ply, stl};
+use crate::resource::mesh::Primitive;
+use nalgebra::{Point3, Vector3};
+use std::path::Path;
+
+/// `starman convert <input> <output>`: converts a model, choosing the output format from the
+/// extension of `output`.
+///
+/// The geometry of every object is written in world space. The files are only read and written,
+/// so this needs no window or graphics context.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &[])?;
+
+    if options.files.len() != 2 {
+        return Err(format!(
+            "Expected an input and an output file.\n\n{}",
+            cli::USAGE
+        ));
+    }
+
+    let (input, output) = (&options.files[0], &options.files[1]);
+    let data =
+        model::read_model(input, &|_| ()).map_err(|e| format!("{}: {}", input.display(), e))?;
+
+    write_model(&data.parts(input), input, output)
+        .map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("Converted {} to {}", input.display(), output.display());
+
+    Ok(())
+}
+
+fn write_model(parts: &[ModelPart], input: &Path, path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "stl" => {
+            let (vertices, _, indices) = world_geometry(parts);
+            let triangles: Vec<[Point3<f32>; 3]> = indices
+                .iter()
+                .map(|t| {
+                    [
+                        vertices[t[0] as usize],
+                        vertices[t[1] as usize],
+                        vertices[t[2] as usize],
+                    ]
+                })
+                .collect();
+            stl::write_stl(path, &triangles).map_err(|e| e.to_string())
+        }
+        "ply" => {
+            let (vertices, normals, indices) = world_geometry(parts);
+            ply::write_ply(path, &vertices, Some(&normals), &indices).map_err(|e| e.to_string())
+        }
+        "obj" => write_obj(parts, path).map_err(|e| e.to_string()),
+        "glb" => write_glb(parts, input, path),
+        _ => Err("Unsupported output format, expected obj, glb, stl or ply".to_string()),
+    }
+}
+
+// Writes each part as a group with its own material, like `SceneNode::export_obj`.
+fn write_obj(parts: &[ModelPart], path: &Path) -> std::io::Result<()> {
+    let out_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut groups = Vec::new();
+
+    for (i, part) in parts.iter().enumerate() {
+        let mut material = match part.material {
+            Some(material) => material.clone(),
+            None => MtlMaterial::new_default(String::new()),
+        };
+        material.name = format!("material_{}", i);
+        material.diffuse = part.color().coords;
+        material.ambiant_texture = None;
+        material.diffuse_texture = match part.texture {
+            Some(ref texture) => Some(obj::copy_texture(texture, out_dir)?),
+            None => None,
+        };
+
+        let mut group = ObjGroup {
+            name: part.name.clone(),
+            coords: part.vertices(),
+            normals: part.normals(),
+            uvs: part.uvs(),
+            faces: part.faces(),
+            primitive: part.mesh.primitive(),
+            material: Some(material),
+        };
+
+        if group.normals.len() != group.coords.len() {
+            group.normals.clear();
+        }
+
+        if group.uvs.len() != group.coords.len() {
+            group.uvs.clear();
+        }
+
+        groups.push(group);
+    }
+
+    obj::write_file(path, &groups)
+}
+
+// Writes each part as a child of a root node named after the input file.
+fn write_glb(parts: &[ModelPart], input: &Path, path: &Path) -> Result<(), String> {
+    let node = |name: Option<String>, mesh: Option<GlbMesh>, children: Vec<GlbNode>| GlbNode {
+        name,
+        translation: [0.0; 3],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0; 3],
+        mesh,
+        children,
+    };
+    let children = parts
+        .iter()
+        .map(|part| {
+            let color = part.color();
+            let mesh = GlbMesh {
+                positions: part.vertices(),
+                normals: part.normals(),
+                uvs: part.uvs(),
+                indices: part.faces().iter().flat_map(|f| [f.x, f.y, f.z]).collect(),
+                primitive: part.mesh.primitive(),
+                base_color: [color.x, color.y, color.z, 1.0],
+                texture: part.texture.clone(),
+            };
+
+            node(Some(part.name.clone()), Some(mesh), Vec::new())
+        })
+        .collect();
+    let name = input.file_stem().map(|s| s.to_string_lossy().into_owned());
+
+    glb::write_glb(path, &node(name, None, children))
+}
+
+type Geometry = (Vec<Point3<f32>>, Vec<Vector3<f32>>, Vec<[u32; 3]>);
+
+// Merges the triangles of every part. Lines and points are left out, since STL and PLY files
+// only hold faces here.
+fn world_geometry(parts: &[ModelPart]) -> Geometry {
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+
+    for part in parts {
+        if part.mesh.primitive() != Primitive::Triangles {
+            continue;
+        }
+
+        let offset = vertices.len() as u32;
+
+        vertices.extend(part.vertices());
+        normals.extend(part.normals());
+        indices.extend(
+            part.faces()
+                .iter()
+                .map(|f| [offset + f.x, offset + f.y, offset + f.z]),
+        );
+
+        normals.resize(vertices.len(), Vector3::zeros());
+    }
+
+    (vertices, normals, indices)
+}
@@ -0,0 +1,119 @@
+use crate::cli::{self, Options};
+use crate::loader::model::{self, ModelData};
+use crate::resource::mesh::Primitive;
+use nalgebra::Point3;
+use std::collections::HashSet;
+
+/// `starman info <file>`: prints statistics about the nodes, meshes and materials of a file.
+///
+/// The file is only read, so this needs no window or graphics context.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &[])?;
+
+    if options.files.len() != 1 {
+        return Err(format!("Expected one file.\n\n{}", cli::USAGE));
+    }
+
+    let file = &options.files[0];
+    let data =
+        model::read_model(file, &|_| ()).map_err(|e| format!("{}: {}", file.display(), e))?;
+    let parts = data.parts(file);
+
+    let mut objects = Vec::new();
+    let mut materials = HashSet::new();
+    let mut textures = HashSet::new();
+    let mut bounds: Option<(Point3<f32>, Point3<f32>)> = None;
+
+    for part in &parts {
+        let vertices = part.vertices();
+
+        for v in &vertices {
+            bounds = Some(match bounds {
+                Some((mins, maxs)) => (mins.inf(v), maxs.sup(v)),
+                None => (*v, *v),
+            });
+        }
+
+        if let Some(material) = part.material {
+            let _ = materials.insert(&material.name);
+        }
+
+        if let Some(ref texture) = part.texture {
+            let _ = textures.insert(texture.clone());
+        }
+
+        objects.push((
+            &part.name,
+            vertices.len(),
+            part.faces().len(),
+            part.mesh.primitive(),
+            part.color(),
+        ));
+    }
+
+    println!("{}", file.display());
+
+    // Only glTF files have a node hierarchy.
+    if let ModelData::Gltf(ref nodes) = data {
+        println!(
+            "  nodes:     {}",
+            nodes.iter().map(|n| n.num_nodes()).sum::<usize>()
+        );
+    }
+
+    println!("  objects:   {}", objects.len());
+    println!(
+        "  vertices:  {}",
+        objects.iter().map(|o| o.1).sum::<usize>()
+    );
+
+    for (primitive, name) in ELEMENT_NAMES {
+        let objects = objects.iter().filter(|o| o.3 == primitive);
+
+        if primitive == Primitive::Triangles || objects.clone().next().is_some() {
+            println!(
+                "  {:<10} {}",
+                format!("{}:", name),
+                objects.map(|o| o.2).sum::<usize>()
+            );
+        }
+    }
+
+    println!("  materials: {}", materials.len());
+    println!("  textures:  {}", textures.len());
+
+    if let Some((mins, maxs)) = bounds {
+        println!(
+            "  bounds:    [{}, {}, {}] .. [{}, {}, {}]",
+            mins.x, mins.y, mins.z, maxs.x, maxs.y, maxs.z
+        );
+    }
+
+    for (name, num_vertices, num_elements, primitive, color) in objects {
+        println!(
+            "  - {:?}: {} vertices, {} {}, color [{}, {}, {}]",
+            name,
+            num_vertices,
+            num_elements,
+            element_name(primitive),
+            color.x,
+            color.y,
+            color.z
+        );
+    }
+
+    Ok(())
+}
+
+const ELEMENT_NAMES: [(Primitive, &str); 3] = [
+    (Primitive::Triangles, "triangles"),
+    (Primitive::Lines, "segments"),
+    (Primitive::Points, "points"),
+];
+
+fn element_name(primitive: Primitive) -> &'static str {
+    ELEMENT_NAMES
+        .iter()
+        .find(|e| e.0 == primitive)
+        .map_or("", |e| e.1)
+}
@@ -0,0 +1,206 @@
+//! The `starman` command-line tool.
+
+pub mod convert;
+pub mod info;
+pub mod render;
+pub mod view;
+
+use crate::camera::arc_ball::ArcBall;
+use crate::camera::camera::Camera;
+use crate::camera::first_person::FirstPerson;
+use crate::light::Light;
+use crate::window::window::Window;
+use nalgebra::{Point3, Vector3};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage:
+    starman view <files...> [--camera arcball|first-person] [--eye x,y,z] [--at x,y,z]
+                            [--background r,g,b] [--light camera|x,y,z] [--wireframe]
+    starman render <file> [--out shot.png] [--size 1920x1080] [--camera ...] [--eye x,y,z]
+                          [--at x,y,z] [--background r,g,b] [--light camera|x,y,z] [--wireframe]
+    starman info <file>
+    starman convert <input> <output>
+
+Supported input formats: obj, glb, gltf, stl, ply.
+Supported output formats: obj, glb, stl, ply.";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraKind {
+    ArcBall,
+    FirstPerson,
+}
+
+/// The arguments of a subcommand.
+pub struct Options {
+    pub files: Vec<PathBuf>,
+    pub camera: CameraKind,
+    pub eye: Option<Point3<f32>>,
+    pub at: Option<Point3<f32>>,
+    pub background: Option<Vector3<f32>>,
+    pub light: Option<Light>,
+    pub wireframe: bool,
+    pub out: Option<PathBuf>,
+    pub size: Option<(u32, u32)>,
+}
+
+impl Options {
+    /// Parses the arguments following the subcommand name, rejecting the flags not in `allowed`.
+    pub fn parse(args: &[String], allowed: &[&str]) -> Result<Options, String> {
+        let mut options = Options {
+            files: Vec::new(),
+            camera: CameraKind::ArcBall,
+            eye: None,
+            at: None,
+            background: None,
+            light: None,
+            wireframe: false,
+            out: None,
+            size: None,
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                options.files.push(PathBuf::from(arg));
+                continue;
+            }
+
+            if !allowed.contains(&arg.as_str()) {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            if arg == "--wireframe" {
+                options.wireframe = true;
+                continue;
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", arg))?;
+
+            match arg.as_str() {
+                "--camera" => {
+                    options.camera = match value.as_str() {
+                        "arcball" => CameraKind::ArcBall,
+                        "first-person" => CameraKind::FirstPerson,
+                        _ => return Err(format!("Unknown camera: {}", value)),
+                    }
+                }
+                "--eye" => options.eye = Some(Point3::from(parse_vector(value)?)),
+                "--at" => options.at = Some(Point3::from(parse_vector(value)?)),
+                "--background" => options.background = Some(parse_vector(value)?),
+                "--light" => {
+                    options.light = Some(if value == "camera" {
+                        Light::StickToCamera
+                    } else {
+                        Light::Absolute(Point3::from(parse_vector(value)?))
+                    })
+                }
+                "--out" => options.out = Some(PathBuf::from(value)),
+                "--size" => options.size = Some(parse_size(value)?),
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Runs the command described by the program arguments, without the program name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    match command {
+        "view" => view::run(&args[1..]),
+        "render" => render::run(&args[1..]),
+        "info" => info::run(&args[1..]),
+        "convert" => convert::run(&args[1..]),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+/// Loads the files into the window scene and applies the display options.
+pub fn setup_scene(window: &mut Window, options: &Options) -> Result<(), String> {
+    for file in options.files.iter() {
+        window
+            .scene_mut()
+            .add_model(file)
+            .map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+
+    apply_display_options(window, options);
+    Ok(())
+}
+
+/// Applies the wireframe, background and light options to the window and its current scene.
+pub fn apply_display_options(window: &mut Window, options: &Options) {
+    if options.wireframe {
+        window.scene_mut().set_surface_rendering_activation(false);
+        window.scene_mut().set_lines_width(1.0);
+    }
+
+    if let Some(background) = options.background {
+        window.set_background_color(background.x, background.y, background.z);
+    }
+
+    window.set_light(options.light.clone().unwrap_or(Light::StickToCamera));
+}
+
+/// Creates the camera requested by the options, framing the whole scene by default.
+pub fn make_camera(window: &Window, options: &Options) -> Box<dyn Camera> {
+    let mut arc_ball = ArcBall::new(Point3::new(0.0, 0.0, -1.0), Point3::origin());
+
+    if let Some(sphere) = window.scene().world_bounding_sphere() {
+        arc_ball.frame_sphere(*sphere.center(), sphere.radius());
+    }
+
+    let eye = options.eye.unwrap_or_else(|| arc_ball.eye());
+    let at = options.at.unwrap_or_else(|| arc_ball.at());
+
+    match options.camera {
+        CameraKind::ArcBall => {
+            arc_ball.look_at(eye, at);
+            Box::new(arc_ball)
+        }
+        CameraKind::FirstPerson => Box::new(FirstPerson::new(eye, at)),
+    }
+}
+
+fn parse_vector(s: &str) -> Result<Vector3<f32>, String> {
+    let components: Vec<f32> = s
+        .split(',')
+        .map(|c| c.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid vector {:?}: {}", s, e))?;
+
+    if components.len() != 3 {
+        return Err(format!("Invalid vector {:?}: expected 3 components", s));
+    }
+
+    Ok(Vector3::new(components[0], components[1], components[2]))
+}
+
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Invalid size {:?}: expected WIDTHxHEIGHT", s);
+    let (w, h) = s.split_once('x').ok_or_else(invalid)?;
+    let w = w.parse::<u32>().map_err(|_| invalid())?;
+    let h = h.parse::<u32>().map_err(|_| invalid())?;
+
+    if w == 0 || h == 0 {
+        return Err(invalid());
+    }
+
+    Ok((w, h))
+}
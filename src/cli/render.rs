@@ -0,0 +1,50 @@
+use crate::cli::{self, Options};
+use crate::window::window::Window;
+use std::path::Path;
+
+const FLAGS: &[&str] = &[
+    "--out",
+    "--size",
+    "--camera",
+    "--eye",
+    "--at",
+    "--background",
+    "--light",
+    "--wireframe",
+];
+
+/// `starman render <file>`: renders a still image without showing a window.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, FLAGS)?;
+
+    if options.files.len() != 1 {
+        return Err(format!("Expected one file to render.\n\n{}", cli::USAGE));
+    }
+
+    let (width, height) = options.size.unwrap_or((1920, 1080));
+    let out = options
+        .out
+        .clone()
+        .unwrap_or_else(|| Path::new("shot.png").to_path_buf());
+
+    // The side panels are not part of the image.
+    let mut window =
+        Window::new_hidden_with_size("Starman", width + 2 * Window::sidebar_width(), height);
+
+    cli::setup_scene(&mut window, &options)?;
+    let mut camera = cli::make_camera(&window, &options);
+
+    // The first frame sets up the viewport and camera projection.
+    for _ in 0..2 {
+        let _ = window.render_with_camera(camera.as_mut());
+    }
+
+    window
+        .snap_scene_image()
+        .save(&out)
+        .map_err(|e| format!("{}: {}", out.display(), e))?;
+
+    println!("Saved {}", out.display());
+
+    Ok(())
+}
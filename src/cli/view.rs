@@ -0,0 +1,68 @@
+use crate::cli::{self, Options};
+use crate::event::window_event::WindowEvent;
+use crate::window::model_loader::ModelHandle;
+use crate::window::window::Window;
+
+const FLAGS: &[&str] = &[
+    "--camera",
+    "--eye",
+    "--at",
+    "--background",
+    "--light",
+    "--wireframe",
+];
+
+/// `starman view <files...>`: opens the files in an interactive window.
+///
+/// The files are loaded in the background. More files can be dropped on the window while it is
+/// open: the window frames them, and the display options are applied to them once loaded.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, FLAGS)?;
+
+    if options.files.is_empty() {
+        return Err(format!("Nothing to view.\n\n{}", cli::USAGE));
+    }
+
+    let names: Vec<String> = options
+        .files
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect();
+    let mut window = Window::new(&format!("Starman - {}", names.join(", ")));
+    window.set_load_dropped_files(true);
+
+    // Files are loaded in the background so that the window opens right away. The camera frames
+    // them once they are all loaded.
+    let models: Vec<ModelHandle> = options
+        .files
+        .iter()
+        .map(|f| window.add_model_async(f))
+        .collect();
+    let mut loading = true;
+    let mut dropped = false;
+
+    cli::apply_display_options(&mut window, &options);
+    let mut camera = cli::make_camera(&window, &options);
+
+    while window.render_with_camera(camera.as_mut()) {
+        if loading && models.iter().all(|m| m.is_finished()) {
+            loading = false;
+            cli::apply_display_options(&mut window, &options);
+            camera = cli::make_camera(&window, &options);
+        }
+
+        // Dropped files are handled by the window at the next frame, and loaded after it.
+        if dropped && window.num_loading_models() == 0 {
+            dropped = false;
+            cli::apply_display_options(&mut window, &options);
+        }
+
+        for event in window.events().iter() {
+            if let WindowEvent::FileDropped(_) = event.value {
+                dropped = true;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -19,6 +19,16 @@ pub struct Renderbuffer(<ContextImpl as AbstractContext>::Renderbuffer);
 pub struct Texture(<ContextImpl as AbstractContext>::Texture);
 pub struct VertexArray(<ContextImpl as AbstractContext>::VertexArray);
 
+/// A uniform or attribute used by a linked program.
+#[derive(Clone, Debug)]
+pub struct ActiveVariable {
+    pub name: String,
+    /// The GLSL type, like `Context::FLOAT_VEC3` or `Context::SAMPLER_2D`.
+    pub type_: GLenum,
+    /// The number of elements, greater than 1 for arrays.
+    pub size: i32,
+}
+
 impl Drop for Buffer {
     fn drop(&mut self) {
         unsafe {
@@ -48,9 +58,18 @@ impl Context {
     pub const STREAM_DRAW: u32 = ContextImpl::STREAM_DRAW;
     pub const ARRAY_BUFFER: u32 = ContextImpl::ARRAY_BUFFER;
     pub const ELEMENT_ARRAY_BUFFER: u32 = ContextImpl::ELEMENT_ARRAY_BUFFER;
+    pub const PIXEL_UNPACK_BUFFER: u32 = ContextImpl::PIXEL_UNPACK_BUFFER;
     pub const VERTEX_SHADER: u32 = ContextImpl::VERTEX_SHADER;
     pub const FRAGMENT_SHADER: u32 = ContextImpl::FRAGMENT_SHADER;
     pub const COMPILE_STATUS: u32 = ContextImpl::COMPILE_STATUS;
+    pub const LINK_STATUS: u32 = ContextImpl::LINK_STATUS;
+    pub const FLOAT_VEC2: u32 = ContextImpl::FLOAT_VEC2;
+    pub const FLOAT_VEC3: u32 = ContextImpl::FLOAT_VEC3;
+    pub const FLOAT_VEC4: u32 = ContextImpl::FLOAT_VEC4;
+    pub const FLOAT_MAT2: u32 = ContextImpl::FLOAT_MAT2;
+    pub const FLOAT_MAT3: u32 = ContextImpl::FLOAT_MAT3;
+    pub const FLOAT_MAT4: u32 = ContextImpl::FLOAT_MAT4;
+    pub const SAMPLER_2D: u32 = ContextImpl::SAMPLER_2D;
     pub const FRAMEBUFFER: u32 = ContextImpl::FRAMEBUFFER;
     pub const RENDERBUFFER: u32 = ContextImpl::RENDERBUFFER;
     pub const DEPTH_ATTACHMENT: u32 = ContextImpl::DEPTH_ATTACHMENT;
@@ -73,6 +92,13 @@ impl Context {
     pub const REPEAT: u32 = ContextImpl::REPEAT;
     pub const MIRRORED_REPEAT: u32 = ContextImpl::MIRRORED_REPEAT;
     pub const LINEAR_MIPMAP_LINEAR: u32 = ContextImpl::LINEAR_MIPMAP_LINEAR;
+    pub const NEAREST_MIPMAP_NEAREST: u32 = ContextImpl::NEAREST_MIPMAP_NEAREST;
+    pub const LINEAR_MIPMAP_NEAREST: u32 = ContextImpl::LINEAR_MIPMAP_NEAREST;
+    pub const NEAREST_MIPMAP_LINEAR: u32 = ContextImpl::NEAREST_MIPMAP_LINEAR;
+    pub const CLAMP_TO_BORDER: u32 = ContextImpl::CLAMP_TO_BORDER;
+    pub const TEXTURE_BORDER_COLOR: u32 = ContextImpl::TEXTURE_BORDER_COLOR;
+    pub const TEXTURE_MAX_ANISOTROPY: u32 = ContextImpl::TEXTURE_MAX_ANISOTROPY;
+    pub const MAX_TEXTURE_MAX_ANISOTROPY: u32 = ContextImpl::MAX_TEXTURE_MAX_ANISOTROPY;
     pub const TRIANGLES: u32 = ContextImpl::TRIANGLES;
     pub const CULL_FACE: u32 = ContextImpl::CULL_FACE;
     pub const FRONT_AND_BACK: u32 = ContextImpl::FRONT_AND_BACK;
@@ -98,6 +124,53 @@ impl Context {
     pub const UNPACK_ALIGNMENT: u32 = ContextImpl::UNPACK_ALIGNMENT;
     pub const ALPHA: u32 = ContextImpl::ALPHA;
     pub const RED: u32 = ContextImpl::RED;
+    pub const RG: u32 = ContextImpl::RG;
+    pub const LUMINANCE: u32 = ContextImpl::LUMINANCE;
+    pub const LUMINANCE_ALPHA: u32 = ContextImpl::LUMINANCE_ALPHA;
+    pub const GREEN: u32 = ContextImpl::GREEN;
+    pub const BLUE: u32 = ContextImpl::BLUE;
+    pub const R8: u32 = ContextImpl::R8;
+    pub const RG8: u32 = ContextImpl::RG8;
+    pub const R16: u32 = ContextImpl::R16;
+    pub const RG16: u32 = ContextImpl::RG16;
+    pub const RGB16: u32 = ContextImpl::RGB16;
+    pub const RGBA16: u32 = ContextImpl::RGBA16;
+    pub const RGB32F: u32 = ContextImpl::RGB32F;
+    pub const RGBA32F: u32 = ContextImpl::RGBA32F;
+    pub const R32F: u32 = ContextImpl::R32F;
+    pub const SRGB8: u32 = ContextImpl::SRGB8;
+    pub const SRGB8_ALPHA8: u32 = ContextImpl::SRGB8_ALPHA8;
+    pub const TEXTURE_SWIZZLE_R: u32 = ContextImpl::TEXTURE_SWIZZLE_R;
+    pub const TEXTURE_SWIZZLE_G: u32 = ContextImpl::TEXTURE_SWIZZLE_G;
+    pub const TEXTURE_SWIZZLE_B: u32 = ContextImpl::TEXTURE_SWIZZLE_B;
+    pub const TEXTURE_SWIZZLE_A: u32 = ContextImpl::TEXTURE_SWIZZLE_A;
+    pub const TEXTURE_MAX_LEVEL: u32 = ContextImpl::TEXTURE_MAX_LEVEL;
+    pub const COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = ContextImpl::COMPRESSED_RGB_S3TC_DXT1_EXT;
+    pub const COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = ContextImpl::COMPRESSED_RGBA_S3TC_DXT1_EXT;
+    pub const COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = ContextImpl::COMPRESSED_RGBA_S3TC_DXT3_EXT;
+    pub const COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = ContextImpl::COMPRESSED_RGBA_S3TC_DXT5_EXT;
+    pub const COMPRESSED_SRGB_S3TC_DXT1_EXT: u32 = ContextImpl::COMPRESSED_SRGB_S3TC_DXT1_EXT;
+    pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 =
+        ContextImpl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT;
+    pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 =
+        ContextImpl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT;
+    pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 =
+        ContextImpl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT;
+    pub const COMPRESSED_RED_RGTC1: u32 = ContextImpl::COMPRESSED_RED_RGTC1;
+    pub const COMPRESSED_RG_RGTC2: u32 = ContextImpl::COMPRESSED_RG_RGTC2;
+    pub const COMPRESSED_RGBA_BPTC_UNORM: u32 = ContextImpl::COMPRESSED_RGBA_BPTC_UNORM;
+    pub const COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32 = ContextImpl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM;
+    pub const COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: u32 =
+        ContextImpl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT;
+    pub const COMPRESSED_RGB_BPTC_SIGNED_FLOAT: u32 = ContextImpl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT;
+    pub const COMPRESSED_RGB8_ETC2: u32 = ContextImpl::COMPRESSED_RGB8_ETC2;
+    pub const COMPRESSED_SRGB8_ETC2: u32 = ContextImpl::COMPRESSED_SRGB8_ETC2;
+    pub const COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 =
+        ContextImpl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2;
+    pub const COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 =
+        ContextImpl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2;
+    pub const COMPRESSED_RGBA8_ETC2_EAC: u32 = ContextImpl::COMPRESSED_RGBA8_ETC2_EAC;
+    pub const COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: u32 = ContextImpl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC;
 
     pub fn init(get_ctxt: impl Fn() -> glow::Context) {
         unsafe {
@@ -122,6 +195,10 @@ impl Context {
         self.ctxt.get_error()
     }
 
+    pub fn get_parameter_f32(&self, pname: GLenum) -> f32 {
+        self.ctxt.get_parameter_f32(pname)
+    }
+
     pub fn uniform_matrix2fv(
         &self,
         location: Option<&UniformLocation>,
@@ -271,6 +348,34 @@ impl Context {
         self.ctxt.get_shader_info_log(&shader.0)
     }
 
+    pub fn get_program_parameter_int(&self, program: &Program, pname: GLenum) -> Option<i32> {
+        self.ctxt.get_program_parameter_int(&program.0, pname)
+    }
+
+    pub fn get_program_info_log(&self, program: &Program) -> Option<String> {
+        self.ctxt.get_program_info_log(&program.0)
+    }
+
+    pub fn bind_attrib_location(&self, program: &Program, index: u32, name: &str) {
+        self.ctxt.bind_attrib_location(&program.0, index, name)
+    }
+
+    pub fn get_active_uniforms(&self, program: &Program) -> u32 {
+        self.ctxt.get_active_uniforms(&program.0)
+    }
+
+    pub fn get_active_uniform(&self, program: &Program, index: u32) -> Option<ActiveVariable> {
+        self.ctxt.get_active_uniform(&program.0, index)
+    }
+
+    pub fn get_active_attributes(&self, program: &Program) -> u32 {
+        self.ctxt.get_active_attributes(&program.0)
+    }
+
+    pub fn get_active_attribute(&self, program: &Program, index: u32) -> Option<ActiveVariable> {
+        self.ctxt.get_active_attribute(&program.0, index)
+    }
+
     pub fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -396,6 +501,54 @@ impl Context {
         )
     }
 
+    /// Like `tex_image2d`, with pixels of the given type instead of `UNSIGNED_BYTE`.
+    pub fn tex_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: Option<&[u8]>,
+    ) {
+        self.ctxt.tex_image2d_with_type(
+            target,
+            level,
+            internalformat,
+            width,
+            height,
+            border,
+            format,
+            ty,
+            pixels,
+        )
+    }
+
+    /// Uploads one level of a block-compressed image. `pixels` holds the compressed blocks.
+    pub fn compressed_tex_image2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: GLenum,
+        width: i32,
+        height: i32,
+        border: i32,
+        pixels: &[u8],
+    ) {
+        self.ctxt.compressed_tex_image2d(
+            target,
+            level,
+            internalformat,
+            width,
+            height,
+            border,
+            pixels,
+        )
+    }
+
     pub fn tex_image2di(
         &self,
         target: GLenum,
@@ -435,10 +588,55 @@ impl Context {
         )
     }
 
+    /// Like `tex_sub_image2d`, with pixels of the given type instead of `UNSIGNED_BYTE`.
+    pub fn tex_sub_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: &[u8],
+    ) {
+        self.ctxt.tex_sub_image2d_with_type(
+            target, level, xoffset, yoffset, width, height, format, ty, pixels,
+        )
+    }
+
+    /// Like `tex_sub_image2d_with_type`, reading the pixels at `offset` in the bound
+    /// `PIXEL_UNPACK_BUFFER`.
+    pub fn tex_sub_image2d_from_buffer(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        offset: usize,
+    ) {
+        self.ctxt.tex_sub_image2d_from_buffer(
+            target, level, xoffset, yoffset, width, height, format, ty, offset,
+        )
+    }
+
     pub fn tex_parameteri(&self, target: GLenum, pname: GLenum, param: i32) {
         self.ctxt.tex_parameteri(target, pname, param)
     }
 
+    pub fn tex_parameterf(&self, target: GLenum, pname: GLenum, param: f32) {
+        self.ctxt.tex_parameterf(target, pname, param)
+    }
+
+    pub fn tex_parameterfv(&self, target: GLenum, pname: GLenum, params: &[f32]) {
+        self.ctxt.tex_parameterfv(target, pname, params)
+    }
+
     pub fn is_texture(&self, texture: Option<&Texture>) -> bool {
         self.ctxt.is_texture(texture.map(|e| &e.0))
     }
@@ -519,6 +717,21 @@ impl Context {
         self.ctxt.read_pixels(x, y, width, height, format, pixels)
     }
 
+    /// The major and minor version of the OpenGL or OpenGL ES API of this context.
+    pub fn version(&self) -> (u32, u32) {
+        self.ctxt.version()
+    }
+
+    /// Whether this is an OpenGL ES or WebGL context.
+    pub fn is_embedded(&self) -> bool {
+        self.ctxt.is_embedded()
+    }
+
+    /// Whether the context exposes the given extension, e.g. `GL_EXT_texture_compression_s3tc`.
+    pub fn supports_extension(&self, name: &str) -> bool {
+        self.ctxt.supports_extension(name)
+    }
+
     pub fn pixel_storei(&self, pname: GLenum, param: i32) {
         self.ctxt.pixel_storei(pname, param)
     }
@@ -545,9 +758,18 @@ pub(crate) trait AbstractContextConst {
     const STREAM_DRAW: u32;
     const ARRAY_BUFFER: u32;
     const ELEMENT_ARRAY_BUFFER: u32;
+    const PIXEL_UNPACK_BUFFER: u32;
     const VERTEX_SHADER: u32;
     const FRAGMENT_SHADER: u32;
     const COMPILE_STATUS: u32;
+    const LINK_STATUS: u32;
+    const FLOAT_VEC2: u32;
+    const FLOAT_VEC3: u32;
+    const FLOAT_VEC4: u32;
+    const FLOAT_MAT2: u32;
+    const FLOAT_MAT3: u32;
+    const FLOAT_MAT4: u32;
+    const SAMPLER_2D: u32;
     const FRAMEBUFFER: u32;
     const RENDERBUFFER: u32;
     const DEPTH_ATTACHMENT: u32;
@@ -570,6 +792,13 @@ pub(crate) trait AbstractContextConst {
     const REPEAT: u32;
     const MIRRORED_REPEAT: u32;
     const LINEAR_MIPMAP_LINEAR: u32;
+    const NEAREST_MIPMAP_NEAREST: u32;
+    const LINEAR_MIPMAP_NEAREST: u32;
+    const NEAREST_MIPMAP_LINEAR: u32;
+    const CLAMP_TO_BORDER: u32;
+    const TEXTURE_BORDER_COLOR: u32;
+    const TEXTURE_MAX_ANISOTROPY: u32;
+    const MAX_TEXTURE_MAX_ANISOTROPY: u32;
     const TRIANGLES: u32;
     const CULL_FACE: u32;
     const FRONT_AND_BACK: u32;
@@ -595,6 +824,47 @@ pub(crate) trait AbstractContextConst {
     const UNPACK_ALIGNMENT: u32;
     const ALPHA: u32;
     const RED: u32;
+    const RG: u32;
+    const LUMINANCE: u32;
+    const LUMINANCE_ALPHA: u32;
+    const GREEN: u32;
+    const BLUE: u32;
+    const R8: u32;
+    const RG8: u32;
+    const R16: u32;
+    const RG16: u32;
+    const RGB16: u32;
+    const RGBA16: u32;
+    const RGB32F: u32;
+    const RGBA32F: u32;
+    const R32F: u32;
+    const SRGB8: u32;
+    const SRGB8_ALPHA8: u32;
+    const TEXTURE_SWIZZLE_R: u32;
+    const TEXTURE_SWIZZLE_G: u32;
+    const TEXTURE_SWIZZLE_B: u32;
+    const TEXTURE_SWIZZLE_A: u32;
+    const TEXTURE_MAX_LEVEL: u32;
+    const COMPRESSED_RGB_S3TC_DXT1_EXT: u32;
+    const COMPRESSED_RGBA_S3TC_DXT1_EXT: u32;
+    const COMPRESSED_RGBA_S3TC_DXT3_EXT: u32;
+    const COMPRESSED_RGBA_S3TC_DXT5_EXT: u32;
+    const COMPRESSED_SRGB_S3TC_DXT1_EXT: u32;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32;
+    const COMPRESSED_RED_RGTC1: u32;
+    const COMPRESSED_RG_RGTC2: u32;
+    const COMPRESSED_RGBA_BPTC_UNORM: u32;
+    const COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32;
+    const COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: u32;
+    const COMPRESSED_RGB_BPTC_SIGNED_FLOAT: u32;
+    const COMPRESSED_RGB8_ETC2: u32;
+    const COMPRESSED_SRGB8_ETC2: u32;
+    const COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32;
+    const COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32;
+    const COMPRESSED_RGBA8_ETC2_EAC: u32;
+    const COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: u32;
 }
 
 pub(crate) trait AbstractContext {
@@ -608,6 +878,7 @@ pub(crate) trait AbstractContext {
     type VertexArray;
 
     fn get_error(&self) -> GLenum;
+    fn get_parameter_f32(&self, pname: GLenum) -> f32;
     fn uniform_matrix2fv(
         &self,
         location: Option<&Self::UniformLocation>,
@@ -658,6 +929,13 @@ pub(crate) trait AbstractContext {
     fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader);
     fn get_shader_parameter_int(&self, shader: &Self::Shader, pname: GLenum) -> Option<i32>;
     fn get_shader_info_log(&self, shader: &Self::Shader) -> Option<String>;
+    fn get_program_parameter_int(&self, program: &Self::Program, pname: GLenum) -> Option<i32>;
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String>;
+    fn bind_attrib_location(&self, program: &Self::Program, index: u32, name: &str);
+    fn get_active_uniforms(&self, program: &Self::Program) -> u32;
+    fn get_active_uniform(&self, program: &Self::Program, index: u32) -> Option<ActiveVariable>;
+    fn get_active_attributes(&self, program: &Self::Program) -> u32;
+    fn get_active_attribute(&self, program: &Self::Program, index: u32) -> Option<ActiveVariable>;
     fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -716,6 +994,28 @@ pub(crate) trait AbstractContext {
         format: GLenum,
         pixels: Option<&[u8]>,
     );
+    fn tex_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: Option<&[u8]>,
+    );
+    fn compressed_tex_image2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: GLenum,
+        width: i32,
+        height: i32,
+        border: i32,
+        pixels: &[u8],
+    );
     fn tex_image2di(
         &self,
         target: GLenum,
@@ -738,7 +1038,33 @@ pub(crate) trait AbstractContext {
         format: GLenum,
         pixels: Option<&[u8]>,
     );
+    fn tex_sub_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: &[u8],
+    );
+    fn tex_sub_image2d_from_buffer(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        offset: usize,
+    );
     fn tex_parameteri(&self, target: GLenum, pname: GLenum, param: i32);
+    fn tex_parameterf(&self, target: GLenum, pname: GLenum, param: f32);
+    fn tex_parameterfv(&self, target: GLenum, pname: GLenum, params: &[f32]);
     fn is_texture(&self, texture: Option<&Self::Texture>) -> bool;
     fn create_texture(&self) -> Option<Self::Texture>;
     fn delete_texture(&self, texture: Option<&Self::Texture>);
@@ -770,6 +1096,9 @@ pub(crate) trait AbstractContext {
         format: GLenum,
         pixels: Option<&mut [u8]>,
     );
+    fn version(&self) -> (u32, u32);
+    fn is_embedded(&self) -> bool;
+    fn supports_extension(&self, name: &str) -> bool;
     fn pixel_storei(&self, pname: GLenum, param: i32);
 
     fn blend_func_separate(
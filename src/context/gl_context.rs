@@ -1,7 +1,9 @@
 use std::mem;
 use std::sync::Arc;
 
-use crate::context::context::{AbstractContext, AbstractContextConst, GLenum, GLintptr};
+use crate::context::context::{
+    AbstractContext, AbstractContextConst, ActiveVariable, GLenum, GLintptr,
+};
 
 use crate::resource::gl_primitive::GLPrimitive;
 use glow::{Context, HasContext};
@@ -37,9 +39,18 @@ impl AbstractContextConst for GLContext {
     const STREAM_DRAW: u32 = glow::STREAM_DRAW;
     const ARRAY_BUFFER: u32 = glow::ARRAY_BUFFER;
     const ELEMENT_ARRAY_BUFFER: u32 = glow::ELEMENT_ARRAY_BUFFER;
+    const PIXEL_UNPACK_BUFFER: u32 = glow::PIXEL_UNPACK_BUFFER;
     const VERTEX_SHADER: u32 = glow::VERTEX_SHADER;
     const FRAGMENT_SHADER: u32 = glow::FRAGMENT_SHADER;
     const COMPILE_STATUS: u32 = glow::COMPILE_STATUS;
+    const LINK_STATUS: u32 = glow::LINK_STATUS;
+    const FLOAT_VEC2: u32 = glow::FLOAT_VEC2;
+    const FLOAT_VEC3: u32 = glow::FLOAT_VEC3;
+    const FLOAT_VEC4: u32 = glow::FLOAT_VEC4;
+    const FLOAT_MAT2: u32 = glow::FLOAT_MAT2;
+    const FLOAT_MAT3: u32 = glow::FLOAT_MAT3;
+    const FLOAT_MAT4: u32 = glow::FLOAT_MAT4;
+    const SAMPLER_2D: u32 = glow::SAMPLER_2D;
     const FRAMEBUFFER: u32 = glow::FRAMEBUFFER;
     const RENDERBUFFER: u32 = glow::RENDERBUFFER;
     const DEPTH_ATTACHMENT: u32 = glow::DEPTH_ATTACHMENT;
@@ -62,6 +73,13 @@ impl AbstractContextConst for GLContext {
     const REPEAT: u32 = glow::REPEAT;
     const MIRRORED_REPEAT: u32 = glow::MIRRORED_REPEAT;
     const LINEAR_MIPMAP_LINEAR: u32 = glow::LINEAR_MIPMAP_LINEAR;
+    const NEAREST_MIPMAP_NEAREST: u32 = glow::NEAREST_MIPMAP_NEAREST;
+    const LINEAR_MIPMAP_NEAREST: u32 = glow::LINEAR_MIPMAP_NEAREST;
+    const NEAREST_MIPMAP_LINEAR: u32 = glow::NEAREST_MIPMAP_LINEAR;
+    const CLAMP_TO_BORDER: u32 = glow::CLAMP_TO_BORDER;
+    const TEXTURE_BORDER_COLOR: u32 = glow::TEXTURE_BORDER_COLOR;
+    const TEXTURE_MAX_ANISOTROPY: u32 = glow::TEXTURE_MAX_ANISOTROPY;
+    const MAX_TEXTURE_MAX_ANISOTROPY: u32 = glow::MAX_TEXTURE_MAX_ANISOTROPY;
     const TRIANGLES: u32 = glow::TRIANGLES;
     const CULL_FACE: u32 = glow::CULL_FACE;
     const FRONT_AND_BACK: u32 = glow::FRONT_AND_BACK;
@@ -90,6 +108,47 @@ impl AbstractContextConst for GLContext {
     const RED: u32 = glow::RED;
     #[cfg(target_arch = "wasm32")]
     const RED: u32 = glow::LUMINANCE; // WebGL 1
+    const RG: u32 = glow::RG;
+    const LUMINANCE: u32 = glow::LUMINANCE;
+    const LUMINANCE_ALPHA: u32 = glow::LUMINANCE_ALPHA;
+    const GREEN: u32 = glow::GREEN;
+    const BLUE: u32 = glow::BLUE;
+    const R8: u32 = glow::R8;
+    const RG8: u32 = glow::RG8;
+    const R16: u32 = glow::R16;
+    const RG16: u32 = glow::RG16;
+    const RGB16: u32 = glow::RGB16;
+    const RGBA16: u32 = glow::RGBA16;
+    const RGB32F: u32 = glow::RGB32F;
+    const RGBA32F: u32 = glow::RGBA32F;
+    const R32F: u32 = glow::R32F;
+    const SRGB8: u32 = glow::SRGB8;
+    const SRGB8_ALPHA8: u32 = glow::SRGB8_ALPHA8;
+    const TEXTURE_SWIZZLE_R: u32 = glow::TEXTURE_SWIZZLE_R;
+    const TEXTURE_SWIZZLE_G: u32 = glow::TEXTURE_SWIZZLE_G;
+    const TEXTURE_SWIZZLE_B: u32 = glow::TEXTURE_SWIZZLE_B;
+    const TEXTURE_SWIZZLE_A: u32 = glow::TEXTURE_SWIZZLE_A;
+    const TEXTURE_MAX_LEVEL: u32 = glow::TEXTURE_MAX_LEVEL;
+    const COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = glow::COMPRESSED_RGB_S3TC_DXT1_EXT;
+    const COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = glow::COMPRESSED_RGBA_S3TC_DXT1_EXT;
+    const COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = glow::COMPRESSED_RGBA_S3TC_DXT3_EXT;
+    const COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = glow::COMPRESSED_RGBA_S3TC_DXT5_EXT;
+    const COMPRESSED_SRGB_S3TC_DXT1_EXT: u32 = glow::COMPRESSED_SRGB_S3TC_DXT1_EXT;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 = glow::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 = glow::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT;
+    const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 = glow::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT;
+    const COMPRESSED_RED_RGTC1: u32 = glow::COMPRESSED_RED_RGTC1;
+    const COMPRESSED_RG_RGTC2: u32 = glow::COMPRESSED_RG_RGTC2;
+    const COMPRESSED_RGBA_BPTC_UNORM: u32 = glow::COMPRESSED_RGBA_BPTC_UNORM;
+    const COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32 = glow::COMPRESSED_SRGB_ALPHA_BPTC_UNORM;
+    const COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: u32 = glow::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT;
+    const COMPRESSED_RGB_BPTC_SIGNED_FLOAT: u32 = glow::COMPRESSED_RGB_BPTC_SIGNED_FLOAT;
+    const COMPRESSED_RGB8_ETC2: u32 = glow::COMPRESSED_RGB8_ETC2;
+    const COMPRESSED_SRGB8_ETC2: u32 = glow::COMPRESSED_SRGB8_ETC2;
+    const COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 = glow::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2;
+    const COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 = glow::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2;
+    const COMPRESSED_RGBA8_ETC2_EAC: u32 = glow::COMPRESSED_RGBA8_ETC2_EAC;
+    const COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: u32 = glow::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC;
 }
 
 impl AbstractContext for GLContext {
@@ -106,6 +165,10 @@ impl AbstractContext for GLContext {
         unsafe { self.context.get_error() }
     }
 
+    fn get_parameter_f32(&self, pname: GLenum) -> f32 {
+        unsafe { self.context.get_parameter_f32(pname) }
+    }
+
     fn uniform_matrix2fv(
         &self,
         location: Option<&Self::UniformLocation>,
@@ -308,6 +371,56 @@ impl AbstractContext for GLContext {
         unsafe { Some(self.context.get_shader_info_log(*shader)) }
     }
 
+    fn get_program_parameter_int(&self, program: &Self::Program, _pname: GLenum) -> Option<i32> {
+        unsafe {
+            if self.context.get_program_link_status(*program) {
+                Some(1)
+            } else {
+                Some(0)
+            }
+        }
+    }
+
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String> {
+        unsafe { Some(self.context.get_program_info_log(*program)) }
+    }
+
+    fn bind_attrib_location(&self, program: &Self::Program, index: u32, name: &str) {
+        unsafe { self.context.bind_attrib_location(*program, index, name) }
+    }
+
+    fn get_active_uniforms(&self, program: &Self::Program) -> u32 {
+        unsafe { self.context.get_active_uniforms(*program) }
+    }
+
+    fn get_active_uniform(&self, program: &Self::Program, index: u32) -> Option<ActiveVariable> {
+        unsafe {
+            self.context
+                .get_active_uniform(*program, index)
+                .map(|u| ActiveVariable {
+                    name: u.name,
+                    type_: u.utype,
+                    size: u.size,
+                })
+        }
+    }
+
+    fn get_active_attributes(&self, program: &Self::Program) -> u32 {
+        unsafe { self.context.get_active_attributes(*program) }
+    }
+
+    fn get_active_attribute(&self, program: &Self::Program, index: u32) -> Option<ActiveVariable> {
+        unsafe {
+            self.context
+                .get_active_attribute(*program, index)
+                .map(|a| ActiveVariable {
+                    name: a.name,
+                    type_: a.atype,
+                    size: a.size,
+                })
+        }
+    }
+
     fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -475,6 +588,57 @@ impl AbstractContext for GLContext {
         }
     }
 
+    fn tex_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: Option<&[u8]>,
+    ) {
+        unsafe {
+            self.context.tex_image_2d(
+                target,
+                level,
+                internalformat,
+                width,
+                height,
+                border,
+                format,
+                ty,
+                pixels,
+            )
+        }
+    }
+
+    fn compressed_tex_image2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internalformat: GLenum,
+        width: i32,
+        height: i32,
+        border: i32,
+        pixels: &[u8],
+    ) {
+        unsafe {
+            self.context.compressed_tex_image_2d(
+                target,
+                level,
+                internalformat as i32,
+                width,
+                height,
+                border,
+                pixels.len() as i32,
+                pixels,
+            )
+        }
+    }
+
     fn tex_image2di(
         &self,
         target: GLenum,
@@ -533,10 +697,72 @@ impl AbstractContext for GLContext {
         }
     }
 
+    fn tex_sub_image2d_with_type(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        pixels: &[u8],
+    ) {
+        unsafe {
+            self.context.tex_sub_image_2d(
+                target,
+                level,
+                xoffset,
+                yoffset,
+                width,
+                height,
+                format,
+                ty,
+                glow::PixelUnpackData::Slice(pixels),
+            )
+        }
+    }
+
+    fn tex_sub_image2d_from_buffer(
+        &self,
+        target: GLenum,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        ty: GLenum,
+        offset: usize,
+    ) {
+        unsafe {
+            self.context.tex_sub_image_2d(
+                target,
+                level,
+                xoffset,
+                yoffset,
+                width,
+                height,
+                format,
+                ty,
+                glow::PixelUnpackData::BufferOffset(offset as u32),
+            )
+        }
+    }
+
     fn tex_parameteri(&self, target: GLenum, pname: GLenum, param: i32) {
         unsafe { self.context.tex_parameter_i32(target, pname, param) }
     }
 
+    fn tex_parameterf(&self, target: GLenum, pname: GLenum, param: f32) {
+        unsafe { self.context.tex_parameter_f32(target, pname, param) }
+    }
+
+    fn tex_parameterfv(&self, target: GLenum, pname: GLenum, params: &[f32]) {
+        unsafe { self.context.tex_parameter_f32_slice(target, pname, params) }
+    }
+
     fn is_texture(&self, texture: Option<&Self::Texture>) -> bool {
         if let Some(t) = texture {
             unsafe { self.context.is_texture(t.clone()) }
@@ -635,6 +861,19 @@ impl AbstractContext for GLContext {
         }
     }
 
+    fn version(&self) -> (u32, u32) {
+        let version = self.context.version();
+        (version.major, version.minor)
+    }
+
+    fn is_embedded(&self) -> bool {
+        self.context.version().is_embedded
+    }
+
+    fn supports_extension(&self, name: &str) -> bool {
+        self.context.supported_extensions().contains(name)
+    }
+
     fn pixel_storei(&self, pname: GLenum, param: i32) {
         unsafe { self.context.pixel_store_i32(pname, param) }
     }
@@ -15,7 +15,7 @@ impl<'a> Drop for Event<'a> {
     #[inline]
     fn drop(&mut self) {
         if !self.inhibited {
-            self.inhibitor.borrow_mut().push(self.value)
+            self.inhibitor.borrow_mut().push(self.value.clone())
         }
     }
 }
@@ -0,0 +1,488 @@
+//! Mapping of named actions to keys, mouse buttons and modifiers.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result as IoResult};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::event::window_event::{Action, Key, Modifiers, MouseButton, WindowEvent};
+
+/// A key or a mouse button.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Input {
+    Key(Key),
+    MouseButton(MouseButton),
+}
+
+/// A combination of inputs triggering an action.
+///
+/// All the inputs of the binding must be held at the same time (a chord). If `modifiers` is set,
+/// the modifiers held must also be exactly these; otherwise they are ignored.
+///
+/// Bindings are written as `+`-separated input names, e.g., `"Control+S"`, `"Shift+MouseLeft"`
+/// or `"G+X"`. Keys use the names of the `Key` variants, mouse buttons those of the
+/// `MouseButton` variants or `MouseLeft`, `MouseRight` and `MouseMiddle`, and modifiers are
+/// `Shift`, `Control` (or `Ctrl`), `Alt` and `Super`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Binding {
+    pub inputs: Vec<Input>,
+    pub modifiers: Option<Modifiers>,
+}
+
+impl Binding {
+    pub fn key(key: Key) -> Binding {
+        Binding::chord(vec![Input::Key(key)])
+    }
+
+    pub fn mouse_button(button: MouseButton) -> Binding {
+        Binding::chord(vec![Input::MouseButton(button)])
+    }
+
+    pub fn chord(inputs: Vec<Input>) -> Binding {
+        Binding {
+            inputs,
+            modifiers: None,
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: Option<Modifiers>) -> Binding {
+        self.modifiers = modifiers;
+        self
+    }
+
+    fn is_active(
+        &self,
+        keys: &HashSet<Key>,
+        buttons: &HashSet<MouseButton>,
+        modifiers: Modifiers,
+    ) -> bool {
+        !self.inputs.is_empty()
+            && self.modifiers.map(|m| m == modifiers).unwrap_or(true)
+            && self.inputs.iter().all(|input| match *input {
+                Input::Key(key) => keys.contains(&key),
+                Input::MouseButton(button) => buttons.contains(&button),
+            })
+    }
+}
+
+const MODIFIER_NAMES: [(&str, Modifiers); 5] = [
+    ("Shift", Modifiers::Shift),
+    ("Control", Modifiers::Control),
+    ("Ctrl", Modifiers::Control),
+    ("Alt", Modifiers::Alt),
+    ("Super", Modifiers::Super),
+];
+
+const MOUSE_BUTTON_ALIASES: [(&str, MouseButton); 3] = [
+    ("MouseLeft", MouseButton::Button1),
+    ("MouseRight", MouseButton::Button2),
+    ("MouseMiddle", MouseButton::Button3),
+];
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Binding, String> {
+        let mut inputs = Vec::new();
+        let mut modifiers: Option<Modifiers> = None;
+
+        for token in s.split('+').map(|t| t.trim()) {
+            if let Some(m) = MODIFIER_NAMES.iter().find(|m| m.0 == token) {
+                modifiers = Some(modifiers.unwrap_or_else(Modifiers::empty) | m.1);
+            } else if let Some(b) = MOUSE_BUTTON_ALIASES.iter().find(|b| b.0 == token) {
+                inputs.push(Input::MouseButton(b.1));
+            } else if let Ok(key) = serde_json::from_value(token.into()) {
+                inputs.push(Input::Key(key));
+            } else if let Ok(button) = serde_json::from_value(token.into()) {
+                inputs.push(Input::MouseButton(button));
+            } else {
+                return Err(format!("unknown input `{}` in binding `{}`", token, s));
+            }
+        }
+
+        if inputs.is_empty() {
+            return Err(format!("binding `{}` has no key or mouse button", s));
+        }
+
+        Ok(Binding { inputs, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tokens = Vec::new();
+
+        if let Some(modifiers) = self.modifiers {
+            for (name, m) in MODIFIER_NAMES.iter() {
+                if *name != "Ctrl" && modifiers.contains(*m) {
+                    tokens.push(name.to_string());
+                }
+            }
+        }
+
+        for input in self.inputs.iter() {
+            let name = match *input {
+                Input::Key(key) => serde_json::to_value(key),
+                Input::MouseButton(button) => serde_json::to_value(button),
+            };
+
+            match name {
+                Ok(serde_json::Value::String(name)) => tokens.push(name),
+                _ => return Err(fmt::Error),
+            }
+        }
+
+        write!(f, "{}", tokens.join("+"))
+    }
+}
+
+/// Maps named actions to bindings and tracks which actions are currently triggered.
+///
+/// The state is updated by feeding every window event to `handle_event`, and `new_frame` must
+/// be called once per frame so that `just_pressed` and `just_released` report the transitions
+/// of the current frame only.
+#[derive(Clone, Debug)]
+pub struct InputMap {
+    bindings: BTreeMap<String, Vec<Binding>>,
+    // The modifiers given to the actions rebound with `rebind_key` or `rebind_mouse_button`,
+    // kept while the actions are unbound.
+    action_modifiers: BTreeMap<String, Modifiers>,
+    keys: HashSet<Key>,
+    buttons: HashSet<MouseButton>,
+    modifiers: Modifiers,
+    active: HashSet<String>,
+    just_pressed: HashSet<String>,
+    just_released: HashSet<String>,
+}
+
+impl Default for InputMap {
+    fn default() -> InputMap {
+        InputMap::new()
+    }
+}
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        InputMap {
+            bindings: BTreeMap::new(),
+            action_modifiers: BTreeMap::new(),
+            keys: HashSet::new(),
+            buttons: HashSet::new(),
+            modifiers: Modifiers::empty(),
+            active: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+
+    /// Reads an input map from a JSON file mapping action names to lists of bindings, e.g.,
+    /// `{ "orbit": ["MouseLeft"], "pan": ["MouseRight", "Shift+MouseLeft"] }`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> IoResult<InputMap> {
+        let mut res = InputMap::new();
+        res.load_bindings(path)?;
+        Ok(res)
+    }
+
+    /// Reads bindings from a JSON file, see `from_file`.
+    ///
+    /// The bindings of the actions listed in the file replace the current ones. Other actions
+    /// are left unchanged.
+    pub fn load_bindings<P: AsRef<Path>>(&mut self, path: P) -> IoResult<()> {
+        let content = fs::read_to_string(path)?;
+        let parsed: BTreeMap<String, Vec<String>> = serde_json::from_str(&content)?;
+
+        for (action, bindings) in parsed {
+            let bindings = bindings
+                .iter()
+                .map(|b| b.parse())
+                .collect::<Result<Vec<Binding>, String>>()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            self.set_bindings(&action, bindings);
+        }
+
+        Ok(())
+    }
+
+    /// Writes all the bindings to a JSON file readable by `from_file`.
+    pub fn save_bindings<P: AsRef<Path>>(&self, path: P) -> IoResult<()> {
+        let serialized: BTreeMap<&String, Vec<String>> = self
+            .bindings
+            .iter()
+            .map(|(action, bindings)| (action, bindings.iter().map(|b| b.to_string()).collect()))
+            .collect();
+
+        fs::write(path, serde_json::to_string_pretty(&serialized)?)
+    }
+
+    /// The names of all the actions with at least one binding.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.bindings.keys().map(|a| a.as_str())
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map(|b| &b[..]).unwrap_or(&[])
+    }
+
+    /// Adds a binding to an action.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.bindings
+            .entry(action.to_string())
+            .or_default()
+            .push(binding);
+        self.update_actions();
+    }
+
+    /// Replaces all the bindings of an action.
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<Binding>) {
+        if bindings.is_empty() {
+            if let Some(old) = self.bindings.remove(action) {
+                self.store_modifiers(action, old[0].modifiers);
+            }
+        } else {
+            let _ = self.bindings.insert(action.to_string(), bindings);
+        }
+
+        self.update_actions();
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.set_bindings(action, Vec::new())
+    }
+
+    /// The key of the first binding of an action made of a single key.
+    pub fn key(&self, action: &str) -> Option<Key> {
+        self.bindings(action).iter().find_map(|b| match b.inputs[..] {
+            [Input::Key(key)] => Some(key),
+            _ => None,
+        })
+    }
+
+    /// The button of the first binding of an action made of a single mouse button.
+    pub fn mouse_button(&self, action: &str) -> Option<MouseButton> {
+        self.bindings(action).iter().find_map(|b| match b.inputs[..] {
+            [Input::MouseButton(button)] => Some(button),
+            _ => None,
+        })
+    }
+
+    /// The modifiers required by the first binding of an action, or those set with
+    /// `set_modifiers` if it is unbound.
+    pub fn modifiers(&self, action: &str) -> Option<Modifiers> {
+        match self.bindings(action).first() {
+            Some(binding) => binding.modifiers,
+            None => self.action_modifiers.get(action).copied(),
+        }
+    }
+
+    /// Sets the modifiers required by all the bindings of an action, including those given to
+    /// it later by `rebind_key` or `rebind_mouse_button`.
+    pub fn set_modifiers(&mut self, action: &str, modifiers: Option<Modifiers>) {
+        self.store_modifiers(action, modifiers);
+
+        if let Some(bindings) = self.bindings.get_mut(action) {
+            for binding in bindings.iter_mut() {
+                binding.modifiers = modifiers;
+            }
+        }
+
+        self.update_actions();
+    }
+
+    /// Binds an action to a single key only, keeping its modifiers, see `modifiers`.
+    pub fn rebind_key(&mut self, action: &str, key: Option<Key>) {
+        let modifiers = self.modifiers(action);
+        let bindings = key
+            .map(|k| vec![Binding::key(k).with_modifiers(modifiers)])
+            .unwrap_or_default();
+        self.set_bindings(action, bindings)
+    }
+
+    /// Binds an action to a single mouse button only, keeping its modifiers, see `modifiers`.
+    pub fn rebind_mouse_button(&mut self, action: &str, button: Option<MouseButton>) {
+        let modifiers = self.modifiers(action);
+        let bindings = button
+            .map(|b| vec![Binding::mouse_button(b).with_modifiers(modifiers)])
+            .unwrap_or_default();
+        self.set_bindings(action, bindings)
+    }
+
+    /// Updates the state of the inputs and actions.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, action, modifiers) => {
+                self.modifiers = modifiers;
+                let _ = match action {
+                    Action::Press => self.keys.insert(key),
+                    Action::Release => self.keys.remove(&key),
+                };
+            }
+            WindowEvent::MouseButton(button, action, modifiers) => {
+                self.modifiers = modifiers;
+                let _ = match action {
+                    Action::Press => self.buttons.insert(button),
+                    Action::Release => self.buttons.remove(&button),
+                };
+            }
+            WindowEvent::CursorPos(_, _, modifiers) | WindowEvent::Scroll(_, _, modifiers) => {
+                self.modifiers = modifiers;
+            }
+            // The releases happening while unfocused are never received, so forget everything
+            // held without reporting the actions as released.
+            WindowEvent::Focus(false) => {
+                self.keys.clear();
+                self.buttons.clear();
+                self.modifiers = Modifiers::empty();
+                self.active.clear();
+                return;
+            }
+            _ => return,
+        }
+
+        self.update_actions();
+    }
+
+    /// Forgets the actions pressed or released during the previous frame.
+    pub fn new_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+
+    /// Returns `true` if one of the bindings of the action is currently held.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.active.contains(action)
+    }
+
+    /// Returns `true` if the action started being held during the current frame.
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.just_pressed.contains(action)
+    }
+
+    /// Returns `true` if the action stopped being held during the current frame.
+    pub fn just_released(&self, action: &str) -> bool {
+        self.just_released.contains(action)
+    }
+
+    fn update_actions(&mut self) {
+        for (action, bindings) in self.bindings.iter() {
+            let active = bindings
+                .iter()
+                .any(|b| b.is_active(&self.keys, &self.buttons, self.modifiers));
+
+            if active && !self.active.contains(action) {
+                let _ = self.active.insert(action.clone());
+                let _ = self.just_pressed.insert(action.clone());
+            } else if !active && self.active.contains(action) {
+                let _ = self.active.remove(action);
+                let _ = self.just_released.insert(action.clone());
+            }
+        }
+
+        // Actions that lost all their bindings.
+        let bindings = &self.bindings;
+        let released: Vec<String> = self
+            .active
+            .iter()
+            .filter(|a| !bindings.contains_key(*a))
+            .cloned()
+            .collect();
+
+        for action in released {
+            let _ = self.active.remove(&action);
+            let _ = self.just_released.insert(action);
+        }
+    }
+
+    fn store_modifiers(&mut self, action: &str, modifiers: Option<Modifiers>) {
+        match modifiers {
+            Some(modifiers) => {
+                let _ = self.action_modifiers.insert(action.to_string(), modifiers);
+            }
+            None => {
+                let _ = self.action_modifiers.remove(action);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_are_parsed_from_input_names() {
+        let parse = |s: &str| s.parse::<Binding>().unwrap();
+
+        assert_eq!(
+            parse("Control+S"),
+            Binding::key(Key::S).with_modifiers(Some(Modifiers::Control))
+        );
+        assert_eq!(
+            parse("Ctrl + Shift + MouseLeft"),
+            Binding::mouse_button(MouseButton::Button1)
+                .with_modifiers(Some(Modifiers::Control | Modifiers::Shift))
+        );
+        assert_eq!(
+            parse("G+X"),
+            Binding::chord(vec![Input::Key(Key::G), Input::Key(Key::X)])
+        );
+        assert_eq!(
+            parse("Button4"),
+            Binding::mouse_button(MouseButton::Button4)
+        );
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        for s in ["", "Control", "Shift+Alt", "Control+Foo", "S+"] {
+            assert!(s.parse::<Binding>().is_err(), "{:?} was accepted", s);
+        }
+    }
+
+    #[test]
+    fn bindings_are_written_as_they_are_parsed() {
+        let written = |s: &str| s.parse::<Binding>().unwrap().to_string();
+
+        assert_eq!(written("Ctrl+S"), "Control+S");
+        assert_eq!(written("Alt+Shift+MouseRight"), "Shift+Alt+Button2");
+
+        for s in ["Control+S", "Shift+Alt+Button2", "G+X", "Super+Space"] {
+            assert_eq!(written(s), s);
+        }
+    }
+
+    #[test]
+    fn bindings_survive_a_json_round_trip() {
+        let mut map = InputMap::new();
+        map.bind("save", "Control+S".parse().unwrap());
+        map.bind("orbit", Binding::mouse_button(MouseButton::Button1));
+        map.bind("orbit", "Shift+MouseMiddle".parse().unwrap());
+        map.bind("grab_x", "G+X".parse().unwrap());
+
+        let path = std::env::temp_dir().join("starman_input_map.json");
+        map.save_bindings(&path).unwrap();
+        let loaded = InputMap::from_file(&path);
+        let _ = fs::remove_file(&path);
+        let loaded = loaded.unwrap();
+
+        assert_eq!(
+            loaded.actions().collect::<Vec<_>>(),
+            map.actions().collect::<Vec<_>>()
+        );
+
+        for action in map.actions() {
+            assert_eq!(loaded.bindings(action), map.bindings(action));
+        }
+    }
+
+    #[test]
+    fn invalid_json_bindings_are_reported() {
+        let path = std::env::temp_dir().join("starman_invalid_input_map.json");
+        fs::write(&path, r#"{ "save": ["Control+Foo"] }"#).unwrap();
+        let error = InputMap::from_file(&path).err().unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
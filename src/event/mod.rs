@@ -1,2 +1,4 @@
 pub mod event_manager;
+pub mod input_map;
+pub mod recorder;
 pub mod window_event;
\ No newline at end of file
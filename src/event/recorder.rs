@@ -0,0 +1,153 @@
+//! Recording and replay of window events.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Result as IoResult, Write};
+use std::path::Path;
+
+use crate::event::window_event::{Action, Key, MouseButton, WindowEvent};
+
+/// A window event together with the frame it was delivered at.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Index of the frame, relative to the start of the recording.
+    pub frame: u64,
+    /// Time elapsed since the start of the recording, in seconds.
+    pub time: f64,
+    /// The recorded event.
+    pub event: WindowEvent,
+}
+
+/// Writes window events to a file, one JSON-encoded `RecordedEvent` per line.
+pub struct EventRecorder {
+    out: BufWriter<File>,
+    first_frame: u64,
+    start_time: f64,
+}
+
+impl EventRecorder {
+    /// Creates the recording file, truncating it if it exists.
+    ///
+    /// `frame` and `time` are the current frame index and clock time; recorded events are
+    /// stored relative to them.
+    pub fn create<P: AsRef<Path>>(path: P, frame: u64, time: f64) -> IoResult<EventRecorder> {
+        Ok(EventRecorder {
+            out: BufWriter::new(File::create(path)?),
+            first_frame: frame,
+            start_time: time,
+        })
+    }
+
+    /// Appends an event delivered at the given frame index and clock time.
+    pub fn record(&mut self, frame: u64, time: f64, event: &WindowEvent) -> IoResult<()> {
+        let recorded = RecordedEvent {
+            frame: frame - self.first_frame,
+            time: time - self.start_time,
+            event: event.clone(),
+        };
+
+        serde_json::to_writer(&mut self.out, &recorded)?;
+        self.out.write_all(b"\n")
+    }
+
+    pub fn flush(&mut self) -> IoResult<()> {
+        self.out.flush()
+    }
+}
+
+/// A stream of recorded events, fed back to a window frame by frame.
+pub struct EventReplay {
+    events: VecDeque<RecordedEvent>,
+    first_frame: Option<u64>,
+}
+
+impl EventReplay {
+    pub fn new(events: Vec<RecordedEvent>) -> EventReplay {
+        EventReplay {
+            events: events.into(),
+            first_frame: None,
+        }
+    }
+
+    /// Reads a file written by an `EventRecorder`.
+    pub fn open<P: AsRef<Path>>(path: P) -> IoResult<EventReplay> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event = serde_json::from_str(&line).map_err(|e| {
+                Error::new(ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+            })?;
+            events.push(event);
+        }
+
+        Ok(EventReplay::new(events))
+    }
+
+    /// Removes and returns the events recorded for the given frame.
+    ///
+    /// The first frame this is called with is matched with the first frame of the recording.
+    pub fn events_for_frame(&mut self, frame: u64) -> Vec<WindowEvent> {
+        let first_frame = *self.first_frame.get_or_insert(frame);
+        let frame = frame - first_frame;
+        let mut res = Vec::new();
+
+        while self.events.front().map(|e| e.frame <= frame).unwrap_or(false) {
+            if let Some(recorded) = self.events.pop_front() {
+                res.push(recorded.event);
+            }
+        }
+
+        res
+    }
+
+    /// Returns `true` if all the recorded events have been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// The keyboard and mouse state resulting from replayed events.
+///
+/// It replaces the live state of the canvas while a replay is running, so that cameras polling
+/// the state of keys and buttons behave like during the recording.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayedInput {
+    keys: HashMap<Key, Action>,
+    buttons: HashMap<MouseButton, Action>,
+    cursor_pos: Option<(f64, f64)>,
+}
+
+impl ReplayedInput {
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, action, _) => {
+                let _ = self.keys.insert(key, action);
+            }
+            WindowEvent::MouseButton(button, action, _) => {
+                let _ = self.buttons.insert(button, action);
+            }
+            WindowEvent::CursorPos(x, y, _) => self.cursor_pos = Some((x, y)),
+            WindowEvent::CursorEnter(false) => self.cursor_pos = None,
+            _ => {}
+        }
+    }
+
+    pub fn get_key(&self, key: Key) -> Action {
+        self.keys.get(&key).cloned().unwrap_or(Action::Release)
+    }
+
+    pub fn get_mouse_button(&self, button: MouseButton) -> Action {
+        self.buttons.get(&button).cloned().unwrap_or(Action::Release)
+    }
+
+    pub fn cursor_pos(&self) -> Option<(f64, f64)> {
+        self.cursor_pos
+    }
+}
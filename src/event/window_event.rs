@@ -1,6 +1,7 @@
 use bitflags::bitflags;
+use std::path::PathBuf;
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 pub enum WindowEvent {
     Pos(i32, i32),
     Size(u32, u32),
@@ -17,6 +18,9 @@ pub enum WindowEvent {
     Char(char),
     CharModifiers(char, Modifiers),
     Touch(u64, f64, f64, TouchAction, Modifiers),
+    FileHovered(PathBuf),
+    FileHoverCancelled,
+    FileDropped(PathBuf),
 }
 
 use WindowEvent::*;
@@ -0,0 +1,1174 @@
+//! CPU decoding of block-compressed textures, for contexts that cannot sample them.
+
+use image::{DynamicImage, GrayImage, ImageBuffer, RgbImage, RgbaImage};
+
+use crate::loader::texture_file::TextureFormat;
+
+/// Decodes an image of the given format, as described by `TextureFile::decode_level`.
+pub fn decode(format: TextureFormat, width: u32, height: u32, data: &[u8]) -> DynamicImage {
+    let rgba = |decode_block: fn(&[u8], &mut [[u8; 4]; 16])| {
+        decode_blocks(width, height, data, format, decode_block)
+    };
+
+    match format {
+        TextureFormat::Rgb8 => {
+            DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, data.to_vec()).unwrap())
+        }
+        TextureFormat::Rgba8 => {
+            DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, data.to_vec()).unwrap())
+        }
+        TextureFormat::Bc1 => rgb(
+            width,
+            height,
+            rgba(|b, out| decode_bc1(b, out, false, false)),
+        ),
+        TextureFormat::Bc1Alpha => rgba_image(
+            width,
+            height,
+            rgba(|b, out| decode_bc1(b, out, true, false)),
+        ),
+        TextureFormat::Bc2 => rgba_image(width, height, rgba(decode_bc2)),
+        TextureFormat::Bc3 => rgba_image(width, height, rgba(decode_bc3)),
+        TextureFormat::Bc4 => {
+            let pixels = rgba(decode_bc4_block);
+            let gray = pixels.chunks_exact(4).map(|p| p[0]).collect();
+            DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, gray).unwrap())
+        }
+        TextureFormat::Bc5 => rgb(width, height, rgba(decode_bc5)),
+        TextureFormat::Bc6hUfloat | TextureFormat::Bc6hSfloat => {
+            let signed = format == TextureFormat::Bc6hSfloat;
+            let pixels = decode_blocks(width, height, data, format, |b, out| {
+                decode_bc6h(b, out, signed)
+            });
+            DynamicImage::ImageRgb32F(ImageBuffer::from_raw(width, height, pixels).unwrap())
+        }
+        TextureFormat::Bc7 => rgba_image(width, height, rgba(decode_bc7)),
+        TextureFormat::Etc2Rgb8 => rgb(width, height, rgba(|b, out| decode_etc2(b, out, false))),
+        TextureFormat::Etc2Rgb8A1 => {
+            rgba_image(width, height, rgba(|b, out| decode_etc2(b, out, true)))
+        }
+        TextureFormat::Etc2Rgba8 => rgba_image(width, height, rgba(decode_etc2_eac)),
+    }
+}
+
+fn rgba_image(width: u32, height: u32, pixels: Vec<u8>) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, pixels).unwrap())
+}
+
+// Drops the alpha channel.
+fn rgb(width: u32, height: u32, pixels: Vec<u8>) -> DynamicImage {
+    let pixels = pixels
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, pixels).unwrap())
+}
+
+// Decodes every block of an image, and returns its pixels row by row. Blocks on the right and
+// bottom edges are cropped to the image.
+fn decode_blocks<T: Copy + Default, const N: usize>(
+    width: u32,
+    height: u32,
+    data: &[u8],
+    format: TextureFormat,
+    decode_block: impl Fn(&[u8], &mut [[T; N]; 16]),
+) -> Vec<T> {
+    let (width, height) = (width as usize, height as usize);
+    let block_bytes = format.block_bytes().unwrap();
+    let blocks_per_row = width.div_ceil(4);
+    let mut pixels = vec![T::default(); width * height * N];
+    let mut block_pixels = [[T::default(); N]; 16];
+
+    for (i, block) in data.chunks_exact(block_bytes).enumerate() {
+        let (x0, y0) = ((i % blocks_per_row) * 4, (i / blocks_per_row) * 4);
+        decode_block(block, &mut block_pixels);
+
+        for y in y0..(y0 + 4).min(height) {
+            for x in x0..(x0 + 4).min(width) {
+                let start = (y * width + x) * N;
+                pixels[start..start + N].copy_from_slice(&block_pixels[(y - y0) * 4 + x - x0]);
+            }
+        }
+    }
+
+    pixels
+}
+
+/*
+ * BC1 to BC5.
+ */
+fn rgb565(color: u16) -> [u8; 4] {
+    let (r, g, b) = ((color >> 11) & 31, (color >> 5) & 63, color & 31);
+    [
+        (r << 3 | r >> 2) as u8,
+        (g << 2 | g >> 4) as u8,
+        (b << 3 | b >> 2) as u8,
+        255,
+    ]
+}
+
+// `(wa * a + wb * b) / (wa + wb)` for each channel, rounded.
+fn mix(a: [u8; 4], b: [u8; 4], wa: u32, wb: u32) -> [u8; 4] {
+    let mut res = [255; 4];
+
+    for c in 0..3 {
+        res[c] = ((a[c] as u32 * wa + b[c] as u32 * wb + (wa + wb) / 2) / (wa + wb)) as u8;
+    }
+
+    res
+}
+
+// Decodes the color part of BC1, BC2 and BC3 blocks. Only BC1 blocks have a mode with three
+// colors and a transparent, or black if there is no alpha, pixel.
+fn decode_bc1(block: &[u8], out: &mut [[u8; 4]; 16], alpha: bool, four_colors: bool) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+
+    let colors = if c0 > c1 || four_colors {
+        [a, b, mix(a, b, 2, 1), mix(a, b, 1, 2)]
+    } else {
+        [
+            a,
+            b,
+            mix(a, b, 1, 1),
+            [0, 0, 0, if alpha { 0 } else { 255 }],
+        ]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = colors[(indices >> (2 * i)) as usize & 3];
+    }
+}
+
+fn decode_bc2(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_bc1(&block[8..], out, false, true);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        pixel[3] = ((block[i / 2] >> (4 * (i % 2))) & 15) * 17;
+    }
+}
+
+fn decode_bc3(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_bc1(&block[8..], out, false, true);
+    let alpha = decode_bc4(block);
+
+    for (pixel, a) in out.iter_mut().zip(alpha.iter()) {
+        pixel[3] = *a;
+    }
+}
+
+// Decodes a single-channel block, also used for the alpha of BC3 and the channels of BC5.
+fn decode_bc4(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut values = [a0, a1, 0, 0, 0, 0, 0, 255];
+
+    if a0 > a1 {
+        for i in 1..7 {
+            values[i + 1] = ((7 - i as u32) * a0 + i as u32 * a1 + 3) / 7;
+        }
+    } else {
+        for i in 1..5 {
+            values[i + 1] = ((5 - i as u32) * a0 + i as u32 * a1 + 2) / 5;
+        }
+    }
+
+    let bits = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, b| bits << 8 | *b as u64);
+    let mut res = [0; 16];
+
+    for (i, value) in res.iter_mut().enumerate() {
+        *value = values[(bits >> (3 * i)) as usize & 7] as u8;
+    }
+
+    res
+}
+
+fn decode_bc4_block(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    for (pixel, r) in out.iter_mut().zip(decode_bc4(block).iter()) {
+        *pixel = [*r, *r, *r, 255];
+    }
+}
+
+fn decode_bc5(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let red = decode_bc4(block);
+    let green = decode_bc4(&block[8..]);
+
+    for i in 0..16 {
+        out[i] = [red[i], green[i], 0, 255];
+    }
+}
+
+/*
+ * BC6H and BC7.
+ */
+// The subset of each pixel of the partitions with two subsets, one bit per pixel.
+const PARTITIONS2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+// The subset of each pixel of the partitions with three subsets, two bits per pixel.
+const PARTITIONS3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+    0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+    0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+    0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+    0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+    0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+    0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+    0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+// The pixel storing one bit less of index for the second subset of two-subset partitions.
+const ANCHORS2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+// The same for the second and third subsets of three-subset partitions.
+#[rustfmt::skip]
+const ANCHORS3: [[u8; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+const WEIGHTS2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+// Reads the bits of a 128-bit block, least significant first.
+struct BitReader(u128);
+
+impl BitReader {
+    fn new(block: &[u8]) -> BitReader {
+        BitReader(u128::from_le_bytes(block[..16].try_into().unwrap()))
+    }
+
+    fn read(&mut self, num_bits: u32) -> u32 {
+        let res = (self.0 & ((1 << num_bits) - 1)) as u32;
+        self.0 >>= num_bits;
+        res
+    }
+}
+
+fn subset(num_subsets: usize, partition: usize, pixel: usize) -> usize {
+    match num_subsets {
+        1 => 0,
+        2 => (PARTITIONS2[partition] >> pixel) as usize & 1,
+        _ => (PARTITIONS3[partition] >> (2 * pixel)) as usize & 3,
+    }
+}
+
+fn is_anchor(num_subsets: usize, partition: usize, pixel: usize) -> bool {
+    match num_subsets {
+        1 => pixel == 0,
+        2 => pixel == 0 || pixel == ANCHORS2[partition] as usize,
+        _ => pixel == 0 || ANCHORS3[partition].contains(&(pixel as u8)),
+    }
+}
+
+fn weight(index_bits: u32, index: u32) -> u32 {
+    match index_bits {
+        2 => WEIGHTS2[index as usize],
+        3 => WEIGHTS3[index as usize],
+        _ => WEIGHTS4[index as usize],
+    }
+}
+
+fn interpolate(e0: i32, e1: i32, weight: u32) -> i32 {
+    ((64 - weight as i32) * e0 + weight as i32 * e1 + 32) >> 6
+}
+
+struct Bc7Mode {
+    num_subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode {
+        num_subsets: 3,
+        partition_bits: 4,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 4,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 3,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        num_subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 6,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: true,
+        index_bits: 3,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        num_subsets: 3,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 0,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        num_subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 0,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        num_subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 1,
+        color_bits: 5,
+        alpha_bits: 6,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 3,
+    },
+    Bc7Mode {
+        num_subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 2,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 8,
+        endpoint_pbits: false,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 2,
+    },
+    Bc7Mode {
+        num_subsets: 1,
+        partition_bits: 0,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 7,
+        alpha_bits: 7,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 4,
+        index2_bits: 0,
+    },
+    Bc7Mode {
+        num_subsets: 2,
+        partition_bits: 6,
+        rotation_bits: 0,
+        index_selection_bits: 0,
+        color_bits: 5,
+        alpha_bits: 5,
+        endpoint_pbits: true,
+        shared_pbits: false,
+        index_bits: 2,
+        index2_bits: 0,
+    },
+];
+
+fn decode_bc7(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let mut bits = BitReader::new(block);
+    // The mode is the number of zeros before the first set bit.
+    let mode = match (0..8).find(|_| bits.read(1) == 1) {
+        Some(mode) => &BC7_MODES[mode],
+        None => {
+            *out = [[0; 4]; 16];
+            return;
+        }
+    };
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+    let num_endpoints = mode.num_subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+
+    for c in 0..3 {
+        for endpoint in endpoints[..num_endpoints].iter_mut() {
+            endpoint[c] = bits.read(mode.color_bits);
+        }
+    }
+
+    if mode.alpha_bits > 0 {
+        for endpoint in endpoints[..num_endpoints].iter_mut() {
+            endpoint[3] = bits.read(mode.alpha_bits);
+        }
+    }
+
+    let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let mut pbit = 0;
+
+        for (i, endpoint) in endpoints[..num_endpoints].iter_mut().enumerate() {
+            if mode.endpoint_pbits || i % 2 == 0 {
+                pbit = bits.read(1);
+            }
+
+            for c in endpoint.iter_mut() {
+                *c = *c << 1 | pbit;
+            }
+        }
+
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    // Expands the endpoints to 8 bits.
+    for endpoint in endpoints[..num_endpoints].iter_mut() {
+        for (c, value) in endpoint.iter_mut().enumerate() {
+            let num_bits = if c < 3 { color_bits } else { alpha_bits };
+
+            if num_bits > 0 {
+                *value = *value << (8 - num_bits) | *value >> (2 * num_bits - 8);
+            }
+        }
+    }
+
+    let mut indices = [0; 16];
+    let mut indices2 = [0; 16];
+
+    for (i, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.num_subsets, partition, i);
+        *index = bits.read(mode.index_bits - anchor as u32);
+    }
+
+    if mode.index2_bits > 0 {
+        for (i, index) in indices2.iter_mut().enumerate() {
+            *index = bits.read(mode.index2_bits - (i == 0) as u32);
+        }
+    }
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let s = subset(mode.num_subsets, partition, i);
+        let (e0, e1) = (endpoints[2 * s], endpoints[2 * s + 1]);
+
+        let (color_weight, alpha_weight) = if mode.index2_bits == 0 {
+            let w = weight(mode.index_bits, indices[i]);
+            (w, w)
+        } else if index_selection == 0 {
+            (
+                weight(mode.index_bits, indices[i]),
+                weight(mode.index2_bits, indices2[i]),
+            )
+        } else {
+            (
+                weight(mode.index2_bits, indices2[i]),
+                weight(mode.index_bits, indices[i]),
+            )
+        };
+
+        for c in 0..3 {
+            pixel[c] = interpolate(e0[c] as i32, e1[c] as i32, color_weight) as u8;
+        }
+
+        pixel[3] = if mode.alpha_bits == 0 {
+            255
+        } else {
+            interpolate(e0[3] as i32, e1[3] as i32, alpha_weight) as u8
+        };
+
+        if rotation > 0 {
+            pixel.swap(rotation as usize - 1, 3);
+        }
+    }
+}
+
+// The endpoint components of BC6H blocks: red, green then blue of each of the four endpoints.
+const R0: u8 = 0;
+const G0: u8 = 1;
+const B0: u8 = 2;
+const R1: u8 = 3;
+const G1: u8 = 4;
+const B1: u8 = 5;
+const R2: u8 = 6;
+const G2: u8 = 7;
+const B2: u8 = 8;
+const R3: u8 = 9;
+const G3: u8 = 10;
+const B3: u8 = 11;
+
+struct Bc6hMode {
+    // The bits of the endpoint components following the mode bits, in block order: a component
+    // and its most and least significant bits. Bits are reversed if the first is lower.
+    fields: &'static [(u8, u8, u8)],
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+}
+
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode {
+        fields: &[
+            (G2, 4, 4), (B2, 4, 4), (B3, 4, 4), (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0),
+            (G3, 4, 4), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1),
+            (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 10,
+        delta_bits: [5, 5, 5],
+    },
+    Bc6hMode {
+        fields: &[
+            (G2, 5, 5), (G3, 4, 4), (G3, 5, 5), (R0, 6, 0), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4),
+            (G0, 6, 0), (B2, 5, 5), (B3, 2, 2), (G2, 4, 4), (B0, 6, 0), (B3, 3, 3), (B3, 5, 5),
+            (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 5, 0), (B2, 3, 0),
+            (R2, 5, 0), (R3, 5, 0),
+        ],
+        transformed: true,
+        endpoint_bits: 7,
+        delta_bits: [6, 6, 6],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0), (R0, 10, 10), (G2, 3, 0), (G1, 3, 0),
+            (G0, 10, 10), (B3, 0, 0), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0),
+            (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [5, 4, 4],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (G3, 4, 4), (G2, 3, 0),
+            (G1, 4, 0), (G0, 10, 10), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0),
+            (R2, 3, 0), (B3, 0, 0), (B3, 2, 2), (R3, 3, 0), (G2, 4, 4), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 5, 4],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (B2, 4, 4), (G2, 3, 0),
+            (G1, 3, 0), (G0, 10, 10), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B0, 10, 10), (B2, 3, 0),
+            (R2, 3, 0), (B3, 1, 1), (B3, 2, 2), (R3, 3, 0), (B3, 4, 4), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 4, 5],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 8, 0), (B2, 4, 4), (G0, 8, 0), (G2, 4, 4), (B0, 8, 0), (B3, 4, 4), (R1, 4, 0),
+            (G3, 4, 4), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1),
+            (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 9,
+        delta_bits: [5, 5, 5],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 7, 0), (G3, 4, 4), (B2, 4, 4), (G0, 7, 0), (B3, 2, 2), (G2, 4, 4), (B0, 7, 0),
+            (B3, 3, 3), (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0),
+            (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+        ],
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [6, 5, 5],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 7, 0), (B3, 0, 0), (B2, 4, 4), (G0, 7, 0), (G2, 5, 5), (G2, 4, 4), (B0, 7, 0),
+            (G3, 5, 5), (B3, 4, 4), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 5, 0), (G3, 3, 0),
+            (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 6, 5],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 7, 0), (B3, 1, 1), (B2, 4, 4), (G0, 7, 0), (B2, 5, 5), (G2, 4, 4), (B0, 7, 0),
+            (B3, 5, 5), (B3, 4, 4), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0),
+            (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 5, 6],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 5, 0), (G3, 4, 4), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 5, 0), (G2, 5, 5),
+            (B2, 5, 5), (B3, 2, 2), (G2, 4, 4), (B0, 5, 0), (G3, 5, 5), (B3, 3, 3), (B3, 5, 5),
+            (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 5, 0), (B2, 3, 0),
+            (R2, 5, 0), (R3, 5, 0),
+        ],
+        transformed: false,
+        endpoint_bits: 6,
+        delta_bits: [6, 6, 6],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 9, 0), (G1, 9, 0), (B1, 9, 0),
+        ],
+        transformed: false,
+        endpoint_bits: 10,
+        delta_bits: [10, 10, 10],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 8, 0), (R0, 10, 10), (G1, 8, 0), (G0, 10, 10),
+            (B1, 8, 0), (B0, 10, 10),
+        ],
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [9, 9, 9],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 7, 0), (R0, 10, 11), (G1, 7, 0), (G0, 10, 11),
+            (B1, 7, 0), (B0, 10, 11),
+        ],
+        transformed: true,
+        endpoint_bits: 12,
+        delta_bits: [8, 8, 8],
+    },
+    Bc6hMode {
+        fields: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 15), (G1, 3, 0), (G0, 10, 15),
+            (B1, 3, 0), (B0, 10, 15),
+        ],
+        transformed: true,
+        endpoint_bits: 16,
+        delta_bits: [4, 4, 4],
+    },
+];
+
+fn sign_extend(value: i32, num_bits: u32) -> i32 {
+    let shift = 32 - num_bits;
+    (value << shift) >> shift
+}
+
+fn decode_bc6h(block: &[u8], out: &mut [[f32; 3]; 16], signed: bool) {
+    let mut bits = BitReader::new(block);
+    let mode = match bits.read(2) {
+        m @ 0..=1 => m as usize,
+        m => match m | bits.read(3) << 2 {
+            0b00010 => 2,
+            0b00110 => 3,
+            0b01010 => 4,
+            0b01110 => 5,
+            0b10010 => 6,
+            0b10110 => 7,
+            0b11010 => 8,
+            0b11110 => 9,
+            0b00011 => 10,
+            0b00111 => 11,
+            0b01011 => 12,
+            0b01111 => 13,
+            // Reserved modes decode to black.
+            _ => {
+                *out = [[0.0; 3]; 16];
+                return;
+            }
+        },
+    };
+    let mode = &BC6H_MODES[mode];
+    let num_subsets = if mode.fields.len() > 9 { 2 } else { 1 };
+    let num_endpoints = num_subsets * 2;
+    let mut endpoints = [[0i32; 3]; 4];
+
+    for &(component, msb, lsb) in mode.fields {
+        let (e, c) = (component as usize / 3, component as usize % 3);
+
+        if msb >= lsb {
+            for bit in lsb..=msb {
+                endpoints[e][c] |= (bits.read(1) as i32) << bit;
+            }
+        } else {
+            for bit in (msb..=lsb).rev() {
+                endpoints[e][c] |= (bits.read(1) as i32) << bit;
+            }
+        }
+    }
+
+    let partition = if num_subsets == 2 {
+        bits.read(5) as usize
+    } else {
+        0
+    };
+
+    let endpoint_bits = mode.endpoint_bits;
+
+    for c in 0..3 {
+        let first = endpoints[0][c];
+
+        if signed {
+            endpoints[0][c] = sign_extend(first, endpoint_bits);
+        }
+
+        for endpoint in endpoints[1..num_endpoints].iter_mut() {
+            if mode.transformed {
+                let delta = sign_extend(endpoint[c], mode.delta_bits[c]);
+                endpoint[c] = (first + delta) & ((1 << endpoint_bits) - 1);
+            }
+
+            if signed {
+                endpoint[c] = sign_extend(endpoint[c], endpoint_bits);
+            }
+        }
+    }
+
+    // Scales the endpoints to 16 bits.
+    for endpoint in endpoints[..num_endpoints].iter_mut() {
+        for value in endpoint.iter_mut() {
+            *value = unquantize_bc6h(*value, endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if num_subsets == 2 { 3 } else { 4 };
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let anchor = is_anchor(num_subsets, partition, i);
+        let index = bits.read(index_bits - anchor as u32);
+        let s = subset(num_subsets, partition, i);
+        let w = weight(index_bits, index);
+
+        for c in 0..3 {
+            let value = interpolate(endpoints[2 * s][c], endpoints[2 * s + 1][c], w);
+            pixel[c] = half_to_f32(finish_unquantize_bc6h(value, signed));
+        }
+    }
+}
+
+fn unquantize_bc6h(value: i32, num_bits: u32, signed: bool) -> i32 {
+    if signed {
+        if num_bits >= 16 {
+            return value;
+        }
+
+        let magnitude = value.abs();
+        let res = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (num_bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (num_bits - 1)
+        };
+
+        if value < 0 {
+            -res
+        } else {
+            res
+        }
+    } else if num_bits >= 15 || value == 0 {
+        value
+    } else if value == (1 << num_bits) - 1 {
+        0xFFFF
+    } else {
+        ((value << 16) + 0x8000) >> num_bits
+    }
+}
+
+// Scales an interpolated value to the bits of a half-float.
+fn finish_unquantize_bc6h(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        0x8000 | ((-value * 31) >> 5) as u16
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 31) as i32;
+    let mantissa = (half & 0x3FF) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2.0f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2.0f32.powi(exponent - 15),
+    }
+}
+
+/*
+ * ETC2 and EAC.
+ */
+const ETC_MODIFIERS: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn clamp_u8(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+fn add(color: [i32; 3], value: i32) -> [u8; 4] {
+    [
+        clamp_u8(color[0] + value),
+        clamp_u8(color[1] + value),
+        clamp_u8(color[2] + value),
+        255,
+    ]
+}
+
+fn extend4(color: [i32; 3]) -> [i32; 3] {
+    color.map(|c| c * 17)
+}
+
+fn extend5(color: [i32; 3]) -> [i32; 3] {
+    color.map(|c| c << 3 | c >> 2)
+}
+
+// Decodes an ETC2 RGB block. With punch-through alpha, the differential bit tells whether the
+// block is opaque, and the transparent pixels are black.
+fn decode_etc2(block: &[u8], out: &mut [[u8; 4]; 16], punchthrough: bool) {
+    let b = |i: usize| block[i] as i32;
+    let diff_bit = block[3] & 2 != 0;
+    let opaque = !punchthrough || diff_bit;
+    let differential = punchthrough || diff_bit;
+
+    // The index of each pixel, stored column by column.
+    let msb = u16::from_be_bytes([block[4], block[5]]);
+    let lsb = u16::from_be_bytes([block[6], block[7]]);
+    let index = |x: usize, y: usize| {
+        let j = x * 4 + y;
+        ((msb >> j) & 1) << 1 | ((lsb >> j) & 1)
+    };
+    let transparent = |i: u16| !opaque && i == 2;
+
+    // Blocks whose second differential color overflows use the T, H or planar modes.
+    let base = [b(0) >> 3, b(1) >> 3, b(2) >> 3];
+    let delta = [
+        sign_extend(b(0) & 7, 3),
+        sign_extend(b(1) & 7, 3),
+        sign_extend(b(2) & 7, 3),
+    ];
+    let overflows = |c: usize| differential && !(0..32).contains(&(base[c] + delta[c]));
+
+    let paint = if overflows(0) {
+        // T mode.
+        let c0 = extend4([((b(0) >> 1) & 0xC) | (b(0) & 3), b(1) >> 4, b(1) & 15]);
+        let c1 = extend4([b(2) >> 4, b(2) & 15, b(3) >> 4]);
+        let d = ETC_DISTANCES[(((b(3) >> 1) & 6) | (b(3) & 1)) as usize];
+        [add(c0, 0), add(c1, d), add(c1, 0), add(c1, -d)]
+    } else if overflows(1) {
+        // H mode.
+        let c0 = [
+            (b(0) >> 3) & 15,
+            ((b(0) & 7) << 1) | ((b(1) >> 4) & 1),
+            (b(1) & 8) | ((b(1) & 3) << 1) | (b(2) >> 7),
+        ];
+        let c1 = [
+            (b(2) >> 3) & 15,
+            ((b(2) & 7) << 1) | (b(3) >> 7),
+            (b(3) >> 3) & 15,
+        ];
+        let order = |c: [i32; 3]| c[0] << 8 | c[1] << 4 | c[2];
+        let d = ETC_DISTANCES
+            [((b(3) & 4) | ((b(3) & 1) << 1) | (order(c0) >= order(c1)) as i32) as usize];
+        let (c0, c1) = (extend4(c0), extend4(c1));
+        [add(c0, d), add(c0, -d), add(c1, d), add(c1, -d)]
+    } else if overflows(2) {
+        decode_etc2_planar(block, out);
+        return;
+    } else {
+        let (c0, c1) = if differential {
+            let c1 = [0, 1, 2].map(|c| base[c] + delta[c]);
+            (extend5(base), extend5(c1))
+        } else {
+            (
+                extend4([b(0) >> 4, b(1) >> 4, b(2) >> 4]),
+                extend4([b(0) & 15, b(1) & 15, b(2) & 15]),
+            )
+        };
+        let tables = [(b(3) >> 5) as usize, ((b(3) >> 2) & 7) as usize];
+        let flip = block[3] & 1 != 0;
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let second = if flip { y >= 2 } else { x >= 2 };
+                let (color, table) = if second {
+                    (c1, tables[1])
+                } else {
+                    (c0, tables[0])
+                };
+                let i = index(x, y);
+                // Without opacity, the smaller modifiers are replaced by the base color.
+                let modifier = if !opaque && i % 2 == 0 {
+                    0
+                } else {
+                    ETC_MODIFIERS[table][i as usize]
+                };
+
+                out[y * 4 + x] = if transparent(i) {
+                    [0; 4]
+                } else {
+                    add(color, modifier)
+                };
+            }
+        }
+
+        return;
+    };
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let i = index(x, y);
+            out[y * 4 + x] = if transparent(i) {
+                [0; 4]
+            } else {
+                paint[i as usize]
+            };
+        }
+    }
+}
+
+// The planar mode, where colors are interpolated from the origin, right and bottom colors.
+fn decode_etc2_planar(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let b = |i: usize| block[i] as i32;
+    let extend6 = |c: i32| c << 2 | c >> 4;
+    let extend7 = |c: i32| c << 1 | c >> 6;
+
+    let origin = [
+        extend6((b(0) >> 1) & 0x3F),
+        extend7(((b(0) & 1) << 6) | ((b(1) >> 1) & 0x3F)),
+        extend6(((b(1) & 1) << 5) | (b(2) & 0x18) | ((b(2) & 3) << 1) | (b(3) >> 7)),
+    ];
+    let horizontal = [
+        extend6(((b(3) >> 1) & 0x3E) | (b(3) & 1)),
+        extend7(b(4) >> 1),
+        extend6(((b(4) & 1) << 5) | (b(5) >> 3)),
+    ];
+    let vertical = [
+        extend6(((b(5) & 7) << 3) | (b(6) >> 5)),
+        extend7(((b(6) & 0x1F) << 2) | (b(7) >> 6)),
+        extend6(b(7) & 0x3F),
+    ];
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let pixel = &mut out[y * 4 + x];
+
+            for c in 0..3 {
+                let value = x as i32 * (horizontal[c] - origin[c])
+                    + y as i32 * (vertical[c] - origin[c])
+                    + 4 * origin[c]
+                    + 2;
+                pixel[c] = clamp_u8(value >> 2);
+            }
+
+            pixel[3] = 255;
+        }
+    }
+}
+
+fn decode_etc2_eac(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_etc2(&block[8..], out, false);
+
+    let base = block[0] as i32;
+    let multiplier = (block[1] >> 4) as i32;
+    let modifiers = &EAC_MODIFIERS[(block[1] & 15) as usize];
+    let bits = block[2..8]
+        .iter()
+        .fold(0u64, |bits, b| bits << 8 | *b as u64);
+
+    for x in 0..4 {
+        for y in 0..4 {
+            let index = (bits >> (45 - 3 * (x * 4 + y))) as usize & 7;
+            out[y * 4 + x][3] = clamp_u8(base + modifiers[index] * multiplier);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packs fields of the given number of bits into a 128-bit block, least significant first.
+    fn pack(fields: &[(u32, u128)]) -> [u8; 16] {
+        let (mut block, mut shift) = (0u128, 0);
+
+        for &(num_bits, value) in fields {
+            block |= value << shift;
+            shift += num_bits;
+        }
+
+        assert_eq!(shift, 128);
+        block.to_le_bytes()
+    }
+
+    #[test]
+    fn bc1_blocks_have_four_or_three_colors() {
+        // Red and blue endpoints, with the indices 0 to 3 on the first row.
+        let mut block = [0x00, 0xF8, 0x1F, 0x00, 0b11100100, 0, 0, 0];
+        let mut out = [[0; 4]; 16];
+
+        decode_bc1(&block, &mut out, true, false);
+        assert_eq!(
+            out[..4],
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 255],
+                [170, 0, 85, 255],
+                [85, 0, 170, 255]
+            ]
+        );
+
+        // The same endpoints swapped select the three-color mode.
+        block[..4].copy_from_slice(&[0x1F, 0x00, 0x00, 0xF8]);
+        decode_bc1(&block, &mut out, true, false);
+        assert_eq!(out[2], [128, 0, 128, 255]);
+        assert_eq!(out[3], [0, 0, 0, 0]);
+
+        decode_bc1(&block, &mut out, false, false);
+        assert_eq!(out[3], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc2_and_bc3_blocks_have_explicit_and_interpolated_alpha() {
+        let mut block = [0; 16];
+        block[0] = 0xF0;
+        block[8..12].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        let mut out = [[0; 4]; 16];
+
+        decode_bc2(&block, &mut out);
+        assert_eq!(out[0], [255, 255, 255, 0]);
+        assert_eq!(out[1], [255, 255, 255, 255]);
+        assert_eq!(out[2][3], 0);
+
+        // Alpha endpoints 255 and 0, with the indices 0, 1, 2 and 7.
+        block[..8].copy_from_slice(&[255, 0, 0x88, 0x0E, 0, 0, 0, 0]);
+        decode_bc3(&block, &mut out);
+        let alpha: Vec<_> = out[..4].iter().map(|p| p[3]).collect();
+        assert_eq!(alpha, [255, 0, 219, 36]);
+    }
+
+    #[test]
+    fn bc4_blocks_with_ordered_endpoints_have_six_values() {
+        // Endpoints 0 and 255, with the indices 2, 5, 6 and 7.
+        let block = [0, 255, 0xAA, 0x0F, 0, 0, 0, 0];
+        let values = decode_bc4(&block);
+
+        assert_eq!(values[..4], [51, 204, 0, 255]);
+        assert!(values[4..].iter().all(|v| *v == 0));
+    }
+
+    #[test]
+    fn bc7_mode_6_interpolates_color_and_alpha() {
+        // Black and white endpoints with their p-bits, and the indices 0, 15 and 8.
+        let mut fields = vec![(7, 1 << 6)];
+        fields.extend([(7, 0), (7, 0x7F)].repeat(4));
+        fields.extend([(1, 0), (1, 1), (3, 0), (4, 15), (4, 8), (52, 0)]);
+        let block = pack(&fields);
+        let mut out = [[0; 4]; 16];
+        decode_bc7(&block, &mut out);
+
+        assert_eq!(out[..4], [[0; 4], [255; 4], [135; 4], [0; 4]]);
+
+        // Blocks without a mode bit are transparent black.
+        decode_bc7(&[0; 16], &mut out);
+        assert_eq!(out, [[0; 4]; 16]);
+    }
+
+    #[test]
+    fn bc6h_mode_11_interpolates_half_floats() {
+        // 10-bit endpoints at 0 and the maximum, with the indices 0 and 15.
+        let block = pack(&[
+            (5, 0b00011),
+            (30, 0),
+            (30, (1 << 30) - 1),
+            (3, 0),
+            (4, 15),
+            (56, 0),
+        ]);
+        let mut out = [[0.0; 3]; 16];
+        decode_bc6h(&block, &mut out, false);
+
+        assert_eq!(out[0], [0.0; 3]);
+        assert_eq!(out[1], [65504.0; 3]);
+
+        // Reserved modes decode to black.
+        out = [[1.0; 3]; 16];
+        decode_bc6h(&pack(&[(5, 0b10011), (123, 1)]), &mut out, false);
+        assert_eq!(out, [[0.0; 3]; 16]);
+    }
+
+    #[test]
+    fn images_are_cropped_to_their_size() {
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0b01, 0b01, 0, 0];
+        let image = decode(TextureFormat::Bc1, 2, 2, &block).to_rgb8();
+
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(0, 1).0, [0, 0, 255]);
+    }
+}
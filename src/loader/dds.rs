@@ -0,0 +1,271 @@
+use std::fs;
+use std::path::Path;
+
+use crate::loader::texture_file::{TextureFile, TextureFormat};
+
+// The magic number and the DDS_HEADER structure.
+const HEADER_BYTES: usize = 128;
+// The DDS_HEADER_DXT10 structure following the header of files with the `DX10` FourCC.
+const DX10_HEADER_BYTES: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+/// Loads a DDS file holding a 2D texture, with its mip levels.
+///
+/// Block-compressed formats, and 24 or 32-bit RGB(A) and BGR(A) pixels are supported.
+pub fn load_dds(file_path: &Path) -> Result<TextureFile, String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    parse_dds(&data)
+}
+
+/// Parses the content of a DDS file.
+pub fn parse_dds(data: &[u8]) -> Result<TextureFile, String> {
+    if data.len() < HEADER_BYTES || &data[..4] != b"DDS " {
+        return Err("Not a DDS file".to_string());
+    }
+
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+
+    let flags = read_u32(8);
+    let height = read_u32(12);
+    let width = read_u32(16);
+    let num_levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+        read_u32(28).max(1) as usize
+    } else {
+        1
+    };
+    let pf_flags = read_u32(80);
+    let four_cc = &data[84..88];
+    let caps2 = read_u32(112);
+
+    if caps2 & (DDSCAPS2_CUBEMAP | DDSCAPS2_VOLUME) != 0 {
+        return Err("Only 2D DDS textures are supported".to_string());
+    }
+
+    let mut offset = HEADER_BYTES;
+    // Whether the red and blue channels are swapped.
+    let mut bgr = false;
+
+    let (format, srgb) = if pf_flags & DDPF_FOURCC != 0 {
+        match four_cc {
+            b"DXT1" => (TextureFormat::Bc1Alpha, false),
+            b"DXT2" | b"DXT3" => (TextureFormat::Bc2, false),
+            b"DXT4" | b"DXT5" => (TextureFormat::Bc3, false),
+            b"ATI1" | b"BC4U" => (TextureFormat::Bc4, false),
+            b"ATI2" | b"BC5U" => (TextureFormat::Bc5, false),
+            b"DX10" => {
+                if data.len() < HEADER_BYTES + DX10_HEADER_BYTES {
+                    return Err("Truncated DDS DX10 header".to_string());
+                }
+
+                let dxgi_format = read_u32(128);
+                let dimension = read_u32(132);
+                let misc_flags = read_u32(136);
+                let array_size = read_u32(140);
+
+                if dimension != RESOURCE_DIMENSION_TEXTURE2D
+                    || misc_flags & RESOURCE_MISC_TEXTURECUBE != 0
+                    || array_size > 1
+                {
+                    return Err("Only 2D DDS textures are supported".to_string());
+                }
+
+                offset += DX10_HEADER_BYTES;
+                bgr = dxgi_format == 87 || dxgi_format == 91;
+                dxgi_format_to_format(dxgi_format).ok_or_else(|| {
+                    format!("Unsupported DDS pixel format: DXGI format {}", dxgi_format)
+                })?
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported DDS pixel format: {}",
+                    String::from_utf8_lossy(four_cc)
+                ))
+            }
+        }
+    } else if pf_flags & DDPF_RGB != 0 {
+        let bits = read_u32(88);
+        let red_mask = read_u32(92);
+        let alpha = pf_flags & DDPF_ALPHAPIXELS != 0;
+
+        bgr = red_mask == 0xff0000;
+        match (bits, alpha) {
+            (32, true) if red_mask == 0xff || bgr => (TextureFormat::Rgba8, false),
+            (24, false) if red_mask == 0xff || bgr => (TextureFormat::Rgb8, false),
+            _ => {
+                return Err(format!(
+                    "Unsupported DDS pixel format: {} bits RGB with red mask {:#x}",
+                    bits, red_mask
+                ))
+            }
+        }
+    } else {
+        return Err("Unsupported DDS pixel format".to_string());
+    };
+
+    let mut levels = Vec::with_capacity(num_levels);
+    let (mut w, mut h) = (width, height);
+
+    for i in 0..num_levels {
+        let length = format.image_bytes(w.max(1), h.max(1));
+        let mut level = data
+            .get(offset..offset + length)
+            .ok_or_else(|| format!("Truncated DDS mip level {}", i))?
+            .to_vec();
+
+        if bgr {
+            let channels = if format == TextureFormat::Rgba8 { 4 } else { 3 };
+            level.chunks_exact_mut(channels).for_each(|p| p.swap(0, 2));
+        }
+
+        levels.push(level);
+        offset += length;
+        w /= 2;
+        h /= 2;
+    }
+
+    TextureFile::new(format, srgb, width, height, levels)
+}
+
+// The texture format and whether it is sRGB, from a DXGI format number.
+fn dxgi_format_to_format(dxgi_format: u32) -> Option<(TextureFormat, bool)> {
+    let format = match dxgi_format {
+        28 => (TextureFormat::Rgba8, false),
+        29 => (TextureFormat::Rgba8, true),
+        71 => (TextureFormat::Bc1Alpha, false),
+        72 => (TextureFormat::Bc1Alpha, true),
+        74 => (TextureFormat::Bc2, false),
+        75 => (TextureFormat::Bc2, true),
+        77 => (TextureFormat::Bc3, false),
+        78 => (TextureFormat::Bc3, true),
+        80 => (TextureFormat::Bc4, false),
+        83 => (TextureFormat::Bc5, false),
+        87 => (TextureFormat::Rgba8, false),
+        91 => (TextureFormat::Rgba8, true),
+        95 => (TextureFormat::Bc6hUfloat, false),
+        96 => (TextureFormat::Bc6hSfloat, false),
+        98 => (TextureFormat::Bc7, false),
+        99 => (TextureFormat::Bc7, true),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A DDS header for a texture of the given size and mip levels, with an empty pixel format.
+    fn header(width: u32, height: u32, num_levels: u32) -> Vec<u8> {
+        let mut data = vec![0; HEADER_BYTES];
+        let mut write_u32 = |offset: usize, value: u32| {
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+
+        write_u32(8, DDSD_MIPMAPCOUNT);
+        write_u32(12, height);
+        write_u32(16, width);
+        write_u32(28, num_levels);
+        data[..4].copy_from_slice(b"DDS ");
+        data
+    }
+
+    fn four_cc(width: u32, height: u32, num_levels: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut data = header(width, height, num_levels);
+        data[80..84].copy_from_slice(&DDPF_FOURCC.to_le_bytes());
+        data[84..88].copy_from_slice(four_cc);
+        data
+    }
+
+    #[test]
+    fn four_cc_files_are_read_with_their_mip_levels() {
+        let mut data = four_cc(8, 4, 4, b"DXT1");
+        // 2x1 blocks, then a single block for each of the 4x2, 2x1 and 1x1 levels.
+        data.extend((0..40).map(|i| i as u8));
+        let file = parse_dds(&data).unwrap();
+
+        assert_eq!(file.format, TextureFormat::Bc1Alpha);
+        assert_eq!(file.levels.len(), 4);
+        assert_eq!(file.levels[0], (0..16).collect::<Vec<u8>>());
+        assert_eq!(file.levels[3], (32..40).collect::<Vec<u8>>());
+        assert!(file.has_full_mip_chain());
+    }
+
+    #[test]
+    fn dx10_files_are_read_after_their_extra_header() {
+        let mut data = four_cc(4, 4, 1, b"DX10");
+
+        for field in [99, RESOURCE_DIMENSION_TEXTURE2D, 0, 1, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        data.extend_from_slice(&[5; 16]);
+        let file = parse_dds(&data).unwrap();
+
+        assert_eq!(file.format, TextureFormat::Bc7);
+        assert!(file.srgb);
+        assert_eq!(file.levels, [vec![5; 16]]);
+
+        // A cube map.
+        data[136] = RESOURCE_MISC_TEXTURECUBE as u8;
+        assert!(parse_dds(&data).is_err());
+        assert_eq!(
+            parse_dds(&data[..HEADER_BYTES + 8]).err().unwrap(),
+            "Truncated DDS DX10 header"
+        );
+    }
+
+    #[test]
+    fn bgra_pixels_are_swapped_to_rgba() {
+        let mut data = header(2, 1, 1);
+        data[80..84].copy_from_slice(&(DDPF_RGB | DDPF_ALPHAPIXELS).to_le_bytes());
+        data[88..92].copy_from_slice(&32u32.to_le_bytes());
+        data[92..96].copy_from_slice(&0xff0000u32.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let file = parse_dds(&data).unwrap();
+
+        assert_eq!(file.format, TextureFormat::Rgba8);
+        assert_eq!(file.levels, [vec![3, 2, 1, 4, 7, 6, 5, 8]]);
+    }
+
+    #[test]
+    fn unsupported_files_are_rejected() {
+        let mut data = four_cc(4, 4, 1, b"DXT5");
+        data.extend_from_slice(&[0; 16]);
+        assert!(parse_dds(&data).is_ok());
+
+        assert_eq!(
+            parse_dds(&data[..data.len() - 1]).err().unwrap(),
+            "Truncated DDS mip level 0"
+        );
+
+        let mut cube = data.clone();
+        cube[112..116].copy_from_slice(&DDSCAPS2_CUBEMAP.to_le_bytes());
+        assert!(parse_dds(&cube).is_err());
+
+        let mut unknown = data.clone();
+        unknown[84..88].copy_from_slice(b"ETC2");
+        assert_eq!(
+            parse_dds(&unknown).err().unwrap(),
+            "Unsupported DDS pixel format: ETC2"
+        );
+
+        data[0] = b'X';
+        assert_eq!(parse_dds(&data).err().unwrap(), "Not a DDS file");
+    }
+}
@@ -1,36 +1,77 @@
 use gltf::Gltf;
 use gltf::mesh::Mode;
-use nalgebra::{Point3, Vector3};
-use std::path::Path;
+use image::{DynamicImage, ImageOutputFormat};
+use nalgebra::{Point2, Point3, Quaternion, UnitQuaternion, Vector3};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 
-pub struct Mesh {
+use crate::loader::texture_file;
+use crate::resource::mesh::Primitive;
+
+/// A node of a glTF scene, with its transform relative to its parent.
+pub struct Node {
+    /// The name of the node, or of its mesh if the node is unnamed.
     pub name: Option<String>,
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+    /// One mesh per primitive of the mesh of the node.
+    pub meshes: Vec<Mesh>,
+    pub children: Vec<Node>,
+}
+
+pub struct Mesh {
     pub vertices: Vec<Point3<f32>>,
-    pub normals: Vec<Vector3<f32>>,
+    /// The normals, which glTF files may leave out.
+    pub normals: Option<Vec<Vector3<f32>>>,
+    /// Three indices per element, stored like the faces of a mesh of `primitive`.
     pub indices: Vec<u32>,
+    pub primitive: Primitive,
 }
 
-pub fn load_glb(file_path: &Path) -> Result<Vec<Mesh>, String> {
+/// Reads the root nodes of the default scene of a glTF file, or of its first scene if it does
+/// not tell which one is the default.
+pub fn load_glb(file_path: &Path) -> Result<Vec<Node>, String> {
     if !file_path.exists() {
         return Err(format!("File not found: {:?}", file_path));
     }
 
     let file_data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
-    let gltf = Gltf::from_slice(&file_data).map_err(|e| format!("Failed to parse GLB: {}", e))?;
+    let mut gltf = Gltf::from_slice(&file_data).map_err(|e| format!("Failed to parse GLB: {}", e))?;
+    // `.gltf` files may reference external `.bin` buffers, resolved relative to the file.
+    let buffers = gltf::import_buffers(&gltf.document, file_path.parent(), gltf.blob.take())
+        .map_err(|e| format!("Failed to load buffers: {}", e))?;
 
+    let scene = match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        Some(scene) => scene,
+        None => return Ok(Vec::new()),
+    };
+
+    scene
+        .nodes()
+        .map(|node| load_node(&node, &buffers))
+        .collect()
+}
+
+fn load_node(node: &gltf::Node, buffers: &[gltf::buffer::Data]) -> Result<Node, String> {
+    let (translation, rotation, scale) = node.transform().decomposed();
     let mut meshes = Vec::new();
 
-    for mesh in gltf.meshes() {
+    if let Some(mesh) = node.mesh() {
         for primitive in mesh.primitives() {
-            if primitive.mode() != Mode::Triangles {
-                return Err(format!("Unsupported primitive mode: {:?}", primitive.mode()));
-            }
+            let kind = match primitive.mode() {
+                Mode::Triangles => Primitive::Triangles,
+                Mode::Lines => Primitive::Lines,
+                Mode::Points => Primitive::Points,
+                mode => return Err(format!("Unsupported primitive mode: {:?}", mode)),
+            };
 
-            let reader = primitive.reader(|buffer| {
-                gltf.blob.as_deref()
-            });
+            let reader =
+                primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
 
             let vertices: Vec<Point3<f32>> = reader
                 .read_positions()
@@ -38,26 +79,354 @@ pub fn load_glb(file_path: &Path) -> Result<Vec<Mesh>, String> {
                 .map(|v| Point3::new(v[0], v[1], v[2]))
                 .collect();
 
-            let normals: Vec<Vector3<f32>> = reader
+            let normals: Option<Vec<Vector3<f32>>> = reader
                 .read_normals()
-                .ok_or("Failed to read normals")?
-                .map(|n| Vector3::new(n[0], n[1], n[2]))
-                .collect();
+                .map(|ns| ns.map(|n| Vector3::new(n[0], n[1], n[2])).collect());
+            // Lines and points have no surface, a constant normal lights them evenly.
+            let normals = match kind {
+                Primitive::Triangles => normals,
+                _ => normals.or_else(|| Some(vec![Vector3::z(); vertices.len()])),
+            };
 
-            let indices: Vec<u32> = reader
-                .read_indices()
-                .ok_or("Failed to read indices")?
-                .into_u32()
-                .collect();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None if kind != Primitive::Triangles => (0..vertices.len() as u32).collect(),
+                None => return Err("Failed to read indices".to_string()),
+            };
+
+            if !indices.len().is_multiple_of(kind.num_vertices()) {
+                return Err(format!(
+                    "The number of indices of a {:?} primitive is not a multiple of {}",
+                    kind,
+                    kind.num_vertices()
+                ));
+            }
 
             meshes.push(Mesh {
-                name: mesh.name().map(|s| s.to_string()),
                 vertices,
                 normals,
-                indices,
+                indices: indices
+                    .chunks(kind.num_vertices())
+                    .flat_map(|element| kind.face(element))
+                    .collect(),
+                primitive: kind,
             });
         }
     }
 
-    Ok(meshes)
+    let mut children = Vec::new();
+
+    for child in node.children() {
+        children.push(load_node(&child, buffers)?);
+    }
+
+    Ok(Node {
+        name: node
+            .name()
+            .or_else(|| node.mesh().and_then(|m| m.name()))
+            .map(|s| s.to_string()),
+        translation: Vector3::from(translation),
+        // glTF quaternions are `[x, y, z, w]`.
+        rotation: UnitQuaternion::from_quaternion(Quaternion::new(
+            rotation[3],
+            rotation[0],
+            rotation[1],
+            rotation[2],
+        )),
+        scale: Vector3::from(scale),
+        meshes,
+        children,
+    })
+}
+
+/// A node written by `write_glb`.
+pub struct GlbNode {
+    pub name: Option<String>,
+    pub translation: [f32; 3],
+    /// The rotation, as a unit quaternion `[i, j, k, w]`.
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub mesh: Option<GlbMesh>,
+    pub children: Vec<GlbNode>,
+}
+
+/// A mesh written by `write_glb`, with a single base-color material.
+pub struct GlbMesh {
+    pub positions: Vec<Point3<f32>>,
+    /// Either empty or one normal per vertex.
+    pub normals: Vec<Vector3<f32>>,
+    /// Either empty or one texture coordinate per vertex.
+    pub uvs: Vec<Point2<f32>>,
+    /// Three indices per element, stored like the faces of a mesh of `primitive`.
+    pub indices: Vec<u32>,
+    pub primitive: Primitive,
+    pub base_color: [f32; 4],
+    /// An image file embedded as the base color texture.
+    pub texture: Option<PathBuf>,
+}
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+#[derive(Default)]
+struct GlbBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    nodes: Vec<Value>,
+    // Index of the texture of each embedded image file.
+    image_textures: HashMap<PathBuf, usize>,
+}
+
+impl GlbBuilder {
+    fn push_buffer_view(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        // Accessors require their data to be aligned to their component size.
+        while !self.buffer.len().is_multiple_of(4) {
+            self.buffer.push(0);
+        }
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": data.len(),
+        });
+
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+
+        self.buffer.extend_from_slice(data);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(&mut self, data: &[u8], target: u32, accessor: Value) -> usize {
+        let view = self.push_buffer_view(data, Some(target));
+        let mut accessor = accessor;
+        accessor["bufferView"] = json!(view);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_texture(&mut self, path: &Path) -> Result<usize, String> {
+        if let Some(texture) = self.image_textures.get(path) {
+            return Ok(*texture);
+        }
+
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        // glTF only allows PNG and JPEG images, other formats are converted to PNG.
+        let (data, mime_type) = match extension.as_str() {
+            "png" => (fs::read(path).map_err(|e| e.to_string())?, "image/png"),
+            "jpg" | "jpeg" => (fs::read(path).map_err(|e| e.to_string())?, "image/jpeg"),
+            _ => {
+                let image = if texture_file::is_texture_file(path) {
+                    // PNG cannot store the floating-point images of BC6H files.
+                    let file = texture_file::load_texture_file(path)?;
+                    DynamicImage::ImageRgba8(file.decode_level(0).into_rgba8())
+                } else {
+                    image::open(path).map_err(|e| e.to_string())?
+                };
+                let mut data = Cursor::new(Vec::new());
+                image
+                    .write_to(&mut data, ImageOutputFormat::Png)
+                    .map_err(|e| e.to_string())?;
+                (data.into_inner(), "image/png")
+            }
+        };
+
+        let view = self.push_buffer_view(&data, None);
+        self.images.push(json!({ "bufferView": view, "mimeType": mime_type }));
+        self.textures
+            .push(json!({ "sampler": 0, "source": self.images.len() - 1 }));
+
+        let texture = self.textures.len() - 1;
+        let _ = self.image_textures.insert(path.to_path_buf(), texture);
+        Ok(texture)
+    }
+
+    fn push_mesh(&mut self, mesh: &GlbMesh) -> Result<usize, String> {
+        let mut mins = [f32::MAX; 3];
+        let mut maxs = [f32::MIN; 3];
+
+        for p in mesh.positions.iter() {
+            for i in 0..3 {
+                mins[i] = mins[i].min(p[i]);
+                maxs[i] = maxs[i].max(p[i]);
+            }
+        }
+
+        let positions = self.push_accessor(
+            &f32_bytes(mesh.positions.iter().flat_map(|p| p.coords.iter())),
+            ARRAY_BUFFER,
+            json!({
+                "componentType": FLOAT,
+                "count": mesh.positions.len(),
+                "type": "VEC3",
+                "min": mins,
+                "max": maxs,
+            }),
+        );
+        let mut attributes = json!({ "POSITION": positions });
+
+        if mesh.normals.len() == mesh.positions.len() {
+            let normals = self.push_accessor(
+                &f32_bytes(mesh.normals.iter().flat_map(|n| n.iter())),
+                ARRAY_BUFFER,
+                json!({ "componentType": FLOAT, "count": mesh.normals.len(), "type": "VEC3" }),
+            );
+            attributes["NORMAL"] = json!(normals);
+        }
+
+        if mesh.uvs.len() == mesh.positions.len() {
+            let uvs = self.push_accessor(
+                &f32_bytes(mesh.uvs.iter().flat_map(|u| u.coords.iter())),
+                ARRAY_BUFFER,
+                json!({ "componentType": FLOAT, "count": mesh.uvs.len(), "type": "VEC2" }),
+            );
+            attributes["TEXCOORD_0"] = json!(uvs);
+        }
+
+        let num_vertices = mesh.primitive.num_vertices();
+        let indices: Vec<u32> = mesh
+            .indices
+            .chunks(3)
+            .flat_map(|face| face[..num_vertices].iter().copied())
+            .collect();
+        let index_bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let indices = self.push_accessor(
+            &index_bytes,
+            ELEMENT_ARRAY_BUFFER,
+            json!({ "componentType": UNSIGNED_INT, "count": indices.len(), "type": "SCALAR" }),
+        );
+        let mode = match mesh.primitive {
+            Primitive::Points => 0,
+            Primitive::Lines => 1,
+            Primitive::Triangles => 4,
+        };
+
+        let mut pbr = json!({
+            "baseColorFactor": mesh.base_color,
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        });
+
+        if let Some(ref texture) = mesh.texture {
+            let texture = self.push_texture(texture)?;
+            pbr["baseColorTexture"] = json!({ "index": texture });
+        }
+
+        self.materials.push(json!({ "pbrMetallicRoughness": pbr }));
+        self.meshes.push(json!({
+            "primitives": [{
+                "attributes": attributes,
+                "indices": indices,
+                "material": self.materials.len() - 1,
+                "mode": mode,
+            }]
+        }));
+
+        Ok(self.meshes.len() - 1)
+    }
+
+    fn push_node(&mut self, node: &GlbNode) -> Result<usize, String> {
+        let mut children = Vec::new();
+
+        for child in node.children.iter() {
+            children.push(self.push_node(child)?);
+        }
+
+        let mut value = json!({
+            "translation": node.translation,
+            "rotation": node.rotation,
+            "scale": node.scale,
+        });
+
+        if let Some(ref name) = node.name {
+            value["name"] = json!(name);
+        }
+
+        if let Some(ref mesh) = node.mesh {
+            if !mesh.positions.is_empty() && !mesh.indices.is_empty() {
+                value["mesh"] = json!(self.push_mesh(mesh)?);
+            }
+        }
+
+        if !children.is_empty() {
+            value["children"] = json!(children);
+        }
+
+        self.nodes.push(value);
+        Ok(self.nodes.len() - 1)
+    }
+}
+
+/// Writes a node hierarchy to a glTF 2.0 binary file, with its textures embedded.
+pub fn write_glb(file_path: &Path, root: &GlbNode) -> Result<(), String> {
+    let mut builder = GlbBuilder::default();
+    let root = builder.push_node(root)?;
+
+    while !builder.buffer.len().is_multiple_of(4) {
+        builder.buffer.push(0);
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "starman" },
+        "scene": 0,
+        "scenes": [{ "nodes": [root] }],
+        "nodes": builder.nodes,
+        "buffers": [{ "byteLength": builder.buffer.len() }],
+    });
+
+    let optional = [
+        ("bufferViews", builder.buffer_views),
+        ("accessors", builder.accessors),
+        ("meshes", builder.meshes),
+        ("materials", builder.materials),
+        ("textures", builder.textures),
+        ("images", builder.images),
+    ];
+
+    for (key, values) in optional {
+        if !values.is_empty() {
+            document[key] = Value::Array(values);
+        }
+    }
+
+    if !builder.image_textures.is_empty() {
+        document["samplers"] = json!([{ "wrapS": 10497, "wrapT": 10497 }]);
+    }
+
+    let mut json_chunk = serde_json::to_vec(&document).map_err(|e| e.to_string())?;
+
+    while !json_chunk.len().is_multiple_of(4) {
+        json_chunk.push(b' ');
+    }
+
+    let total_length = 12 + 8 + json_chunk.len() + 8 + builder.buffer.len();
+    let mut out = Vec::with_capacity(total_length);
+    out.extend_from_slice(b"glTF");
+    out.extend_from_slice(&2u32.to_le_bytes());
+    out.extend_from_slice(&(total_length as u32).to_le_bytes());
+    out.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"JSON");
+    out.extend_from_slice(&json_chunk);
+    out.extend_from_slice(&(builder.buffer.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"BIN\0");
+    out.extend_from_slice(&builder.buffer);
+
+    fs::write(file_path, out).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+fn f32_bytes<'a, I: Iterator<Item = &'a f32>>(values: I) -> Vec<u8> {
+    values.flat_map(|v| v.to_le_bytes()).collect()
 }
@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+
+use crate::loader::texture_file::{TextureFile, TextureFormat};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+// The identifier, the header and the index, before the level index.
+const HEADER_BYTES: usize = 80;
+
+/// Loads a KTX2 file holding a 2D texture, with its mip levels.
+///
+/// Supercompressed files (Basis Universal, Zstandard) are not supported.
+pub fn load_ktx2(file_path: &Path) -> Result<TextureFile, String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    parse_ktx2(&data)
+}
+
+/// Parses the content of a KTX2 file.
+pub fn parse_ktx2(data: &[u8]) -> Result<TextureFile, String> {
+    if data.len() < HEADER_BYTES || data[..12] != IDENTIFIER {
+        return Err("Not a KTX2 file".to_string());
+    }
+
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let read_u64 = |offset: usize| read_u32(offset) as u64 | (read_u32(offset + 4) as u64) << 32;
+
+    let vk_format = read_u32(12);
+    let width = read_u32(20);
+    let height = read_u32(24);
+    let depth = read_u32(28);
+    let layers = read_u32(32);
+    let faces = read_u32(36);
+    let num_levels = read_u32(40).max(1) as usize;
+    let supercompression = read_u32(44);
+
+    if supercompression != 0 {
+        return Err(format!(
+            "Supercompressed KTX2 files are not supported (scheme {})",
+            supercompression
+        ));
+    }
+
+    if depth > 0 || layers > 1 || faces != 1 || height == 0 {
+        return Err("Only 2D KTX2 textures are supported".to_string());
+    }
+
+    let (format, srgb) = vk_format_to_format(vk_format)
+        .ok_or_else(|| format!("Unsupported KTX2 pixel format: VkFormat {}", vk_format))?;
+
+    if data.len() < HEADER_BYTES + num_levels * 24 {
+        return Err("Truncated KTX2 level index".to_string());
+    }
+
+    let levels = (0..num_levels)
+        .map(|i| {
+            let entry = HEADER_BYTES + i * 24;
+            let offset = read_u64(entry) as usize;
+            let length = read_u64(entry + 8) as usize;
+
+            data.get(offset..offset.saturating_add(length))
+                .map(|level| level.to_vec())
+                .ok_or_else(|| format!("Truncated KTX2 mip level {}", i))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    TextureFile::new(format, srgb, width, height, levels)
+}
+
+// The texture format and whether it is sRGB, from a Vulkan format number.
+fn vk_format_to_format(vk_format: u32) -> Option<(TextureFormat, bool)> {
+    let format = match vk_format {
+        23 => (TextureFormat::Rgb8, false),
+        29 => (TextureFormat::Rgb8, true),
+        37 => (TextureFormat::Rgba8, false),
+        43 => (TextureFormat::Rgba8, true),
+        131 => (TextureFormat::Bc1, false),
+        132 => (TextureFormat::Bc1, true),
+        133 => (TextureFormat::Bc1Alpha, false),
+        134 => (TextureFormat::Bc1Alpha, true),
+        135 => (TextureFormat::Bc2, false),
+        136 => (TextureFormat::Bc2, true),
+        137 => (TextureFormat::Bc3, false),
+        138 => (TextureFormat::Bc3, true),
+        139 => (TextureFormat::Bc4, false),
+        141 => (TextureFormat::Bc5, false),
+        143 => (TextureFormat::Bc6hUfloat, false),
+        144 => (TextureFormat::Bc6hSfloat, false),
+        145 => (TextureFormat::Bc7, false),
+        146 => (TextureFormat::Bc7, true),
+        147 => (TextureFormat::Etc2Rgb8, false),
+        148 => (TextureFormat::Etc2Rgb8, true),
+        149 => (TextureFormat::Etc2Rgb8A1, false),
+        150 => (TextureFormat::Etc2Rgb8A1, true),
+        151 => (TextureFormat::Etc2Rgba8, false),
+        152 => (TextureFormat::Etc2Rgba8, true),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A KTX2 file with the given header fields, followed by the level index and the levels.
+    fn ktx2(vk_format: u32, size: (u32, u32), faces: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let mut data = IDENTIFIER.to_vec();
+        let num_levels = levels.len() as u32;
+
+        // The format, type size, size, depth, layers, faces, levels and supercompression.
+        for field in [vk_format, 1, size.0, size.1, 0, 0, faces, num_levels, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        data.resize(HEADER_BYTES, 0);
+        let mut offset = HEADER_BYTES + levels.len() * 24;
+
+        for level in levels {
+            for field in [offset, level.len(), level.len()] {
+                data.extend_from_slice(&(field as u64).to_le_bytes());
+            }
+
+            offset += level.len();
+        }
+
+        for level in levels {
+            data.extend_from_slice(level);
+        }
+
+        data
+    }
+
+    #[test]
+    fn levels_are_read_from_the_level_index() {
+        let level0: Vec<u8> = (0..16).collect();
+        let data = ktx2(43, (2, 2), 1, &[&level0, &[1, 2, 3, 4]]);
+        let file = parse_ktx2(&data).unwrap();
+
+        assert_eq!(file.format, TextureFormat::Rgba8);
+        assert!(file.srgb);
+        assert_eq!((file.width, file.height), (2, 2));
+        assert_eq!(file.levels, [level0, vec![1, 2, 3, 4]]);
+        assert!(file.has_full_mip_chain());
+    }
+
+    #[test]
+    fn block_compressed_levels_are_read() {
+        let data = ktx2(145, (5, 3), 1, &[&[7; 32]]);
+        let file = parse_ktx2(&data).unwrap();
+
+        assert_eq!(file.format, TextureFormat::Bc7);
+        assert!(!file.srgb);
+        assert_eq!(file.levels, [vec![7; 32]]);
+    }
+
+    #[test]
+    fn unsupported_files_are_rejected() {
+        let mut data = ktx2(37, (1, 1), 1, &[&[0; 4]]);
+        data[0] = 0;
+        assert_eq!(parse_ktx2(&data).err().unwrap(), "Not a KTX2 file");
+
+        let mut data = ktx2(37, (1, 1), 1, &[&[0; 4]]);
+        data[44] = 2;
+        assert!(parse_ktx2(&data)
+            .err()
+            .unwrap()
+            .starts_with("Supercompressed"));
+
+        let cube = ktx2(37, (1, 1), 6, &[&[0; 4]]);
+        assert!(parse_ktx2(&cube).is_err());
+
+        let unknown = ktx2(1000, (1, 1), 1, &[&[0; 4]]);
+        assert!(parse_ktx2(&unknown)
+            .err()
+            .unwrap()
+            .contains("VkFormat 1000"));
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let data = ktx2(37, (2, 2), 1, &[&[0; 16]]);
+
+        assert_eq!(
+            parse_ktx2(&data[..data.len() - 1]).err().unwrap(),
+            "Truncated KTX2 mip level 0"
+        );
+        assert_eq!(
+            parse_ktx2(&data[..HEADER_BYTES + 8]).err().unwrap(),
+            "Truncated KTX2 level index"
+        );
+
+        // A level of the wrong size for its dimensions.
+        let data = ktx2(37, (2, 2), 1, &[&[0; 12]]);
+        assert!(parse_ktx2(&data).is_err());
+    }
+}
@@ -1,3 +1,10 @@
 pub mod mtl;
 pub mod obj;
-pub mod glb;
\ No newline at end of file
+pub mod glb;
+pub mod model;
+pub mod ply;
+pub mod stl;
+pub mod block_decode;
+pub mod dds;
+pub mod ktx2;
+pub mod texture_file;
\ No newline at end of file
@@ -0,0 +1,515 @@
+//! Reading of model files, independent from the graphics context so it can run on any thread.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Result as IoResult};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use nalgebra::{Isometry3, Point2, Point3, Translation3, Vector3};
+
+use crate::loader::mtl::MtlMaterial;
+use crate::loader::{glb, obj, ply, stl};
+use crate::resource::mesh::Mesh;
+use crate::resource::vertex_index::VertexIndex;
+
+/// A triangle mesh indexed with `u32`: its name, vertices, optional normals and indices.
+pub type IndexedMesh = (
+    Option<String>,
+    Vec<Point3<f32>>,
+    Option<Vec<Vector3<f32>>>,
+    Vec<u32>,
+);
+
+/// The content of a model file, ready to be added to a scene with `SceneNode::add_model_data`.
+pub enum ModelData {
+    /// The meshes of an OBJ file, and the directory its materials were looked up in.
+    Obj {
+        meshes: Vec<obj::ObjMesh>,
+        mtl_dir: PathBuf,
+    },
+    /// The meshes of a glTF, STL or PLY file, as split by `indexed_mesh_chunks`, with the name
+    /// of the mesh they come from.
+    Indexed(Vec<(Option<String>, Mesh)>),
+    /// The root nodes of the scene of a glTF file.
+    Gltf(Vec<ModelNode>),
+}
+
+/// A mesh of a model, placed and named as `SceneNode::add_model_data` adds it to a scene.
+pub struct ModelPart<'a> {
+    pub name: String,
+    pub mesh: &'a Mesh,
+    /// The transform relative to the model, applied after `scale`.
+    pub transform: Isometry3<f32>,
+    pub scale: Vector3<f32>,
+    /// The material of an OBJ mesh.
+    pub material: Option<&'a MtlMaterial>,
+    /// The texture file of the material, if any.
+    pub texture: Option<PathBuf>,
+}
+
+impl<'a> ModelPart<'a> {
+    /// The vertices of the mesh, relative to the model.
+    pub fn vertices(&self) -> Vec<Point3<f32>> {
+        let coords = self.mesh.coords().read().unwrap();
+
+        coords
+            .data()
+            .iter()
+            .flatten()
+            .map(|v| self.transform * Point3::from(v.coords.component_mul(&self.scale)))
+            .collect()
+    }
+
+    /// The normals of the mesh, relative to the model.
+    pub fn normals(&self) -> Vec<Vector3<f32>> {
+        let normals = self.mesh.normals().read().unwrap();
+
+        normals
+            .data()
+            .iter()
+            .flatten()
+            .map(|n| {
+                (self.transform.rotation * n.component_div(&self.scale))
+                    .try_normalize(1.0e-12)
+                    .unwrap_or_else(Vector3::zeros)
+            })
+            .collect()
+    }
+
+    pub fn uvs(&self) -> Vec<Point2<f32>> {
+        let uvs = self.mesh.uvs().read().unwrap();
+        uvs.data().iter().flatten().cloned().collect()
+    }
+
+    /// The elements of the mesh, stored like its faces.
+    pub fn faces(&self) -> Vec<Point3<u32>> {
+        let faces = self.mesh.faces().read().unwrap();
+
+        faces
+            .data()
+            .iter()
+            .flatten()
+            .map(|f| Point3::new(f.x as u32, f.y as u32, f.z as u32))
+            .collect()
+    }
+
+    /// The diffuse color of the material, or white.
+    pub fn color(&self) -> Point3<f32> {
+        self.material
+            .map_or(Point3::new(1.0, 1.0, 1.0), |m| Point3::from(m.diffuse))
+    }
+}
+
+/// A node of a glTF scene, with its meshes split by `indexed_mesh_chunks`.
+pub struct ModelNode {
+    pub name: Option<String>,
+    /// The transform relative to the parent node, see `SceneNode::set_local_transformation`.
+    pub transform: Isometry3<f32>,
+    pub scale: Vector3<f32>,
+    pub meshes: Vec<Mesh>,
+    pub children: Vec<ModelNode>,
+}
+
+impl ModelNode {
+    // Scale does not propagate to the children translations in a scene graph as it does in
+    // glTF, so the translations are scaled by the scale of the parents instead. This is exact
+    // as long as the scales of the parents are uniform.
+    fn from_glb(node: glb::Node, parent_scale: &Vector3<f32>) -> Result<ModelNode, String> {
+        let translation = node.translation.component_mul(parent_scale);
+        let scale = parent_scale.component_mul(&node.scale);
+        let mut meshes = Vec::new();
+
+        for m in node.meshes {
+            for (_, mut mesh) in
+                indexed_mesh_chunks(vec![(None, m.vertices, m.normals, m.indices)])?
+            {
+                mesh.set_primitive(m.primitive);
+                meshes.push(mesh);
+            }
+        }
+
+        let mut children = Vec::new();
+
+        for child in node.children {
+            children.push(ModelNode::from_glb(child, &scale)?);
+        }
+
+        Ok(ModelNode {
+            name: node.name,
+            transform: Isometry3::from_parts(Translation3::from(translation), node.rotation),
+            scale: node.scale,
+            meshes,
+            children,
+        })
+    }
+
+    /// The number of nodes of this subtree.
+    pub fn num_nodes(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(ModelNode::num_nodes)
+            .sum::<usize>()
+    }
+
+    // The translations of the nodes already account for the scale of their parents, see
+    // `from_glb`.
+    fn collect_parts<'a>(
+        &'a self,
+        parent_transform: &Isometry3<f32>,
+        parent_scale: &Vector3<f32>,
+        parts: &mut Vec<ModelPart<'a>>,
+    ) {
+        let transform = parent_transform * self.transform;
+        let scale = parent_scale.component_mul(&self.scale);
+
+        for mesh in &self.meshes {
+            parts.push(ModelPart {
+                name: self.name.clone().unwrap_or_default(),
+                mesh,
+                transform,
+                scale,
+                material: None,
+                texture: None,
+            });
+        }
+
+        for child in &self.children {
+            child.collect_parts(&transform, &scale, parts);
+        }
+    }
+
+    /// Moves the meshes of this node and its descendants to `meshes`, depth-first, in the
+    /// order `SceneNode::add_model_data` creates their objects.
+    pub fn collect_meshes(self, meshes: &mut Vec<Mesh>) {
+        meshes.extend(self.meshes);
+
+        for child in self.children {
+            child.collect_meshes(meshes);
+        }
+    }
+}
+
+impl ModelData {
+    /// The texture files used by the materials of the model.
+    pub fn texture_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        if let ModelData::Obj {
+            ref meshes,
+            ref mtl_dir,
+        } = *self
+        {
+            for mtl in meshes.iter().filter_map(|m| m.material.as_ref()) {
+                for t in mtl.diffuse_texture.iter().chain(mtl.ambiant_texture.iter()) {
+                    let path = mtl_dir.join(t);
+
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// The meshes of the model read from the file at `path`, in the order
+    /// `SceneNode::add_model_data` creates their objects.
+    pub fn parts(&self, path: &Path) -> Vec<ModelPart<'_>> {
+        let file_name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut parts = Vec::new();
+
+        match *self {
+            ModelData::Obj {
+                ref meshes,
+                ref mtl_dir,
+            } => {
+                let basename = path.to_string_lossy();
+                let mut object_sizes: HashMap<&str, usize> = HashMap::new();
+
+                for name in meshes.iter().filter_map(|m| m.object.as_ref()) {
+                    *object_sizes.entry(name).or_insert(0) += 1;
+                }
+
+                for m in meshes {
+                    let group_name = obj_group_name(&m.group, &basename);
+                    let name = match m.object {
+                        Some(ref object) if object_sizes[&object[..]] > 1 => {
+                            group_name.unwrap_or(object)
+                        }
+                        Some(ref object) => object,
+                        None => group_name.unwrap_or(&file_name),
+                    };
+                    // The ambient texture replaces the diffuse one, as in `add_model_data`.
+                    let texture = m.material.as_ref().and_then(|mtl| {
+                        mtl.ambiant_texture
+                            .as_ref()
+                            .or(mtl.diffuse_texture.as_ref())
+                            .map(|t| mtl_dir.join(t))
+                    });
+
+                    parts.push(ModelPart {
+                        name: name.to_string(),
+                        mesh: &m.mesh,
+                        transform: Isometry3::identity(),
+                        scale: Vector3::from_element(1.0),
+                        material: m.material.as_ref(),
+                        texture,
+                    });
+                }
+            }
+            ModelData::Indexed(ref meshes) => {
+                for (name, mesh) in meshes {
+                    parts.push(ModelPart {
+                        name: name.clone().unwrap_or_else(|| file_name.clone()),
+                        mesh,
+                        transform: Isometry3::identity(),
+                        scale: Vector3::from_element(1.0),
+                        material: None,
+                        texture: None,
+                    });
+                }
+            }
+            ModelData::Gltf(ref nodes) => {
+                for node in nodes {
+                    node.collect_parts(
+                        &Isometry3::identity(),
+                        &Vector3::from_element(1.0),
+                        &mut parts,
+                    );
+                }
+            }
+        }
+
+        parts
+    }
+
+    /// Removes the texture file at `path` from the materials of the model, so that their objects
+    /// use the default texture.
+    pub fn remove_texture(&mut self, path: &Path) {
+        if let ModelData::Obj {
+            ref mut meshes,
+            ref mtl_dir,
+        } = *self
+        {
+            for mtl in meshes.iter_mut().filter_map(|m| m.material.as_mut()) {
+                for t in [&mut mtl.diffuse_texture, &mut mtl.ambiant_texture] {
+                    if t.as_ref().is_some_and(|t| mtl_dir.join(t) == path) {
+                        *t = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reads a model file, choosing the loader from its extension.
+///
+/// Supported extensions are `obj` (materials are looked up next to the file), `glb`, `gltf`,
+/// `stl` and `ply`. `progress` is called with the fraction of the file read so far by the
+/// loaders that stream their input. The meshes are ready to be uploaded: they are split and
+/// their missing normals computed here, so that none of this work is left to the caller.
+pub fn read_model(path: &Path, progress: &dyn Fn(f32)) -> Result<ModelData, String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let data = match extension.as_str() {
+        "obj" => {
+            let mtl_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            let basename = path.to_str().ok_or("The path is not valid unicode")?;
+            let file = File::open(path).map_err(|e| e.to_string())?;
+            let reader = ProgressReader {
+                inner: file,
+                read: 0,
+                len: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+                progress,
+            };
+            let meshes =
+                obj::parse(BufReader::new(reader), mtl_dir, basename).map_err(|e| e.to_string())?;
+
+            ModelData::Obj {
+                meshes,
+                mtl_dir: mtl_dir.to_path_buf(),
+            }
+        }
+        "glb" | "gltf" => ModelData::Gltf(read_glb(path)?),
+        "stl" => ModelData::Indexed(indexed_mesh_chunks(read_stl(path)?)?),
+        "ply" => ModelData::Indexed(indexed_mesh_chunks(read_ply(path)?)?),
+        _ => return Err(format!("Unsupported model format: {:?}", path)),
+    };
+
+    progress(1.0);
+    Ok(data)
+}
+
+/// Reads the root nodes of a binary `.glb` file or a `.gltf` file with external buffers.
+pub fn read_glb(path: &Path) -> Result<Vec<ModelNode>, String> {
+    let scale = Vector3::from_element(1.0);
+
+    glb::load_glb(path)?
+        .into_iter()
+        .map(|node| ModelNode::from_glb(node, &scale))
+        .collect()
+}
+
+/// Reads the mesh of an ASCII or binary STL file.
+pub fn read_stl(path: &Path) -> Result<Vec<IndexedMesh>, String> {
+    let mesh = stl::load_stl(path)?;
+    let indices = (0..mesh.vertices.len() as u32).collect();
+
+    Ok(vec![(
+        mesh.name,
+        mesh.vertices,
+        Some(mesh.normals),
+        indices,
+    )])
+}
+
+/// Reads the mesh of an ASCII or binary PLY file.
+pub fn read_ply(path: &Path) -> Result<Vec<IndexedMesh>, String> {
+    let mesh = ply::load_ply(path)?;
+
+    Ok(vec![(None, mesh.vertices, mesh.normals, mesh.indices)])
+}
+
+/// Checks the meshes read from a glTF, STL or PLY file, and splits them into meshes addressable
+/// with `VertexIndex`, named after the mesh they come from.
+///
+/// Missing normals are computed. The meshes are only on RAM, so this can run on any thread.
+pub fn indexed_mesh_chunks(
+    meshes: Vec<IndexedMesh>,
+) -> Result<Vec<(Option<String>, Mesh)>, String> {
+    let mut chunks = Vec::new();
+
+    for (name, vertices, normals, indices) in meshes.into_iter() {
+        if indices.len() % 3 != 0 {
+            return Err("The number of indices is not a multiple of 3".to_string());
+        }
+
+        if indices.iter().any(|i| *i as usize >= vertices.len()) {
+            return Err("Vertex index out of bounds".to_string());
+        }
+
+        if normals
+            .as_ref()
+            .map(|n| n.len() != vertices.len())
+            .unwrap_or(false)
+        {
+            return Err("The number of normals does not match the number of vertices".to_string());
+        }
+
+        for mesh in split_indexed_mesh(&vertices, normals.as_deref(), &indices) {
+            chunks.push((name.clone(), mesh));
+        }
+    }
+
+    Ok(chunks)
+}
+
+// Splits a triangle mesh indexed with `u32` into meshes addressable with `VertexIndex`.
+fn split_indexed_mesh(
+    vertices: &[Point3<f32>],
+    normals: Option<&[Vector3<f32>]>,
+    indices: &[u32],
+) -> Vec<Mesh> {
+    let max_vertices = VertexIndex::MAX as usize + 1;
+    let mut meshes = Vec::new();
+    let mut remap = vec![VertexIndex::MAX; vertices.len()];
+    let mut used = Vec::new();
+    let mut coords = Vec::new();
+    let mut ns = Vec::new();
+    let mut faces = Vec::new();
+
+    let mut flush = |coords: &mut Vec<Point3<f32>>,
+                     ns: &mut Vec<Vector3<f32>>,
+                     faces: &mut Vec<Point3<VertexIndex>>| {
+        if !faces.is_empty() {
+            let normals = normals.map(|_| mem::take(ns));
+            meshes.push(Mesh::new(
+                mem::take(coords),
+                mem::take(faces),
+                normals,
+                None,
+                false,
+            ));
+        }
+    };
+
+    for tri in indices.chunks(3) {
+        let missing = tri
+            .iter()
+            .filter(|i| remap[**i as usize] == VertexIndex::MAX)
+            .count();
+
+        if coords.len() + missing > max_vertices - 1 {
+            flush(&mut coords, &mut ns, &mut faces);
+
+            for i in used.drain(..) {
+                remap[i] = VertexIndex::MAX;
+            }
+        }
+
+        let mut face = [0; 3];
+
+        for (k, i) in tri.iter().enumerate() {
+            let i = *i as usize;
+
+            if remap[i] == VertexIndex::MAX {
+                remap[i] = coords.len() as VertexIndex;
+                used.push(i);
+                coords.push(vertices[i]);
+
+                if let Some(normals) = normals {
+                    ns.push(normals[i]);
+                }
+            }
+
+            face[k] = remap[i];
+        }
+
+        faces.push(Point3::new(face[0], face[1], face[2]));
+    }
+
+    flush(&mut coords, &mut ns, &mut faces);
+    meshes
+}
+
+/// The name of an OBJ group, without the `<basename>/` prefix given by the OBJ parser. The
+/// implicit default group, named `<basename>`, has none.
+pub fn obj_group_name<'a>(group: &'a str, basename: &str) -> Option<&'a str> {
+    let name = group.strip_prefix(basename).unwrap_or(group);
+    let name = name.strip_prefix('/').unwrap_or(name);
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+struct ProgressReader<'a, R> {
+    inner: R,
+    read: u64,
+    len: u64,
+    progress: &'a dyn Fn(f32),
+}
+
+impl<'a, R: Read> Read for ProgressReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+
+        if self.len != 0 {
+            (self.progress)((self.read as f64 / self.len as f64).min(1.0) as f32);
+        }
+
+        Ok(n)
+    }
+}
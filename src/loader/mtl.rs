@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::io::Result as IoResult;
 use std::mem;
 use std::path::Path;
@@ -56,6 +56,9 @@ pub fn parse(string: &str) -> Vec<MtlMaterial> {
                         "Kd" => curr_material.diffuse = parse_color(l, words),
                         "Ks" => curr_material.specular = parse_color(l, words),
                         "Ns" => curr_material.shininess = parse_scalar(l, words),
+                        "Ke" => curr_material.emissive = parse_color(l, words),
+                        "Pr" => curr_material.roughness = parse_scalar(l, words),
+                        "Pm" => curr_material.metalness = parse_scalar(l, words),
                         "d" => curr_material.alpha = parse_scalar(l, words),
                         "map_Ka" => curr_material.ambiant_texture = Some(parse_name(l, words)),
                         "map_Kd" => curr_material.diffuse_texture = Some(parse_name(l, words)),
@@ -79,6 +82,40 @@ pub fn parse(string: &str) -> Vec<MtlMaterial> {
     res
 }
 
+/// Writes materials in the MTL format.
+pub fn write<W: Write>(out: &mut W, materials: &[MtlMaterial]) -> IoResult<()> {
+    for (i, m) in materials.iter().enumerate() {
+        if i != 0 {
+            writeln!(out)?;
+        }
+
+        writeln!(out, "newmtl {}", m.name)?;
+        writeln!(out, "Ka {} {} {}", m.ambiant.x, m.ambiant.y, m.ambiant.z)?;
+        writeln!(out, "Kd {} {} {}", m.diffuse.x, m.diffuse.y, m.diffuse.z)?;
+        writeln!(out, "Ks {} {} {}", m.specular.x, m.specular.y, m.specular.z)?;
+        writeln!(out, "Ns {}", m.shininess)?;
+        writeln!(out, "Ke {} {} {}", m.emissive.x, m.emissive.y, m.emissive.z)?;
+        writeln!(out, "Pr {}", m.roughness)?;
+        writeln!(out, "Pm {}", m.metalness)?;
+        writeln!(out, "d {}", m.alpha)?;
+
+        let maps = [
+            ("map_Ka", &m.ambiant_texture),
+            ("map_Kd", &m.diffuse_texture),
+            ("map_Ks", &m.specular_texture),
+            ("map_d", &m.opacity_map),
+        ];
+
+        for (tag, map) in maps.iter() {
+            if let Some(map) = map {
+                writeln!(out, "{} {}", tag, map)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn parse_name<'a>(_: usize, ws: Words<'a>) -> String {
     let res: Vec<&'a str> = ws.collect();
     res.join(" ")
@@ -129,6 +166,12 @@ pub struct MtlMaterial {
     pub specular: Vector3<f32>,
     pub shininess: f32,
     pub alpha: f32,
+    /// The `Ke` color, emitted regardless of lighting.
+    pub emissive: Vector3<f32>,
+    /// The `Pr` roughness of the PBR extension of the format.
+    pub roughness: f32,
+    /// The `Pm` metalness of the PBR extension of the format.
+    pub metalness: f32,
 }
 
 impl MtlMaterial {
@@ -144,6 +187,9 @@ impl MtlMaterial {
             ambiant: Vector3::new(1.0, 1.0, 1.0),
             diffuse: Vector3::new(1.0, 1.0, 1.0),
             specular: Vector3::new(1.0, 1.0, 1.0),
+            emissive: Vector3::zeros(),
+            roughness: 1.0,
+            metalness: 0.0,
         }
     }
 
@@ -170,6 +216,9 @@ impl MtlMaterial {
             opacity_map,
             shininess,
             alpha,
+            emissive: Vector3::zeros(),
+            roughness: 1.0,
+            metalness: 0.0,
         }
     }
 }
\ No newline at end of file
@@ -1,24 +1,19 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
 use std::io::Result as IoResult;
-use std::iter::repeat;
 use std::iter::Filter;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::str::Split;
-use std::sync::{Arc, RwLock};
 
 use nalgebra::{Point2, Point3, Vector3};
-use ncollide3d::procedural::{IndexBuffer, TriMesh};
-use num_traits::{Bounded, Zero};
 
 use crate::loader::mtl;
 use crate::loader::mtl::MtlMaterial;
 use crate::resource::vertex_index::VertexIndex;
-use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec};
-use crate::resource::mesh::{self, Mesh};
+use crate::resource::mesh::{Mesh, Primitive};
 
 pub type Coord = Point3<f32>;
 pub type Normal = Vector3<f32>;
@@ -39,174 +34,472 @@ pub fn split_words(s: &str) -> Words {
     s.split(is_whitespace).filter(is_not_empty)
 }
 
-fn error(line: usize, err: &str) -> ! {
-    panic!("At line {}: {}", line, err)
+fn error(line: usize, err: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("At line {}: {}", line, err))
 }
 
 fn warn(line: usize, err: &str) {
     println!("At line {}: {}", line, err)
 }
 
-pub fn parse_file(
-    path: &Path,
+/// The elements of an OBJ file sharing the same object, group, material and primitive.
+///
+/// Faces (`f`) are triangulated as fans, polylines (`l`) are split into segments, and points
+/// (`p`) are kept as is, each in meshes of the matching `Primitive`.
+pub struct ObjMesh {
+    /// The name given by the last `o` statement, if any.
+    pub object: Option<String>,
+    /// The group name, `<basename>/<group>`, or `<basename>` for the default group.
+    pub group: String,
+    pub mesh: Mesh,
+    pub material: Option<MtlMaterial>,
+}
+
+/// Parses an OBJ file. Materials are looked up in `mtl_base_dir`.
+pub fn parse_file(path: &Path, mtl_base_dir: &Path, basename: &str) -> IoResult<Vec<ObjMesh>> {
+    parse(BufReader::new(File::open(path)?), mtl_base_dir, basename)
+}
+
+/// Parses OBJ statements line by line.
+///
+/// Meshes with more vertices than a `VertexIndex` can address are split into several meshes
+/// with the same names. Malformed statements are reported with their line number.
+pub fn parse<R: BufRead>(
+    mut reader: R,
     mtl_base_dir: &Path,
     basename: &str,
-) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
-    match File::open(path) {
-        Ok(mut file) => {
-            let mut sfile = String::new();
-            file.read_to_string(&mut sfile)
-                .map(|_| parse(&sfile[..], mtl_base_dir, basename))
+) -> IoResult<Vec<ObjMesh>> {
+    let mut parser = Parser::new(mtl_base_dir, basename);
+    let mut line = String::new();
+    let mut l = 0;
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 {
+            break;
         }
-        Err(e) => Err(e),
+
+        l += 1;
+        parser.parse_line(l, &line)?;
     }
+
+    Ok(parser.finish())
 }
 
-pub fn parse(
-    string: &str,
-    mtl_base_dir: &Path,
-    basename: &str,
-) -> Vec<(String, Mesh, Option<MtlMaterial>)> {
-    let mut coords: Vec<Coord> = Vec::new();
-    let mut normals: Vec<Normal> = Vec::new();
-    let mut uvs: Vec<UV> = Vec::new();
-    let mut groups: HashMap<String, usize> = HashMap::new();
-    let mut groups_ids: Vec<Vec<Point3<VertexIndex>>> = Vec::new();
-    let mut curr_group: usize = 0;
-    let mut ignore_normals = false;
-    let mut ignore_uvs = false;
-    let mut mtllib = HashMap::new();
-    let mut group2mtl = HashMap::new();
-    let mut curr_mtl = None::<MtlMaterial>;
-
-    groups_ids.push(Vec::new());
-    let _ = groups.insert(basename.to_string(), 0);
-
-    for (l, line) in string.lines().enumerate() {
+const NONE: u32 = u32::MAX;
+
+// The zero-based indices of a face corner. Missing texture coordinates and normals are `NONE`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+    v: u32,
+    vt: u32,
+    vn: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ElementsKey {
+    object: Option<String>,
+    group: String,
+    material: Option<String>,
+    primitive: Primitive,
+}
+
+struct Elements {
+    key: ElementsKey,
+    material: Option<MtlMaterial>,
+    // Three corners per triangle, segment or point, stored like the faces of a `Primitive`.
+    corners: Vec<Corner>,
+    // The smoothing key of each triangle, see `Parser::smoothing_key`.
+    smoothing: Vec<u64>,
+}
+
+struct Parser<'a> {
+    mtl_base_dir: &'a Path,
+    basename: &'a str,
+    coords: Vec<Coord>,
+    normals: Vec<Normal>,
+    uvs: Vec<UV>,
+    mtllib: HashMap<String, MtlMaterial>,
+    elements: Vec<Elements>,
+    elements_ids: HashMap<ElementsKey, usize>,
+    // The elements receiving the next statements, indexed by primitive.
+    curr_elements: [Option<usize>; 3],
+    object: Option<String>,
+    group: String,
+    material: Option<MtlMaterial>,
+    // The current smoothing group, 0 if smoothing is off.
+    smoothing: u32,
+    num_flat_faces: u64,
+    missing_uvs: bool,
+    missing_normals: bool,
+    unknown_statements: HashSet<String>,
+    corners: Vec<Corner>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(mtl_base_dir: &'a Path, basename: &'a str) -> Parser<'a> {
+        Parser {
+            mtl_base_dir,
+            basename,
+            coords: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            mtllib: HashMap::new(),
+            elements: Vec::new(),
+            elements_ids: HashMap::new(),
+            curr_elements: [None; 3],
+            object: None,
+            group: basename.to_string(),
+            material: None,
+            // Files without `s` statements keep their vertices shared, hence smooth.
+            smoothing: 1,
+            num_flat_faces: 0,
+            missing_uvs: false,
+            missing_normals: false,
+            unknown_statements: HashSet::new(),
+            corners: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, l: usize, line: &str) -> IoResult<()> {
         let mut words = split_words(line);
-        let tag = words.next();
+
+        let tag = match words.next() {
+            Some(w) if !w.starts_with('#') => w,
+            _ => return Ok(()),
+        };
+
         match tag {
-            None => {}
-            Some(w) => {
-                if !w.is_empty() && w.as_bytes()[0] != b'#' {
-                    match w {
-                        "v" => coords.push(Point3::from(parse_v_or_vn(l, words))),
-                        "vn" => {
-                            if !ignore_normals {
-                                normals.push(parse_v_or_vn(l, words))
-                            }
-                        }
-                        "f" => parse_f(
-                            l,
-                            words,
-                            &coords[..],
-                            &uvs[..],
-                            &normals[..],
-                            &mut ignore_uvs,
-                            &mut ignore_normals,
-                            &mut groups_ids,
-                            curr_group,
-                        ),
-                        "vt" => {
-                            if !ignore_uvs {
-                                uvs.push(parse_vt(l, words))
-                            }
-                        }
-                        "g" => {
-                            curr_group = parse_g(l, words, basename, &mut groups, &mut groups_ids);
-                            let _ = curr_mtl
-                                .as_ref()
-                                .map(|mtl| group2mtl.insert(curr_group, mtl.clone()));
-                        }
-                        "mtllib" => parse_mtllib(l, words, mtl_base_dir, &mut mtllib),
-                        "usemtl" => {
-                            curr_group = parse_usemtl(
-                                l,
-                                words,
-                                curr_group,
-                                &mtllib,
-                                &mut group2mtl,
-                                &mut groups,
-                                &mut groups_ids,
-                                &mut curr_mtl,
-                            )
-                        }
-                        _ => {
-                            println!("Warning: unknown line {} ignored: `{}'", l, line);
-                        }
-                    }
+            "v" => self.coords.push(Point3::from(parse_v_or_vn(l, words)?)),
+            "vn" => self.normals.push(parse_v_or_vn(l, words)?),
+            "vt" => self.uvs.push(parse_vt(l, words)?),
+            "f" => self.parse_f(l, words)?,
+            "l" => self.parse_l(l, words)?,
+            "p" => self.parse_p(l, words)?,
+            "o" => {
+                let name = join_words(words);
+                self.object = if name.is_empty() { None } else { Some(name) };
+                self.group = self.basename.to_string();
+                self.curr_elements = [None; 3];
+            }
+            "g" => {
+                let name = join_words(words);
+                self.group = if name.is_empty() {
+                    self.basename.to_string()
+                } else {
+                    format!("{}/{}", self.basename, name)
+                };
+                self.curr_elements = [None; 3];
+            }
+            "s" => self.smoothing = parse_s(l, words)?,
+            "mtllib" => parse_mtllib(l, words, self.mtl_base_dir, &mut self.mtllib),
+            "usemtl" => {
+                self.material = parse_usemtl(l, words, &self.mtllib);
+                self.curr_elements = [None; 3];
+            }
+            _ => {
+                if self.unknown_statements.insert(tag.to_string()) {
+                    warn(l, &format!("unknown statement `{}' ignored", tag));
                 }
             }
         }
+
+        Ok(())
+    }
+
+    fn parse_f(&mut self, l: usize, ws: Words) -> IoResult<()> {
+        self.parse_corners(l, ws)?;
+
+        if self.corners.len() < 3 {
+            return Err(error(
+                l,
+                &format!("a face needs at least 3 vertices, found {}", self.corners.len()),
+            ));
+        }
+
+        for c in self.corners.iter() {
+            self.missing_uvs |= c.vt == NONE;
+            self.missing_normals |= c.vn == NONE;
+        }
+
+        let smoothing = self.smoothing_key();
+        let i = self.curr_elements(Primitive::Triangles);
+        let elements = &mut self.elements[i];
+        let corners = &self.corners;
+
+        for k in 1..corners.len() - 1 {
+            elements
+                .corners
+                .extend_from_slice(&[corners[0], corners[k], corners[k + 1]]);
+            elements.smoothing.push(smoothing);
+        }
+
+        Ok(())
+    }
+
+    fn parse_l(&mut self, l: usize, ws: Words) -> IoResult<()> {
+        self.parse_corners(l, ws)?;
+
+        if self.corners.len() < 2 {
+            return Err(error(
+                l,
+                &format!("a line needs at least 2 vertices, found {}", self.corners.len()),
+            ));
+        }
+
+        let i = self.curr_elements(Primitive::Lines);
+        let elements = &mut self.elements[i];
+
+        for segment in self.corners.windows(2) {
+            elements
+                .corners
+                .extend_from_slice(&Primitive::Lines.face(segment));
+        }
+
+        Ok(())
     }
 
-    if uvs.is_empty() && ignore_uvs {
-        println!("Warning: some texture coordinates are missing. Dropping texture coordinates infos for every vertex.");
+    fn parse_p(&mut self, l: usize, ws: Words) -> IoResult<()> {
+        self.parse_corners(l, ws)?;
+
+        if self.corners.is_empty() {
+            return Err(error(l, "a point statement needs at least 1 vertex, found 0"));
+        }
+
+        let i = self.curr_elements(Primitive::Points);
+        let elements = &mut self.elements[i];
+
+        for c in self.corners.chunks(1) {
+            elements
+                .corners
+                .extend_from_slice(&Primitive::Points.face(c));
+        }
+
+        Ok(())
     }
 
-    if normals.is_empty() && ignore_normals {
-        println!("Warning: some normals are missing. Dropping normals infos for every vertex.");
+    fn parse_corners(&mut self, l: usize, ws: Words) -> IoResult<()> {
+        self.corners.clear();
+
+        for word in ws {
+            let mut ids = word.split('/');
+            let v = resolve_index(l, ids.next().unwrap_or(""), self.coords.len(), "vertex")?;
+            let vt = match ids.next() {
+                Some(w) if !w.is_empty() => {
+                    resolve_index(l, w, self.uvs.len(), "texture coordinate")?
+                }
+                _ => NONE,
+            };
+            let vn = match ids.next() {
+                Some(w) if !w.is_empty() => resolve_index(l, w, self.normals.len(), "normal")?,
+                _ => NONE,
+            };
+
+            self.corners.push(Corner { v, vt, vn });
+        }
+
+        Ok(())
     }
 
-    reformat(
-        coords,
-        if ignore_normals { None } else { Some(normals) },
-        if ignore_uvs { None } else { Some(uvs) },
-        groups_ids,
-        groups,
-        group2mtl,
-    )
-}
+    // Triangles with the same smoothing key share their vertices when normals are computed.
+    // Faces outside of any smoothing group get a key of their own, so they are flat shaded.
+    fn smoothing_key(&mut self) -> u64 {
+        if self.smoothing != 0 {
+            self.smoothing as u64
+        } else {
+            self.num_flat_faces += 1;
+            u32::MAX as u64 + self.num_flat_faces
+        }
+    }
 
-fn parse_usemtl<'a>(
-    l: usize,
-    ws: Words<'a>,
-    curr_group: usize,
-    mtllib: &HashMap<String, MtlMaterial>,
-    group2mtl: &mut HashMap<usize, MtlMaterial>,
-    groups: &mut HashMap<String, usize>,
-    groups_ids: &mut Vec<Vec<Point3<VertexIndex>>>,
-    curr_mtl: &mut Option<MtlMaterial>,
-) -> usize {
-    let mname: Vec<&'a str> = ws.collect();
-    let mname = mname.join(" ");
-    let none = "None";
-    if mname[..] != none[..] {
-        match mtllib.get(&mname) {
-            None => {
-                *curr_mtl = None;
-                warn(l, &format!("could not find the material {}", mname)[..]);
-
-                curr_group
+    fn curr_elements(&mut self, primitive: Primitive) -> usize {
+        if let Some(i) = self.curr_elements[primitive as usize] {
+            return i;
+        }
+
+        let key = ElementsKey {
+            object: self.object.clone(),
+            group: self.group.clone(),
+            material: self.material.as_ref().map(|m| m.name.clone()),
+            primitive,
+        };
+        let elements = &mut self.elements;
+        let material = &self.material;
+
+        let i = *self.elements_ids.entry(key.clone()).or_insert_with(|| {
+            elements.push(Elements {
+                key,
+                material: material.clone(),
+                corners: Vec::new(),
+                smoothing: Vec::new(),
+            });
+            elements.len() - 1
+        });
+
+        self.curr_elements[primitive as usize] = Some(i);
+        i
+    }
+
+    fn finish(self) -> Vec<ObjMesh> {
+        if self.missing_uvs && !self.uvs.is_empty() {
+            println!("Warning: some texture coordinates are missing. Dropping texture coordinates infos for every vertex.");
+        }
+
+        if self.missing_normals && !self.normals.is_empty() {
+            println!("Warning: some normals are missing. Dropping normals infos for every vertex.");
+        }
+
+        let mut meshes = Vec::new();
+
+        for elements in self.elements.iter() {
+            self.build_meshes(elements, &mut meshes);
+        }
+
+        meshes
+    }
+
+    // Creates the meshes of `elements`, splitting them if they have too many vertices.
+    fn build_meshes(&self, elements: &Elements, meshes: &mut Vec<ObjMesh>) {
+        let primitive = elements.key.primitive;
+        let use_uvs = !self.missing_uvs;
+        let use_normals = !self.missing_normals;
+        let compute_normals = primitive == Primitive::Triangles && !use_normals;
+        let max_vertices = VertexIndex::MAX as usize + 1;
+
+        let mut ids: HashMap<(Corner, u64), VertexIndex> = HashMap::new();
+        let mut coords = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut faces = Vec::new();
+
+        let mut flush = |coords: &mut Vec<Coord>,
+                         normals: &mut Vec<Normal>,
+                         uvs: &mut Vec<UV>,
+                         faces: &mut Vec<Point3<VertexIndex>>| {
+            if faces.is_empty() {
+                return;
             }
-            Some(m) => {
-                if !group2mtl.contains_key(&curr_group) {
-                    let _ = group2mtl.insert(curr_group, m.clone());
-                    *curr_mtl = Some(m.clone());
-                    curr_group
-                } else {
-                    let mut g = curr_group.to_string();
-                    g.push_str(&mname[..]);
 
-                    let new_group = parse_g(
-                        l,
-                        split_words(&g[..]),
-                        "auto_generated_group_",
-                        groups,
-                        groups_ids,
-                    );
+            let normals = if compute_normals {
+                Mesh::compute_normals_array(coords, faces)
+            } else {
+                mem::take(normals)
+            };
+            let mut mesh = Mesh::new(
+                mem::take(coords),
+                mem::take(faces),
+                Some(normals),
+                Some(mem::take(uvs)),
+                false,
+            );
+            mesh.set_primitive(primitive);
+
+            meshes.push(ObjMesh {
+                object: elements.key.object.clone(),
+                group: elements.key.group.clone(),
+                mesh,
+                material: elements.material.clone(),
+            });
+        };
+
+        for (t, triangle) in elements.corners.chunks(3).enumerate() {
+            let smoothing = if compute_normals {
+                elements.smoothing[t]
+            } else {
+                0
+            };
+            let mut keys = [(triangle[0], smoothing); 3];
+
+            for (key, corner) in keys.iter_mut().zip(triangle.iter()) {
+                key.0 = *corner;
 
-                    let _ = group2mtl.insert(new_group, m.clone());
-                    *curr_mtl = Some(m.clone());
+                if !use_uvs {
+                    key.0.vt = NONE;
+                }
 
-                    new_group
+                if !use_normals {
+                    key.0.vn = NONE;
                 }
             }
+
+            let missing = (0..3)
+                .filter(|k| !ids.contains_key(&keys[*k]) && !keys[..*k].contains(&keys[*k]))
+                .count();
+
+            if coords.len() + missing > max_vertices {
+                flush(&mut coords, &mut normals, &mut uvs, &mut faces);
+                ids.clear();
+            }
+
+            let mut face = [0; 3];
+
+            for (id, key) in face.iter_mut().zip(keys.iter()) {
+                *id = *ids.entry(*key).or_insert_with(|| {
+                    let c = key.0;
+
+                    coords.push(self.coords[c.v as usize]);
+                    uvs.push(if c.vt == NONE {
+                        Point2::origin()
+                    } else {
+                        self.uvs[c.vt as usize]
+                    });
+
+                    // Lines and points have no surface, a constant normal lights them evenly.
+                    if !compute_normals {
+                        normals.push(if c.vn == NONE {
+                            Vector3::z()
+                        } else {
+                            self.normals[c.vn as usize]
+                        });
+                    }
+
+                    (coords.len() - 1) as VertexIndex
+                });
+            }
+
+            faces.push(Point3::new(face[0], face[1], face[2]));
         }
-    } else {
-        *curr_mtl = None;
-        curr_group
+
+        flush(&mut coords, &mut normals, &mut uvs, &mut faces);
+    }
+}
+
+fn join_words(ws: Words) -> String {
+    let words: Vec<&str> = ws.collect();
+    words.join(" ")
+}
+
+// OBJ indices start at 1, and negative indices are relative to the end of the list.
+fn resolve_index(l: usize, w: &str, len: usize, what: &str) -> IoResult<u32> {
+    let id: i64 = FromStr::from_str(w)
+        .map_err(|e| error(l, &format!("failed to parse `{}' as an index: {}", w, e)))?;
+    let id = if id < 0 { len as i64 + id } else { id - 1 };
+
+    if id < 0 || id >= len as i64 {
+        return Err(error(
+            l,
+            &format!("{} index {} is out of bounds, there are {} of them", what, w, len),
+        ));
+    }
+
+    Ok(id as u32)
+}
+
+fn parse_usemtl(l: usize, ws: Words, mtllib: &HashMap<String, MtlMaterial>) -> Option<MtlMaterial> {
+    let mname = join_words(ws);
+
+    if mname == "None" {
+        return None;
+    }
+
+    let material = mtllib.get(&mname).cloned();
+
+    if material.is_none() {
+        warn(l, &format!("could not find the material {}", mname));
     }
+
+    material
 }
 
 fn parse_mtllib<'a>(
@@ -234,254 +527,282 @@ fn parse_mtllib<'a>(
     }
 }
 
-fn parse_v_or_vn(l: usize, mut ws: Words) -> Vector3<f32> {
-    let sx = ws
-        .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 0."));
-    let sy = ws
-        .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 1."));
-    let sz = ws
-        .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 2."));
-
-    let x: Result<f32, _> = FromStr::from_str(sx);
-    let y: Result<f32, _> = FromStr::from_str(sy);
-    let z: Result<f32, _> = FromStr::from_str(sz);
-
-    let x =
-        x.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]));
-    let y =
-        y.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]));
-    let z =
-        z.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sz, e)[..]));
-
-    Vector3::new(x, y, z)
+fn parse_s(l: usize, mut ws: Words) -> IoResult<u32> {
+    match ws.next() {
+        None | Some("off") => Ok(0),
+        Some(w) => FromStr::from_str(w)
+            .map_err(|e| error(l, &format!("failed to parse `{}' as a smoothing group: {}", w, e))),
+    }
 }
 
-fn parse_f<'a>(
-    l: usize,
-    ws: Words<'a>,
-    coords: &[Point3<f32>],
-    uvs: &[Point2<f32>],
-    normals: &[Vector3<f32>],
-    ignore_uvs: &mut bool,
-    ignore_normals: &mut bool,
-    groups_ids: &mut Vec<Vec<Point3<VertexIndex>>>,
-    curr_group: usize,
-) {
-    let mut i = 0;
-    for word in ws {
-        let mut curr_ids: Vector3<i32> = Bounded::max_value();
-
-        for (i, w) in word.split('/').enumerate() {
-            if i == 0 || !w.is_empty() {
-                let idx: Result<i32, _> = FromStr::from_str(w);
-                match idx {
-                    Ok(id) => curr_ids[i] = id - 1,
-                    Err(e) => error(l, &format!("failed to parse `{}' as a i32: {}", w, e)[..]),
-                }
-            }
-        }
+fn parse_f32(l: usize, w: &str) -> IoResult<f32> {
+    FromStr::from_str(w).map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", w, e)))
+}
 
-        if i > 2 {
-            let g = &mut groups_ids[curr_group];
-            let p1 = (*g)[g.len() - i];
-            let p2 = (*g)[g.len() - 1];
-            g.push(p1);
-            g.push(p2);
-        }
+fn parse_v_or_vn(l: usize, mut ws: Words) -> IoResult<Vector3<f32>> {
+    let mut v = Vector3::zeros();
 
-        if curr_ids.y == i32::max_value() as i32 {
-            *ignore_uvs = true;
-        }
+    for i in 0..3 {
+        let w = ws.next().ok_or_else(|| {
+            error(l, &format!("3 components were expected, found {}.", i))
+        })?;
+        v[i] = parse_f32(l, w)?;
+    }
 
-        if curr_ids.z == i32::max_value() as i32 {
-            *ignore_normals = true;
-        }
+    Ok(v)
+}
 
-        let x;
-        let y;
-        let z;
+fn parse_vt(l: usize, mut ws: Words) -> IoResult<UV> {
+    let mut uv = Point2::origin();
 
-        if curr_ids.x < 0 {
-            x = coords.len() as i32 + curr_ids.x + 1;
-        } else {
-            x = curr_ids.x;
+    for i in 0..2 {
+        let w = ws.next().ok_or_else(|| {
+            error(l, &format!("at least 2 components were expected, found {}.", i))
+        })?;
+        uv[i] = parse_f32(l, w)?;
+    }
+
+    Ok(uv)
+}
+
+
+/// A group of triangles, segments or points written by `write_file`.
+pub struct ObjGroup {
+    pub name: String,
+    pub coords: Vec<Coord>,
+    /// Either empty or one normal per vertex.
+    pub normals: Vec<Normal>,
+    /// Either empty or one texture coordinate per vertex.
+    pub uvs: Vec<UV>,
+    /// The elements of the group, stored as the faces of a mesh of `primitive`.
+    pub faces: Vec<Point3<u32>>,
+    pub primitive: Primitive,
+    pub material: Option<MtlMaterial>,
+}
+
+/// Writes groups of triangles, segments or points to an OBJ file, as `f`, `l` and `p`
+/// statements.
+///
+/// If any group has a material, they are written to a MTL file with the same name and the `mtl`
+/// extension, next to the OBJ file.
+pub fn write_file(path: &Path, groups: &[ObjGroup]) -> IoResult<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let materials: Vec<MtlMaterial> = groups.iter().filter_map(|g| g.material.clone()).collect();
+
+    writeln!(out, "# Written by starman")?;
+
+    if !materials.is_empty() {
+        let mtl_path = path.with_extension("mtl");
+        let mut mtl_out = BufWriter::new(File::create(&mtl_path)?);
+        mtl::write(&mut mtl_out, &materials)?;
+        mtl_out.flush()?;
+
+        let mtl_name = mtl_path.file_name().unwrap_or_default().to_string_lossy();
+        writeln!(out, "mtllib {}", mtl_name)?;
+    }
+
+    // OBJ indices are global to the file, and start at 1.
+    let (mut v_offset, mut vn_offset, mut vt_offset) = (1, 1, 1);
+
+    for group in groups.iter() {
+        let has_normals = !group.normals.is_empty();
+        let has_uvs = !group.uvs.is_empty();
+
+        writeln!(out, "g {}", group.name)?;
+
+        if let Some(ref material) = group.material {
+            writeln!(out, "usemtl {}", material.name)?;
         }
 
-        if curr_ids.y < 0 {
-            y = uvs.len() as i32 + curr_ids.y + 1;
-        } else {
-            y = curr_ids.y;
+        for c in group.coords.iter() {
+            writeln!(out, "v {} {} {}", c.x, c.y, c.z)?;
         }
 
-        if curr_ids.z < 0 {
-            z = normals.len() as i32 + curr_ids.z + 1;
-        } else {
-            z = curr_ids.z;
+        for n in group.normals.iter() {
+            writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
         }
 
-        assert!(x >= 0 && y >= 0 && z >= 0);
-        groups_ids[curr_group].push(Point3::new(
-            x as VertexIndex,
-            y as VertexIndex,
-            z as VertexIndex,
-        ));
+        for u in group.uvs.iter() {
+            writeln!(out, "vt {} {}", u.x, u.y)?;
+        }
 
-        i += 1;
-    }
+        let (statement, num_vertices) = match group.primitive {
+            Primitive::Triangles => ("f", 3),
+            Primitive::Lines => ("l", 2),
+            Primitive::Points => ("p", 1),
+        };
+        // Segments and points have no normals.
+        let has_normals = has_normals && group.primitive == Primitive::Triangles;
+
+        for f in group.faces.iter() {
+            write!(out, "{}", statement)?;
+
+            for i in f.iter().take(num_vertices) {
+                let (v, vn, vt) = (i + v_offset, i + vn_offset, i + vt_offset);
+
+                match (has_uvs, has_normals) {
+                    (true, true) => write!(out, " {}/{}/{}", v, vt, vn)?,
+                    (true, false) => write!(out, " {}/{}", v, vt)?,
+                    (false, true) => write!(out, " {}//{}", v, vn)?,
+                    (false, false) => write!(out, " {}", v)?,
+                }
+            }
 
-    if i < 2 {
-        for _ in 0usize..3 - i {
-            let last = *(*groups_ids)[curr_group].last().unwrap();
-            groups_ids[curr_group].push(last);
+            writeln!(out)?;
         }
+
+        v_offset += group.coords.len() as u32;
+        vn_offset += group.normals.len() as u32;
+        vt_offset += group.uvs.len() as u32;
     }
-}
 
-fn parse_vt(l: usize, mut ws: Words) -> UV {
-    let sx = ws
-        .next()
-        .unwrap_or_else(|| error(l, "at least 2 components were expected, found 0."));
-    let sy = ws
-        .next()
-        .unwrap_or_else(|| error(l, "at least 2 components were expected, found 1."));
+    out.flush()
+}
 
-    let x: Result<f32, _> = FromStr::from_str(sx);
-    let y: Result<f32, _> = FromStr::from_str(sy);
+/// Copies a texture file to `dir`, where an OBJ file is written, and returns its file name.
+pub fn copy_texture(path: &Path, dir: &Path) -> IoResult<String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid texture path"))?;
+    let dest = dir.join(file_name);
 
-    let x =
-        x.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]));
-    let y =
-        y.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]));
+    if fs::canonicalize(path)? != fs::canonicalize(&dest).unwrap_or_default() {
+        let _ = fs::copy(path, &dest)?;
+    }
 
-    Point2::new(x, y)
+    Ok(file_name.to_string_lossy().into_owned())
 }
 
-fn parse_g<'a>(
-    _: usize,
-    ws: Words<'a>,
-    prefix: &str,
-    groups: &mut HashMap<String, usize>,
-    groups_ids: &mut Vec<Vec<Point3<VertexIndex>>>,
-) -> usize {
-    let suffix: Vec<&'a str> = ws.collect();
-    let suffix = suffix.join(" ");
-    let name = if suffix.is_empty() {
-        prefix.to_string()
-    } else {
-        format!("{}/{}", prefix, suffix)
-    };
-
-    match groups.entry(name) {
-        Entry::Occupied(entry) => *entry.into_mut(),
-        Entry::Vacant(entry) => {
-            groups_ids.push(Vec::new());
-
-            let val = groups_ids.len() - 1;
-            *entry.insert(val)
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn parse_str(obj: &str) -> IoResult<Vec<ObjMesh>> {
+        parse(obj.as_bytes(), Path::new(""), "test")
     }
-}
 
-fn reformat(
-    coords: Vec<Coord>,
-    normals: Option<Vec<Normal>>,
-    uvs: Option<Vec<UV>>,
-    groups_ids: Vec<Vec<Point3<VertexIndex>>>,
-    groups: HashMap<String, usize>,
-    group2mtl: HashMap<usize, MtlMaterial>,
-) -> Vec<(String, Mesh, Option<MtlMaterial>)> {
-    let mut vt2id: HashMap<Point3<VertexIndex>, VertexIndex> = HashMap::new();
-    let mut vertex_ids: Vec<VertexIndex> = Vec::new();
-    let mut resc: Vec<Coord> = Vec::new();
-    let mut resn: Option<Vec<Normal>> = normals.as_ref().map(|_| Vec::new());
-    let mut resu: Option<Vec<UV>> = uvs.as_ref().map(|_| Vec::new());
-    let mut resfs: Vec<Vec<Point3<VertexIndex>>> = Vec::new();
-    let mut allfs: Vec<Point3<VertexIndex>> = Vec::new();
-    let mut names: Vec<String> = Vec::new();
-    let mut mtls: Vec<Option<MtlMaterial>> = Vec::new();
-
-    for (name, i) in groups.into_iter() {
-        names.push(name);
-        mtls.push(group2mtl.get(&i).cloned());
-
-        for point in groups_ids[i].iter() {
-            let idx = match vt2id.get(point) {
-                Some(i) => {
-                    vertex_ids.push(*i);
-                    None
-                }
-                None => {
-                    let idx = resc.len() as VertexIndex;
+    fn coords(mesh: &Mesh) -> Vec<Coord> {
+        mesh.coords().read().unwrap().data().clone().unwrap()
+    }
 
-                    resc.push(coords[point.x as usize]);
+    fn faces(mesh: &Mesh) -> Vec<Point3<VertexIndex>> {
+        mesh.faces().read().unwrap().data().clone().unwrap()
+    }
 
-                    let _ = resu
-                        .as_mut()
-                        .map(|l| l.push((*uvs.as_ref().unwrap())[point.y as usize]));
-                    let _ = resn
-                        .as_mut()
-                        .map(|l| l.push((*normals.as_ref().unwrap())[point.z as usize]));
+    #[test]
+    fn errors_report_their_line_number() {
+        let error = |obj: &str| parse_str(obj).err().unwrap().to_string();
 
-                    vertex_ids.push(idx);
+        assert!(error(&format!("{}\nf 1 2\n", SQUARE)).starts_with("At line 6:"));
+        assert!(error(&format!("{}f 1 2 5\n", SQUARE)).starts_with("At line 5:"));
+        assert!(error("v 0 0 0\nv 1 x 0\n").starts_with("At line 2:"));
+        assert!(error("v 0 0 0\nl 1\n").starts_with("At line 2:"));
+        assert!(error("# comment\np\n").starts_with("At line 2:"));
+    }
 
-                    Some(idx)
-                }
-            };
+    #[test]
+    fn negative_indices_are_relative_to_the_last_vertex() {
+        let absolute = parse_str(&format!("{}f 2 3 4\n", SQUARE)).unwrap();
+        let relative = parse_str(&format!("{}f -3 -2 -1\n", SQUARE)).unwrap();
+
+        assert_eq!(coords(&relative[0].mesh), coords(&absolute[0].mesh));
+        assert_eq!(faces(&relative[0].mesh), faces(&absolute[0].mesh));
+        assert_eq!(
+            coords(&relative[0].mesh),
+            vec![
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0)
+            ]
+        );
+        assert!(parse_str(&format!("{}f -5 -2 -1\n", SQUARE)).is_err());
+    }
 
-            let _ = idx.map(|i| vt2id.insert(*point, i));
-        }
+    #[test]
+    fn objects_and_groups_split_the_meshes() {
+        let obj = format!(
+            "{}o door\ng frame\nf 1 2 3\ng panel\nf 1 3 4\no window\nf 1 2 4\n",
+            SQUARE
+        );
+        let meshes = parse_str(&obj).unwrap();
+        let names: Vec<(Option<&str>, &str)> = meshes
+            .iter()
+            .map(|m| (m.object.as_deref(), m.group.as_str()))
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                (Some("door"), "test/frame"),
+                (Some("door"), "test/panel"),
+                (Some("window"), "test"),
+            ]
+        );
+    }
 
-        let mut resf = Vec::with_capacity(vertex_ids.len() / 3);
+    #[test]
+    fn smoothing_groups_share_vertices() {
+        let smooth = parse_str(&format!("{}s 1\nf 1 2 3\nf 1 3 4\n", SQUARE)).unwrap();
+        let flat = parse_str(&format!("{}s off\nf 1 2 3\nf 1 3 4\n", SQUARE)).unwrap();
 
-        assert!(vertex_ids.len() % 3 == 0);
+        assert_eq!(coords(&smooth[0].mesh).len(), 4);
+        assert_eq!(coords(&flat[0].mesh).len(), 6);
+    }
 
-        for f in vertex_ids[..].chunks(3) {
-            resf.push(Point3::new(f[0], f[1], f[2]));
-            allfs.push(Point3::new(f[0], f[1], f[2]));
-        }
+    #[test]
+    fn lines_and_points_get_their_own_meshes() {
+        let obj = format!("{}f 1 2 3\nl 1 2 3\np 4 1\n", SQUARE);
+        let meshes = parse_str(&obj).unwrap();
+        let primitives: Vec<Primitive> = meshes.iter().map(|m| m.mesh.primitive()).collect();
+
+        assert_eq!(
+            primitives,
+            [Primitive::Triangles, Primitive::Lines, Primitive::Points]
+        );
+        assert_eq!(
+            faces(&meshes[1].mesh),
+            [Point3::new(0, 1, 1), Point3::new(1, 2, 2)]
+        );
+        assert_eq!(
+            faces(&meshes[2].mesh),
+            [Point3::new(0, 0, 0), Point3::new(1, 1, 1)]
+        );
+    }
 
-        resfs.push(resf);
-        vertex_ids.clear();
-    }
-
-    let resn = resn.unwrap_or_else(|| Mesh::compute_normals_array(&resc[..], &allfs[..]));
-    let resn = Arc::new(RwLock::new(GPUVec::new(
-        resn,
-        BufferType::Array,
-        AllocationType::StaticDraw,
-    )));
-    let resu = resu.unwrap_or_else(|| repeat(Point2::origin()).take(resc.len()).collect());
-    let resu = Arc::new(RwLock::new(GPUVec::new(
-        resu,
-        BufferType::Array,
-        AllocationType::StaticDraw,
-    )));
-    let resc = Arc::new(RwLock::new(GPUVec::new(
-        resc,
-        BufferType::Array,
-        AllocationType::StaticDraw,
-    )));
-
-    let mut meshes = Vec::new();
-    for ((fs, name), mtl) in resfs
-        .into_iter()
-        .zip(names.into_iter())
-        .zip(mtls.into_iter())
-    {
-        if !fs.is_empty() {
-            let fs = Arc::new(RwLock::new(GPUVec::new(
-                fs,
-                BufferType::ElementArray,
-                AllocationType::StaticDraw,
-            )));
-            let mesh = Mesh::new_with_gpu_vectors(resc.clone(), fs, resn.clone(), resu.clone());
-            meshes.push((name, mesh, mtl))
+    #[test]
+    fn written_lines_and_points_are_read_back() {
+        let obj = format!("{}f 1 2 3\nl 1 2 3\np 4 1\n", SQUARE);
+        let groups: Vec<ObjGroup> = parse_str(&obj)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, m)| ObjGroup {
+                name: format!("group_{}", i),
+                coords: coords(&m.mesh),
+                normals: Vec::new(),
+                uvs: Vec::new(),
+                faces: faces(&m.mesh)
+                    .iter()
+                    .map(|f| Point3::new(f.x as u32, f.y as u32, f.z as u32))
+                    .collect(),
+                primitive: m.mesh.primitive(),
+                material: None,
+            })
+            .collect();
+
+        let path = std::env::temp_dir().join("starman_obj_primitives.obj");
+        write_file(&path, &groups).unwrap();
+        let read = parse_file(&path, Path::new(""), "test");
+        let _ = fs::remove_file(&path);
+        let read = read.unwrap();
+
+        assert_eq!(read.len(), groups.len());
+
+        for (group, mesh) in groups.iter().zip(read.iter()) {
+            assert_eq!(mesh.mesh.primitive(), group.primitive);
+            assert_eq!(faces(&mesh.mesh).len(), group.faces.len());
         }
     }
-
-    meshes
 }
-
@@ -0,0 +1,454 @@
+use nalgebra::{Point3, Vector3};
+use std::fs::{self, File};
+use std::io::{BufWriter, Result as IoResult, Write};
+use std::path::Path;
+
+pub struct Mesh {
+    pub vertices: Vec<Point3<f32>>,
+    /// The vertex normals, if the file declares the `nx`, `ny` and `nz` properties.
+    pub normals: Option<Vec<Vector3<f32>>>,
+    /// Three indices per triangle; polygonal faces are triangulated as fans.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    List(String, Scalar, Scalar),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Loads an ASCII or binary PLY file.
+///
+/// Only the `x`, `y`, `z` and `nx`, `ny`, `nz` properties of the `vertex` element, and the
+/// `vertex_indices` (or `vertex_index`) list of the `face` element are used. Other elements and
+/// properties are skipped.
+pub fn load_ply(file_path: &Path) -> Result<Mesh, String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (format, elements, body_start) = parse_header(&data)?;
+    let body = &data[body_start..];
+
+    let mut reader = match format {
+        Format::Ascii => Reader::Ascii(
+            std::str::from_utf8(body)
+                .map_err(|e| format!("Invalid ASCII PLY body: {}", e))?
+                .split_whitespace(),
+        ),
+        _ => Reader::Binary {
+            data: body,
+            offset: 0,
+            big_endian: format == Format::BinaryBigEndian,
+        },
+    };
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+
+    for element in elements.iter() {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+
+        for _ in 0..element.count {
+            let mut pos = [0.0f32; 3];
+            let mut normal = [0.0f32; 3];
+            let mut face = Vec::new();
+
+            for property in element.properties.iter() {
+                match property {
+                    Property::Scalar(name, ty) => {
+                        let value = reader.read(*ty)? as f32;
+
+                        if is_vertex {
+                            match name.as_str() {
+                                "x" => pos[0] = value,
+                                "y" => pos[1] = value,
+                                "z" => pos[2] = value,
+                                "nx" => normal[0] = value,
+                                "ny" => normal[1] = value,
+                                "nz" => normal[2] = value,
+                                _ => {}
+                            }
+                        }
+                    }
+                    Property::List(name, count_ty, item_ty) => {
+                        let count = reader.read(*count_ty)? as usize;
+                        let is_indices =
+                            is_face && (name == "vertex_indices" || name == "vertex_index");
+
+                        for _ in 0..count {
+                            let item = reader.read(*item_ty)?;
+
+                            if is_indices {
+                                face.push(item as u32);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if is_vertex {
+                vertices.push(Point3::new(pos[0], pos[1], pos[2]));
+                normals.push(Vector3::new(normal[0], normal[1], normal[2]));
+            }
+
+            for i in 1..face.len().saturating_sub(1) {
+                indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+            }
+        }
+    }
+
+    if let Some(i) = indices.iter().find(|i| **i as usize >= vertices.len()) {
+        return Err(format!("Vertex index out of bounds: {}", i));
+    }
+
+    let has_normals = elements.iter().any(|e| {
+        e.name == "vertex"
+            && e.properties
+                .iter()
+                .any(|p| matches!(p, Property::Scalar(name, _) if name == "nx"))
+    });
+
+    Ok(Mesh {
+        vertices,
+        normals: if has_normals { Some(normals) } else { None },
+        indices,
+    })
+}
+
+/// Writes a triangle mesh to a binary little-endian PLY file.
+pub fn write_ply(
+    file_path: &Path,
+    vertices: &[Point3<f32>],
+    normals: Option<&[Vector3<f32>]>,
+    indices: &[[u32; 3]],
+) -> IoResult<()> {
+    let mut out = BufWriter::new(File::create(file_path)?);
+
+    writeln!(out, "ply")?;
+    writeln!(out, "format binary_little_endian 1.0")?;
+    writeln!(out, "comment written by starman")?;
+    writeln!(out, "element vertex {}", vertices.len())?;
+    writeln!(out, "property float x")?;
+    writeln!(out, "property float y")?;
+    writeln!(out, "property float z")?;
+
+    if normals.is_some() {
+        writeln!(out, "property float nx")?;
+        writeln!(out, "property float ny")?;
+        writeln!(out, "property float nz")?;
+    }
+
+    writeln!(out, "element face {}", indices.len())?;
+    writeln!(out, "property list uchar uint vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    for (i, v) in vertices.iter().enumerate() {
+        for c in v.coords.iter() {
+            out.write_all(&c.to_le_bytes())?;
+        }
+
+        if let Some(normals) = normals {
+            for c in normals[i].iter() {
+                out.write_all(&c.to_le_bytes())?;
+            }
+        }
+    }
+
+    for face in indices {
+        out.write_all(&[3])?;
+
+        for i in face {
+            out.write_all(&i.to_le_bytes())?;
+        }
+    }
+
+    out.flush()
+}
+
+fn parse_header(data: &[u8]) -> Result<(Format, Vec<Element>, usize), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut first = true;
+
+    loop {
+        let end = data[offset..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or("Unterminated PLY header")?;
+        let line = String::from_utf8_lossy(&data[offset..offset + end]);
+        let line = line.trim();
+        offset += end + 1;
+
+        if first {
+            if line != "ply" {
+                return Err("Not a PLY file".to_string());
+            }
+
+            first = false;
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["format", f, ..] => {
+                format = Some(match *f {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(format!("Unsupported PLY format: {}", f)),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|e| format!("Invalid element count: {}", e))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_ty, item_ty, name] => elements
+                .last_mut()
+                .ok_or("Property declared before any element")?
+                .properties
+                .push(Property::List(
+                    name.to_string(),
+                    parse_scalar(count_ty)?,
+                    parse_scalar(item_ty)?,
+                )),
+            ["property", ty, name] => elements
+                .last_mut()
+                .ok_or("Property declared before any element")?
+                .properties
+                .push(Property::Scalar(name.to_string(), parse_scalar(ty)?)),
+            ["end_header"] => break,
+            _ => {} // Comments, obj_info, and blank lines.
+        }
+    }
+
+    let format = format.ok_or("Missing PLY format declaration")?;
+    Ok((format, elements, offset))
+}
+
+fn parse_scalar(name: &str) -> Result<Scalar, String> {
+    match name {
+        "char" | "int8" => Ok(Scalar::I8),
+        "uchar" | "uint8" => Ok(Scalar::U8),
+        "short" | "int16" => Ok(Scalar::I16),
+        "ushort" | "uint16" => Ok(Scalar::U16),
+        "int" | "int32" => Ok(Scalar::I32),
+        "uint" | "uint32" => Ok(Scalar::U32),
+        "float" | "float32" => Ok(Scalar::F32),
+        "double" | "float64" => Ok(Scalar::F64),
+        _ => Err(format!("Unknown PLY property type: {}", name)),
+    }
+}
+
+fn scalar_size(ty: Scalar) -> usize {
+    match ty {
+        Scalar::I8 | Scalar::U8 => 1,
+        Scalar::I16 | Scalar::U16 => 2,
+        Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+        Scalar::F64 => 8,
+    }
+}
+
+enum Reader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        offset: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        match self {
+            Reader::Ascii(words) => words
+                .next()
+                .ok_or_else(|| "Unexpected end of PLY data".to_string())?
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid PLY value: {}", e)),
+            Reader::Binary {
+                data,
+                offset,
+                big_endian,
+            } => {
+                let size = scalar_size(ty);
+                let bytes = data
+                    .get(*offset..*offset + size)
+                    .ok_or("Unexpected end of PLY data")?;
+                *offset += size;
+
+                let mut buf = [0u8; 8];
+                buf[..size].copy_from_slice(bytes);
+
+                if *big_endian {
+                    buf[..size].reverse();
+                }
+
+                let value = match ty {
+                    Scalar::I8 => buf[0] as i8 as f64,
+                    Scalar::U8 => buf[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(buf),
+                };
+
+                Ok(value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, data: &[u8]) -> Result<Mesh, String> {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        let mesh = load_ply(&path);
+        let _ = fs::remove_file(&path);
+        mesh
+    }
+
+    #[test]
+    fn ascii_polygons_are_triangulated_as_fans() {
+        let ply = "ply\n\
+                   format ascii 1.0\n\
+                   comment a quad\n\
+                   element vertex 4\n\
+                   property float x\n\
+                   property float y\n\
+                   property float z\n\
+                   property uchar red\n\
+                   element face 1\n\
+                   property list uchar int vertex_indices\n\
+                   element edge 1\n\
+                   property int vertex1\n\
+                   property int vertex2\n\
+                   end_header\n\
+                   0 0 0 255\n1 0 0 255\n1 1 0 255\n0 1 0 255\n\
+                   4 0 1 2 3\n\
+                   0 2\n";
+        let mesh = load("starman_ascii.ply", ply.as_bytes()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.vertices[2], Point3::new(1.0, 1.0, 0.0));
+        assert!(mesh.normals.is_none());
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn binary_big_endian_files_are_read() {
+        let mut ply = b"ply\n\
+                        format binary_big_endian 1.0\n\
+                        element vertex 3\n\
+                        property float x\n\
+                        property float y\n\
+                        property float z\n\
+                        property double nx\n\
+                        property double ny\n\
+                        property double nz\n\
+                        element face 1\n\
+                        property list uchar ushort vertex_index\n\
+                        end_header\n"
+            .to_vec();
+
+        for v in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            for c in v {
+                ply.extend_from_slice(&c.to_be_bytes());
+            }
+
+            for c in [0.0f64, 0.0, 1.0] {
+                ply.extend_from_slice(&c.to_be_bytes());
+            }
+        }
+
+        ply.push(3);
+
+        for i in [0u16, 1, 2] {
+            ply.extend_from_slice(&i.to_be_bytes());
+        }
+
+        let mesh = load("starman_big_endian.ply", &ply).unwrap();
+
+        assert_eq!(mesh.vertices[1], Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(mesh.normals, Some(vec![Vector3::z(); 3]));
+        assert_eq!(mesh.indices, [0, 1, 2]);
+
+        let truncated = &ply[..ply.len() - 1];
+        assert!(load("starman_truncated.ply", truncated).is_err());
+    }
+
+    #[test]
+    fn written_meshes_are_read_back() {
+        let vertices = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.5),
+        ];
+        let normals = [Vector3::z(), Vector3::z(), Vector3::x(), Vector3::y()];
+        let indices = [[0, 1, 2], [0, 2, 3]];
+
+        let path = std::env::temp_dir().join("starman_round_trip.ply");
+        write_ply(&path, &vertices, Some(&normals), &indices).unwrap();
+        let mesh = load_ply(&path);
+        let _ = fs::remove_file(&path);
+        let mesh = mesh.unwrap();
+
+        assert_eq!(mesh.vertices, vertices);
+        assert_eq!(mesh.normals, Some(normals.to_vec()));
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let header = "format ascii 1.0\nelement vertex 1\nproperty float x\n";
+
+        assert!(load("starman_not_ply.ply", header.as_bytes()).is_err());
+        assert!(load("starman_no_end.ply", format!("ply\n{}", header).as_bytes()).is_err());
+        assert!(load(
+            "starman_bad_type.ply",
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n0\n"
+        )
+        .is_err());
+        assert!(load(
+            "starman_bad_index.ply",
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n\
+              element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+              0\n3 0 0 1\n"
+        )
+        .is_err());
+    }
+}
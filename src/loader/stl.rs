@@ -0,0 +1,268 @@
+use nalgebra::{Point3, Vector3};
+use std::fs::{self, File};
+use std::io::{BufWriter, Result as IoResult, Write};
+use std::path::Path;
+
+pub struct Mesh {
+    /// The name given after `solid` in ASCII files.
+    pub name: Option<String>,
+    /// Three vertices per triangle; STL files do not share vertices between facets.
+    pub vertices: Vec<Point3<f32>>,
+    /// The facet normal of each vertex, recomputed from the triangles.
+    pub normals: Vec<Vector3<f32>>,
+}
+
+/// Loads an ASCII or binary STL file.
+pub fn load_stl(file_path: &Path) -> Result<Mesh, String> {
+    let data = fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let vertices = if is_binary(&data) {
+        parse_binary(&data)?
+    } else {
+        let text = String::from_utf8_lossy(&data);
+        return parse_ascii(&text);
+    };
+
+    Ok(with_facet_normals(None, vertices))
+}
+
+// A binary file has a 80-bytes header, a triangle count, and 50 bytes per triangle. ASCII files
+// start with `solid`, but so do some binary files, so the size is checked first.
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() < 84 {
+        return false;
+    }
+
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    data.len() == 84 + count * 50 || !data.starts_with(b"solid")
+}
+
+fn parse_binary(data: &[u8]) -> Result<Vec<Point3<f32>>, String> {
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+
+    if data.len() < 84 + count * 50 {
+        return Err(format!(
+            "Truncated binary STL: {} triangles announced, {} bytes found",
+            count,
+            data.len()
+        ));
+    }
+
+    let read_f32 = |offset: usize| {
+        f32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+
+    let mut vertices = Vec::with_capacity(count * 3);
+
+    for i in 0..count {
+        // Skip the stored normal, 12 bytes.
+        let facet = 84 + i * 50 + 12;
+
+        for v in 0..3 {
+            let offset = facet + v * 12;
+            vertices.push(Point3::new(
+                read_f32(offset),
+                read_f32(offset + 4),
+                read_f32(offset + 8),
+            ));
+        }
+    }
+
+    Ok(vertices)
+}
+
+fn parse_ascii(text: &str) -> Result<Mesh, String> {
+    let mut name = None;
+    let mut vertices = Vec::new();
+
+    for (l, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("solid") => {
+                let solid_name: Vec<&str> = words.collect();
+
+                if name.is_none() && !solid_name.is_empty() {
+                    name = Some(solid_name.join(" "));
+                }
+            }
+            Some("vertex") => {
+                let coords: Vec<f32> = words
+                    .map(|w| w.parse::<f32>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("At line {}: {}", l + 1, e))?;
+
+                if coords.len() != 3 {
+                    return Err(format!("At line {}: expected 3 coordinates", l + 1));
+                }
+
+                vertices.push(Point3::new(coords[0], coords[1], coords[2]));
+            }
+            _ => {}
+        }
+    }
+
+    if vertices.len() % 3 != 0 {
+        return Err("The number of vertices is not a multiple of 3".to_string());
+    }
+
+    Ok(with_facet_normals(name, vertices))
+}
+
+fn with_facet_normals(name: Option<String>, vertices: Vec<Point3<f32>>) -> Mesh {
+    let mut normals = Vec::with_capacity(vertices.len());
+
+    for tri in vertices.chunks(3) {
+        let n = (tri[1] - tri[0])
+            .cross(&(tri[2] - tri[0]))
+            .try_normalize(1.0e-12)
+            .unwrap_or_else(Vector3::z);
+        normals.extend_from_slice(&[n, n, n]);
+    }
+
+    Mesh {
+        name,
+        vertices,
+        normals,
+    }
+}
+
+/// Writes triangles to a binary STL file.
+///
+/// Facet normals are computed from the triangles.
+pub fn write_stl(file_path: &Path, triangles: &[[Point3<f32>; 3]]) -> IoResult<()> {
+    let mut out = BufWriter::new(File::create(file_path)?);
+
+    let mut header = [0u8; 80];
+    let title = b"binary STL written by starman";
+    header[..title.len()].copy_from_slice(title);
+    out.write_all(&header)?;
+    out.write_all(&(triangles.len() as u32).to_le_bytes())?;
+
+    for tri in triangles {
+        let n = (tri[1] - tri[0])
+            .cross(&(tri[2] - tri[0]))
+            .try_normalize(1.0e-12)
+            .unwrap_or_else(Vector3::zeros);
+
+        for c in n.iter().chain(tri.iter().flat_map(|p| p.coords.iter())) {
+            out.write_all(&c.to_le_bytes())?;
+        }
+
+        // Attribute byte count, unused.
+        out.write_all(&[0, 0])?;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, data: &[u8]) -> Result<Mesh, String> {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        let mesh = load_stl(&path);
+        let _ = fs::remove_file(&path);
+        mesh
+    }
+
+    fn binary(header: &[u8], triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut stl = vec![0; 80];
+        stl[..header.len()].copy_from_slice(header);
+        stl.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+
+        for tri in triangles {
+            // A wrong stored normal, which is recomputed.
+            stl.extend_from_slice(&[0; 12]);
+
+            for c in tri.iter().flatten() {
+                stl.extend_from_slice(&c.to_le_bytes());
+            }
+
+            stl.extend_from_slice(&[0, 0]);
+        }
+
+        stl
+    }
+
+    #[test]
+    fn ascii_files_are_read_with_their_name() {
+        let stl = "solid my part\n\
+                   facet normal 0 0 0\n\
+                   outer loop\n\
+                   vertex 0 0 0\n\
+                   vertex 1 0 0\n\
+                   vertex 0 1 0\n\
+                   endloop\n\
+                   endfacet\n\
+                   endsolid my part\n";
+        let mesh = load("starman_ascii.stl", stl.as_bytes()).unwrap();
+
+        assert_eq!(mesh.name.as_deref(), Some("my part"));
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.normals, [Vector3::z(); 3]);
+    }
+
+    #[test]
+    fn ascii_errors_report_their_line_number() {
+        let stl = "solid\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n";
+        let error = load("starman_bad_vertex.stl", stl.as_bytes())
+            .err()
+            .unwrap();
+
+        assert!(error.starts_with("At line 4:"), "{}", error);
+        assert!(load(
+            "starman_two_vertices.stl",
+            b"solid\nvertex 0 0 0\nvertex 1 0 0\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn binary_files_starting_with_solid_are_read_as_binary() {
+        let tri = [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+        let stl = binary(b"solid but binary", &[tri]);
+        let mesh = load("starman_solid_binary.stl", &stl).unwrap();
+
+        assert!(mesh.name.is_none());
+        assert_eq!(mesh.vertices[1], Point3::new(0.0, 1.0, 0.0));
+        assert_eq!(mesh.normals, [-Vector3::z(); 3]);
+
+        let truncated = binary(b"binary", &[tri, tri]);
+        let truncated = &truncated[..truncated.len() - 10];
+        assert!(load("starman_truncated.stl", truncated).is_err());
+    }
+
+    #[test]
+    fn written_triangles_are_read_back() {
+        let triangles = [
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            [
+                Point3::new(0.0, 0.0, 1.0),
+                Point3::new(0.0, 1.0, 1.0),
+                Point3::new(1.0, 0.0, 1.0),
+            ],
+        ];
+
+        let path = std::env::temp_dir().join("starman_round_trip.stl");
+        write_stl(&path, &triangles).unwrap();
+        let mesh = load_stl(&path);
+        let _ = fs::remove_file(&path);
+        let mesh = mesh.unwrap();
+
+        assert_eq!(mesh.vertices, triangles.concat());
+        assert_eq!(mesh.normals[0], Vector3::z());
+        assert_eq!(mesh.normals[3], -Vector3::z());
+    }
+}
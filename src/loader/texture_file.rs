@@ -0,0 +1,165 @@
+//! Textures stored in GPU formats, with their mip chain.
+
+use image::DynamicImage;
+use std::path::Path;
+
+use crate::loader::{block_decode, dds, ktx2};
+
+/// The pixel format of a texture file.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgb8,
+    Rgba8,
+    /// BC1 (DXT1) without alpha.
+    Bc1,
+    /// BC1 (DXT1) with 1-bit alpha.
+    Bc1Alpha,
+    /// BC2 (DXT3).
+    Bc2,
+    /// BC3 (DXT5).
+    Bc3,
+    /// BC4, a single channel.
+    Bc4,
+    /// BC5, two channels.
+    Bc5,
+    /// BC6H, unsigned half-floats.
+    Bc6hUfloat,
+    /// BC6H, signed half-floats.
+    Bc6hSfloat,
+    Bc7,
+    Etc2Rgb8,
+    /// ETC2 with 1-bit alpha.
+    Etc2Rgb8A1,
+    /// ETC2 with EAC alpha.
+    Etc2Rgba8,
+}
+
+impl TextureFormat {
+    /// The size in bytes of a block of 4x4 pixels, or `None` for uncompressed formats.
+    pub fn block_bytes(self) -> Option<usize> {
+        match self {
+            TextureFormat::Rgb8 | TextureFormat::Rgba8 => None,
+            TextureFormat::Bc1
+            | TextureFormat::Bc1Alpha
+            | TextureFormat::Bc4
+            | TextureFormat::Etc2Rgb8
+            | TextureFormat::Etc2Rgb8A1 => Some(8),
+            _ => Some(16),
+        }
+    }
+
+    /// The size in bytes of an image of this format.
+    pub fn image_bytes(self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+
+        match self {
+            TextureFormat::Rgb8 => width * height * 3,
+            TextureFormat::Rgba8 => width * height * 4,
+            _ => {
+                let blocks = width.div_ceil(4) * height.div_ceil(4);
+                blocks * self.block_bytes().unwrap()
+            }
+        }
+    }
+}
+
+/// A texture read from a KTX2 or DDS file.
+pub struct TextureFile {
+    pub format: TextureFormat,
+    /// Whether the color channels are sRGB-encoded.
+    pub srgb: bool,
+    pub width: u32,
+    pub height: u32,
+    /// The pixels of each mip level, starting with the full-size image.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl TextureFile {
+    /// Creates a texture file, checking the size of each level.
+    pub fn new(
+        format: TextureFormat,
+        srgb: bool,
+        width: u32,
+        height: u32,
+        levels: Vec<Vec<u8>>,
+    ) -> Result<TextureFile, String> {
+        if width == 0 || height == 0 {
+            return Err("Empty texture".to_string());
+        }
+
+        if levels.is_empty() {
+            return Err("Texture without mip level".to_string());
+        }
+
+        let file = TextureFile {
+            format,
+            srgb,
+            width,
+            height,
+            levels,
+        };
+
+        for (i, level) in file.levels.iter().enumerate() {
+            let (w, h) = file.level_size(i);
+            let expected = format.image_bytes(w, h);
+
+            if level.len() != expected {
+                return Err(format!(
+                    "Mip level {} has {} bytes, expected {} for {}x{} {:?} pixels",
+                    i,
+                    level.len(),
+                    expected,
+                    w,
+                    h,
+                    format
+                ));
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// The size of a mip level.
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Whether the levels go down to 1x1 pixel.
+    pub fn has_full_mip_chain(&self) -> bool {
+        self.level_size(self.levels.len() - 1) == (1, 1)
+    }
+
+    /// The total size of the levels in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.levels.iter().map(|l| l.len()).sum()
+    }
+
+    /// Decodes a mip level on the CPU.
+    ///
+    /// BC4 images are decoded to gray, BC5 images to red and green, BC6H images to 32-bit floats
+    /// and other formats to 8-bit RGB or RGBA.
+    pub fn decode_level(&self, level: usize) -> DynamicImage {
+        let (width, height) = self.level_size(level);
+        block_decode::decode(self.format, width, height, &self.levels[level])
+    }
+}
+
+/// Whether `path` has the extension of a file read by `load_texture_file`.
+pub fn is_texture_file(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "ktx2" | "dds")
+}
+
+/// Loads a KTX2 or DDS file, depending on its extension.
+pub fn load_texture_file(path: &Path) -> Result<TextureFile, String> {
+    match extension(path).as_str() {
+        "ktx2" => ktx2::load_ktx2(path),
+        "dds" => dds::load_dds(path),
+        _ => Err(format!("Unsupported texture file: {}", path.display())),
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
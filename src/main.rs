@@ -3,15 +3,12 @@ extern crate serde_derive;
 extern crate serde;
 extern crate bitflags;
 
-use light::Light;
-use nalgebra::{Isometry3, Point2, Point3, Translation3, UnitQuaternion, Vector3};
-use scene::object::Object;
-use std::f32;
-use std::path::Path;
+use std::env;
+use std::process;
 
 mod window;
-use window::window::Window;
 
+mod cli;
 mod loader;
 mod light;
 mod resource;
@@ -28,41 +25,10 @@ mod post_processing;
 mod planar_line_renderer;
 
 fn main() {
-    let mut window = Window::new("Starman Project");
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let obj_path = Path::new("assets/rocket/rocket.obj");
-    let mtl_path = Path::new("assets/rocket/rocket.mtl");
-    let glb_path = Path::new("assets/box.glb");
-
-    let mut glb = window.add_glb(glb_path, Vector3::new(0.1, 0.1, 0.1));
-    glb.append_translation(&Translation3::new(0.0, 0.0, 0.0));
-
-    let mut rocket = window.add_obj(obj_path, mtl_path, Vector3::new(0.1, 0.1, 0.1), Vector3::new(0.5, 0.0, 0.9));
-
-    window.set_light(Light::StickToCamera);
-
-    let rot_rocket = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.014);
-
-    while window.render() {
-        rocket.prepend_to_local_rotation(&rot_rocket);
-        glb.prepend_to_local_rotation(&rot_rocket);
-        window.draw_compass(10.0, &Point3::new(1.0, 0.0, 0.0));
-
-        window.draw_line(
-            &Point3::origin(),
-            &Point3::new(1.0, 0.0, 0.0),
-            &Point3::new(1.0, 0.0, 0.0),
-        );
-        window.draw_line(
-            &Point3::origin(),
-            &Point3::new(0.0, 1.0, 0.0),
-            &Point3::new(0.0, 1.0, 0.0),
-        );
-        window.draw_line(
-            &Point3::origin(),
-            &Point3::new(0.0, 0.0, 1.0),
-            &Point3::new(0.0, 0.0, 1.0),
-        );
+    if let Err(e) = cli::run(&args) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
     }
 }
-
@@ -19,7 +19,8 @@ pub struct PlanarLineRenderer {
 
 impl PlanarLineRenderer {
     pub fn new() -> PlanarLineRenderer {
-        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
@@ -32,7 +32,8 @@ impl Waves {
         fbo_vertices.load_to_gpu();
         fbo_vertices.unload_from_ram();
 
-        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER);
+        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
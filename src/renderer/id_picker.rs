@@ -0,0 +1,112 @@
+//! Object picking by rendering unique per-object colors to an offscreen buffer.
+
+use crate::builtin::id_material::{color_to_id, IdMaterial};
+use crate::camera::camera::Camera;
+use crate::context::context::Context;
+use crate::light::Light;
+use crate::resource::framebuffer_manager::{FramebufferManager, RenderTarget};
+use crate::resource::material::Material;
+use crate::scene::scene_node::SceneNode;
+use crate::verify;
+
+pub struct IdPicker {
+    material: IdMaterial,
+    target: RenderTarget,
+    size: (usize, usize),
+}
+
+impl IdPicker {
+    pub fn new() -> IdPicker {
+        IdPicker {
+            material: IdMaterial::new(),
+            target: FramebufferManager::new_render_target(1, 1, false),
+            size: (1, 1),
+        }
+    }
+
+    /// Returns the nodes with an object covering at least one pixel of the given rectangle.
+    ///
+    /// The scene is rendered as seen by `camera` on a viewport of `viewport_size` pixels. The
+    /// rectangle `(x, y, width, height)` is expressed in that viewport, with its origin at the
+    /// top-left corner. Nodes are returned in scene traversal order, without duplicates.
+    pub fn pick_rect(
+        &mut self,
+        framebuffer_manager: &mut FramebufferManager,
+        scene: &SceneNode,
+        camera: &mut dyn Camera,
+        viewport_size: (usize, usize),
+        rect: (i32, i32, i32, i32),
+    ) -> Vec<SceneNode> {
+        let (vw, vh) = (viewport_size.0.max(1), viewport_size.1.max(1));
+        let x0 = rect.0.max(0);
+        let y0 = rect.1.max(0);
+        let x1 = (rect.0 + rect.2).min(vw as i32);
+        let y1 = (rect.1 + rect.3).min(vh as i32);
+
+        if x0 >= x1 || y0 >= y1 {
+            return Vec::new();
+        }
+
+        if self.size != (vw, vh) {
+            self.target.resize(vw as f32, vh as f32);
+            self.size = (vw, vh);
+        }
+
+        let ctxt = Context::get();
+        framebuffer_manager.select(&self.target);
+        verify!(ctxt.viewport(0, 0, vw as i32, vh as i32));
+        verify!(ctxt.scissor(0, 0, vw as i32, vh as i32));
+        verify!(ctxt.disable(Context::BLEND));
+        verify!(ctxt.clear_color(0.0, 0.0, 0.0, 0.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+
+        // Identifier 0 is the background.
+        let mut nodes = Vec::new();
+        let material = &mut self.material;
+        let light = Light::StickToCamera;
+
+        scene.apply_to_rendered_objects(&mut |node, o, transform, scale| {
+            nodes.push(node.clone());
+            material.set_id(nodes.len() as u32);
+            material.render(
+                0,
+                transform,
+                scale,
+                camera,
+                &light,
+                o.data(),
+                &mut o.mesh().borrow_mut(),
+            );
+        });
+
+        let (w, h) = ((x1 - x0) as usize, (y1 - y0) as usize);
+        let mut pixels = vec![0u8; w * h * 4];
+        verify!(ctxt.pixel_storei(Context::PACK_ALIGNMENT, 1));
+        verify!(ctxt.read_pixels(
+            x0,
+            vh as i32 - y1,
+            w as i32,
+            h as i32,
+            Context::RGBA,
+            Some(&mut pixels)
+        ));
+
+        framebuffer_manager.select(&FramebufferManager::screen());
+
+        let mut seen = vec![false; nodes.len()];
+
+        for px in pixels.chunks(4) {
+            let id = color_to_id(px) as usize;
+
+            if id != 0 && id <= nodes.len() {
+                seen[id - 1] = true;
+            }
+        }
+
+        nodes
+            .into_iter()
+            .zip(seen)
+            .filter_map(|(node, seen)| if seen { Some(node) } else { None })
+            .collect()
+    }
+}
@@ -18,7 +18,8 @@ pub struct LineRenderer {
 
 impl LineRenderer {
     pub fn new() -> LineRenderer {
-        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
@@ -1,3 +1,5 @@
+pub mod id_picker;
 pub mod line_renderer;
+pub mod outline_renderer;
 pub mod point_renderer;
 pub mod renderer;
@@ -0,0 +1,220 @@
+//! Outlines drawn around the silhouette of highlighted scene nodes.
+
+use nalgebra::{Point4, Vector2};
+
+use crate::builtin::id_material::IdMaterial;
+use crate::camera::camera::Camera;
+use crate::context::context::Context;
+use crate::light::Light;
+use crate::resource::effect::{Effect, ShaderAttribute, ShaderUniform};
+use crate::resource::framebuffer_manager::{FramebufferManager, RenderTarget};
+use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec};
+use crate::resource::material::Material;
+use crate::scene::highlight::MAX_HIGHLIGHT_WIDTH;
+use crate::scene::scene_node::SceneNode;
+use crate::verify;
+
+/// Draws the outlines of the highlighted nodes of a scene on top of what is already rendered.
+///
+/// Highlighted objects are first rendered to an offscreen mask storing the outline color and
+/// width of each pixel. The mask is then dilated on a full-screen quad, drawing only the pixels
+/// outside of the silhouettes.
+pub struct OutlineRenderer {
+    material: IdMaterial,
+    mask: RenderTarget,
+    size: (usize, usize),
+    shader: Effect,
+    texel: ShaderUniform<Vector2<f32>>,
+    mask_texture: ShaderUniform<i32>,
+    v_coord: ShaderAttribute<Vector2<f32>>,
+    quad: GPUVec<Vector2<f32>>,
+}
+
+impl OutlineRenderer {
+    pub fn new() -> OutlineRenderer {
+        let quad: Vec<Vector2<f32>> = vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(-1.0, 1.0),
+            Vector2::new(1.0, 1.0),
+        ];
+
+        let mut quad = GPUVec::new(quad, BufferType::Array, AllocationType::StaticDraw);
+        quad.load_to_gpu();
+        quad.unload_from_ram();
+
+        let mut shader = Effect::new_from_str(OUTLINE_VERTEX_SRC, OUTLINE_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        shader.use_program();
+
+        OutlineRenderer {
+            material: IdMaterial::new(),
+            mask: FramebufferManager::new_render_target(1, 1, false),
+            size: (1, 1),
+            texel: shader.get_uniform("texel").unwrap(),
+            mask_texture: shader.get_uniform("mask").unwrap(),
+            v_coord: shader.get_attrib("v_coord").unwrap(),
+            quad,
+            shader,
+        }
+    }
+
+    /// Draws the outlines of the highlighted nodes of `scene` on the screen.
+    ///
+    /// `viewport` is the `(x, y, width, height)` screen area the scene was rendered to. Nothing
+    /// is drawn if no node is highlighted.
+    pub fn render(
+        &mut self,
+        framebuffer_manager: &mut FramebufferManager,
+        scene: &SceneNode,
+        camera: &mut dyn Camera,
+        viewport: (i32, i32, i32, i32),
+    ) {
+        let mut objects = Vec::new();
+
+        scene.apply_to_rendered_objects(&mut |node, _, transform, scale| {
+            if let Some(highlight) = node.inherited_highlight() {
+                objects.push((node.clone(), *transform, *scale, highlight));
+            }
+        });
+
+        if objects.is_empty() {
+            return;
+        }
+
+        let (x, y, w, h) = viewport;
+        let size = (w.max(1) as usize, h.max(1) as usize);
+
+        if self.size != size {
+            self.mask.resize(size.0 as f32, size.1 as f32);
+            self.size = size;
+        }
+
+        let ctxt = Context::get();
+
+        /*
+         * Render the mask: the outline color in RGB, the outline width in alpha.
+         */
+        framebuffer_manager.select(&self.mask);
+        verify!(ctxt.viewport(0, 0, size.0 as i32, size.1 as i32));
+        verify!(ctxt.scissor(0, 0, size.0 as i32, size.1 as i32));
+        verify!(ctxt.disable(Context::BLEND));
+        verify!(ctxt.clear_color(0.0, 0.0, 0.0, 0.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+
+        let light = Light::StickToCamera;
+
+        for (node, transform, scale, highlight) in objects.iter() {
+            let data = node.data();
+            let o = match data.object() {
+                Some(o) => o,
+                None => continue,
+            };
+            let width = highlight.width.clamp(1.0, MAX_HIGHLIGHT_WIDTH).round();
+            self.material.set_color(Point4::new(
+                highlight.color.x,
+                highlight.color.y,
+                highlight.color.z,
+                width / 255.0,
+            ));
+            self.material.render(
+                0,
+                transform,
+                scale,
+                camera,
+                &light,
+                o.data(),
+                &mut o.mesh().borrow_mut(),
+            );
+        }
+
+        /*
+         * Dilate the mask on top of the screen.
+         */
+        framebuffer_manager.select(&FramebufferManager::screen());
+        verify!(ctxt.viewport(x, y, w, h));
+        verify!(ctxt.scissor(x, y, w, h));
+        verify!(ctxt.disable(Context::DEPTH_TEST));
+        verify!(ctxt.enable(Context::BLEND));
+        verify!(ctxt.blend_func_separate(
+            Context::SRC_ALPHA,
+            Context::ONE_MINUS_SRC_ALPHA,
+            Context::ONE,
+            Context::ONE_MINUS_SRC_ALPHA,
+        ));
+
+        self.shader.use_program();
+        self.v_coord.enable();
+
+        self.texel
+            .upload(&Vector2::new(1.0 / size.0 as f32, 1.0 / size.1 as f32));
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, self.mask.texture_id()));
+        self.mask_texture.upload(&0);
+
+        self.v_coord.bind(&mut self.quad);
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        self.v_coord.disable();
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, None));
+        verify!(ctxt.disable(Context::BLEND));
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+    }
+}
+
+static OUTLINE_VERTEX_SRC: &str = "#version 100
+attribute vec2 v_coord;
+varying vec2 f_texcoord;
+
+void main() {
+    gl_Position = vec4(v_coord, 0.0, 1.0);
+    f_texcoord = (v_coord + 1.0) / 2.0;
+}
+";
+
+// NOTE: the loop bounds must match `MAX_HIGHLIGHT_WIDTH`.
+static OUTLINE_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+uniform sampler2D mask;
+uniform vec2 texel;
+varying vec2 f_texcoord;
+
+void main() {
+    if (texture2D(mask, f_texcoord).a > 0.0) {
+        discard;
+    }
+
+    vec3 color = vec3(0.0);
+    float coverage = 0.0;
+
+    for (int i = -8; i <= 8; ++i) {
+        for (int j = -8; j <= 8; ++j) {
+            vec2 offset = vec2(float(i), float(j));
+            vec4 texel_value = texture2D(mask, f_texcoord + offset * texel);
+
+            if (texel_value.a > 0.0) {
+                float width = texel_value.a * 255.0;
+                float c = clamp(width + 0.5 - length(offset), 0.0, 1.0);
+
+                if (c > coverage) {
+                    coverage = c;
+                    color = texel_value.rgb;
+                }
+            }
+        }
+    }
+
+    if (coverage == 0.0) {
+        discard;
+    }
+
+    gl_FragColor = vec4(color, coverage);
+}
+";
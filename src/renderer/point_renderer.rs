@@ -18,7 +18,8 @@ pub struct PointRenderer {
 
 impl PointRenderer {
     pub fn new() -> PointRenderer {
-        let mut shader = Effect::new_from_str(POINTS_VERTEX_SRC, POINTS_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(POINTS_VERTEX_SRC, POINTS_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
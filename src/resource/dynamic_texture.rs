@@ -0,0 +1,362 @@
+//! Textures updated from CPU pixel buffers.
+
+use image::{DynamicImage, ImageBuffer};
+use std::rc::Rc;
+use std::slice;
+
+use crate::context::context::{Buffer, Context, Texture};
+use crate::resource::texture_manager::{
+    self, ColorSpace, PixelFormat, SamplerOptions, TextureManager,
+};
+use crate::verify;
+
+/// The layout of the pixels passed to the updates of a `DynamicTexture`.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum DynamicTextureFormat {
+    Luma8,
+    Rgb8,
+    Rgba8,
+    /// Gray 32-bit floats, for data such as heatmaps.
+    Luma32F,
+    Rgb32F,
+    Rgba32F,
+}
+
+impl DynamicTextureFormat {
+    /// The number of channels of a pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            DynamicTextureFormat::Luma8 | DynamicTextureFormat::Luma32F => 1,
+            DynamicTextureFormat::Rgb8 | DynamicTextureFormat::Rgb32F => 3,
+            DynamicTextureFormat::Rgba8 | DynamicTextureFormat::Rgba32F => 4,
+        }
+    }
+
+    /// Whether pixels are 32-bit floats, given to `update_f32` instead of `update`.
+    pub fn is_float(self) -> bool {
+        matches!(
+            self,
+            DynamicTextureFormat::Luma32F
+                | DynamicTextureFormat::Rgb32F
+                | DynamicTextureFormat::Rgba32F
+        )
+    }
+
+    // The pixels as an image. Gray floats become RGB since images have no such format.
+    fn image(self, width: u32, height: u32, pixels: Pixels) -> DynamicImage {
+        let (w, h) = (width, height);
+
+        match (self, pixels) {
+            (DynamicTextureFormat::Luma8, Pixels::U8(p)) => {
+                DynamicImage::ImageLuma8(ImageBuffer::from_raw(w, h, p.to_vec()).unwrap())
+            }
+            (DynamicTextureFormat::Rgb8, Pixels::U8(p)) => {
+                DynamicImage::ImageRgb8(ImageBuffer::from_raw(w, h, p.to_vec()).unwrap())
+            }
+            (DynamicTextureFormat::Rgba8, Pixels::U8(p)) => {
+                DynamicImage::ImageRgba8(ImageBuffer::from_raw(w, h, p.to_vec()).unwrap())
+            }
+            (DynamicTextureFormat::Luma32F, Pixels::F32(p)) => {
+                let rgb = p.iter().flat_map(|v| [*v; 3]).collect();
+                DynamicImage::ImageRgb32F(ImageBuffer::from_raw(w, h, rgb).unwrap())
+            }
+            (DynamicTextureFormat::Rgb32F, Pixels::F32(p)) => {
+                DynamicImage::ImageRgb32F(ImageBuffer::from_raw(w, h, p.to_vec()).unwrap())
+            }
+            (DynamicTextureFormat::Rgba32F, Pixels::F32(p)) => {
+                DynamicImage::ImageRgba32F(ImageBuffer::from_raw(w, h, p.to_vec()).unwrap())
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+// The pixels of an update.
+#[derive(Copy, Clone)]
+enum Pixels<'a> {
+    U8(&'a [u8]),
+    F32(&'a [f32]),
+}
+
+impl<'a> Pixels<'a> {
+    fn len(self) -> usize {
+        match self {
+            Pixels::U8(p) => p.len(),
+            Pixels::F32(p) => p.len(),
+        }
+    }
+
+    fn as_bytes(self) -> &'a [u8] {
+        match self {
+            Pixels::U8(p) => p,
+            Pixels::F32(p) => unsafe {
+                slice::from_raw_parts(p.as_ptr() as *const u8, std::mem::size_of_val(p))
+            },
+        }
+    }
+}
+
+/// A texture whose pixels are replaced from memory, for content changing every frame such as
+/// video or simulation results.
+///
+/// Pixels are given row by row, in the same order as the images given to
+/// `TextureManager::add_image`.
+pub struct DynamicTexture {
+    texture: Rc<Texture>,
+    format: DynamicTextureFormat,
+    color_space: ColorSpace,
+    pixel_format: PixelFormat,
+    // Whether pixels are converted before being uploaded, when the context cannot store the
+    // format as it is.
+    convert: bool,
+    width: u32,
+    height: u32,
+    // The pixel unpack buffer updates are streamed through.
+    buffer: Option<Buffer>,
+}
+
+impl DynamicTexture {
+    /// Creates a texture of the given size. Its pixels are undefined until it is updated.
+    pub fn new(
+        width: u32,
+        height: u32,
+        format: DynamicTextureFormat,
+        color_space: ColorSpace,
+        sampler: &SamplerOptions,
+    ) -> DynamicTexture {
+        assert!(width > 0 && height > 0, "Empty dynamic texture");
+
+        let ctxt = Context::get();
+        let (major, _) = ctxt.version();
+        let float_gray =
+            major >= 3 && !ctxt.is_embedded() && texture_manager::supports_swizzle(&ctxt);
+
+        let (pixel_format, convert) = match format {
+            DynamicTextureFormat::Luma32F if float_gray => (
+                PixelFormat {
+                    internal: Context::R32F,
+                    format: Context::RED,
+                    ty: Context::FLOAT,
+                    swizzle: Some([Context::RED, Context::RED, Context::RED, Context::ONE]),
+                },
+                false,
+            ),
+            _ => {
+                let zeros = vec![0.0; format.channels()];
+                let pixels = if format.is_float() {
+                    Pixels::F32(&zeros)
+                } else {
+                    Pixels::U8(&[0; 4][..format.channels()])
+                };
+                let image = format.image(1, 1, pixels);
+                let color = image.color();
+                let (image, pixel_format) = PixelFormat::select(image, color_space);
+
+                let convert = format == DynamicTextureFormat::Luma32F || image.color() != color;
+                (pixel_format, convert)
+            }
+        };
+
+        let texture = Texture::new();
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*texture)));
+        verify!(ctxt.tex_image2d_with_type(
+            Context::TEXTURE_2D,
+            0,
+            pixel_format.internal as i32,
+            width as i32,
+            height as i32,
+            0,
+            pixel_format.format,
+            pixel_format.ty,
+            None
+        ));
+        let _ =
+            TextureManager::set_texture_parameters(&ctxt, pixel_format.swizzle, 1, true, sampler);
+
+        DynamicTexture {
+            texture,
+            format,
+            color_space,
+            pixel_format,
+            convert,
+            width,
+            height,
+            buffer: None,
+        }
+    }
+
+    /// The texture, to be set on objects.
+    pub fn texture(&self) -> Rc<Texture> {
+        self.texture.clone()
+    }
+
+    pub fn format(&self) -> DynamicTextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The size of the pixels on the GPU, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    /// Changes how the texture is sampled.
+    pub fn set_sampler(&self, sampler: &SamplerOptions) {
+        let ctxt = Context::get();
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*self.texture)));
+        sampler.apply(&ctxt, false);
+    }
+
+    /// Streams updates through a pixel unpack buffer, so uploads do not wait for the GPU to
+    /// be done with the previous pixels.
+    ///
+    /// Returns whether streaming is used, which needs OpenGL 2.1 or OpenGL ES 3.
+    pub fn set_streaming(&mut self, enabled: bool) -> bool {
+        let ctxt = Context::get();
+        let (major, minor) = ctxt.version();
+        let supported = if ctxt.is_embedded() {
+            major >= 3
+        } else {
+            (major, minor) >= (2, 1)
+        };
+
+        if enabled && supported {
+            if self.buffer.is_none() {
+                self.buffer = Some(verify!(ctxt
+                    .create_buffer()
+                    .expect("Could not create GPU buffer.")));
+            }
+        } else if let Some(buffer) = self.buffer.take() {
+            verify!(ctxt.delete_buffer(Some(&buffer)));
+        }
+
+        self.buffer.is_some()
+    }
+
+    /// Whether updates are streamed through a pixel unpack buffer.
+    pub fn is_streaming(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    /// Replaces all the pixels of an 8-bit texture.
+    pub fn update(&mut self, pixels: &[u8]) {
+        self.update_region(0, 0, self.width, self.height, pixels)
+    }
+
+    /// Replaces the pixels of a rectangle of an 8-bit texture.
+    pub fn update_region(&mut self, x: u32, y: u32, width: u32, height: u32, pixels: &[u8]) {
+        assert!(
+            !self.format.is_float(),
+            "{:?} textures are updated with `update_f32`",
+            self.format
+        );
+        self.upload(x, y, width, height, Pixels::U8(pixels))
+    }
+
+    /// Replaces all the pixels of a floating point texture.
+    pub fn update_f32(&mut self, pixels: &[f32]) {
+        self.update_region_f32(0, 0, self.width, self.height, pixels)
+    }
+
+    /// Replaces the pixels of a rectangle of a floating point texture.
+    pub fn update_region_f32(&mut self, x: u32, y: u32, width: u32, height: u32, pixels: &[f32]) {
+        assert!(
+            self.format.is_float(),
+            "{:?} textures are updated with `update`",
+            self.format
+        );
+        self.upload(x, y, width, height, Pixels::F32(pixels))
+    }
+
+    fn upload(&mut self, x: u32, y: u32, width: u32, height: u32, pixels: Pixels) {
+        assert!(
+            x.checked_add(width).is_some_and(|r| r <= self.width)
+                && y.checked_add(height).is_some_and(|b| b <= self.height),
+            "The {}x{} region at ({}, {}) is out of the {}x{} texture",
+            width,
+            height,
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * self.format.channels(),
+            "Wrong number of values for a {}x{} {:?} region",
+            width,
+            height,
+            self.format
+        );
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let converted;
+        let bytes = if self.convert {
+            let image = self.format.image(width, height, pixels);
+            converted = PixelFormat::select(image, self.color_space).0;
+            converted.as_bytes()
+        } else {
+            pixels.as_bytes()
+        };
+
+        let ctxt = Context::get();
+        let format = &self.pixel_format;
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*self.texture)));
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+
+        if let Some(buffer) = &self.buffer {
+            verify!(ctxt.bind_buffer(Context::PIXEL_UNPACK_BUFFER, Some(buffer)));
+            // Orphans the storage of the previous update, which the GPU may still be reading.
+            verify!(ctxt.buffer_data_uninitialized(
+                Context::PIXEL_UNPACK_BUFFER,
+                bytes.len(),
+                Context::STREAM_DRAW
+            ));
+            verify!(ctxt.buffer_sub_data(Context::PIXEL_UNPACK_BUFFER, 0, bytes));
+            verify!(ctxt.tex_sub_image2d_from_buffer(
+                Context::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format.format,
+                format.ty,
+                0
+            ));
+            verify!(ctxt.bind_buffer(Context::PIXEL_UNPACK_BUFFER, None));
+        } else {
+            verify!(ctxt.tex_sub_image2d_with_type(
+                Context::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format.format,
+                format.ty,
+                bytes
+            ));
+        }
+
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 4));
+    }
+}
+
+impl Drop for DynamicTexture {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            verify!(Context::get().delete_buffer(Some(&buffer)));
+        }
+    }
+}
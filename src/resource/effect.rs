@@ -1,53 +1,106 @@
-use std::fs::File;
-use std::io::Read;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
 use std::marker::PhantomData;
 use std::mem;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
 use std::str;
 use std::sync::Mutex;
 use lazy_static::lazy_static;
 
-use crate::context::context::{Context, GLintptr, Program, Shader, UniformLocation, VertexArray};
+use crate::context::context::{
+    ActiveVariable, Context, GLintptr, Program, Shader, UniformLocation, VertexArray,
+};
 use crate::resource::gl_primitive::GLPrimitive;
 use crate::resource::gpu_vector::GPUVec;
+use crate::resource::hot_reload;
+use crate::resource::shader_diagnostics::{ShaderDiagnostic, ShaderError, ShaderErrorKind};
+use crate::resource::shader_preprocessor::{
+    self, GlslVersion, PreprocessedShader, ShaderDefines, ShaderStage,
+};
 use crate::verify;
 
+// The location of a uniform, shared by the effect and the `ShaderUniform` it handed out.
+type LocationCell = RefCell<Option<UniformLocation>>;
+
 pub struct Effect {
     program: Program,
     vshader: Shader,
     fshader: Shader,
+    source: Option<EffectSource>,
 }
 
-impl Effect {
-    pub fn new(vshader_path: &Path, fshader_path: &Path) -> Option<Effect> {
-        let mut vshader = String::new();
-        let mut fshader = String::new();
+// The files the shaders of an effect were read from, and the locations handed out, to look
+// them up again when the shaders are reloaded.
+struct EffectSource {
+    vshader: PathBuf,
+    fshader: PathBuf,
+    defines: ShaderDefines,
+    generation: u64,
+    uniforms: RefCell<Vec<(String, Weak<LocationCell>)>>,
+    attributes: RefCell<Vec<(String, u32)>>,
+}
 
-        if File::open(vshader_path)
-            .map(|mut v| v.read_to_string(&mut vshader))
-            .is_err()
-        {
-            return None;
-        }
+impl Effect {
+    /// Compiles the shaders read from files, preprocessed like with `new_from_str`.
+    ///
+    /// The shaders are compiled again when the files change while hot reloading is enabled, see
+    /// `Window::set_hot_reload`. The uniforms and attributes obtained from this effect keep
+    /// working with the reloaded shaders.
+    pub fn new(vshader_path: &Path, fshader_path: &Path) -> Result<Effect, ShaderError> {
+        Effect::new_with_defines(vshader_path, fshader_path, &ShaderDefines::new())
+    }
 
-        if File::open(fshader_path)
-            .map(|mut f| f.read_to_string(&mut fshader))
-            .is_err()
-        {
-            return None;
-        }
+    /// Like `new`, with macros defined at the top of the shaders.
+    pub fn new_with_defines(
+        vshader_path: &Path,
+        fshader_path: &Path,
+        defines: &ShaderDefines,
+    ) -> Result<Effect, ShaderError> {
+        let vshader = read_shader(vshader_path, ShaderStage::Vertex)?;
+        let fshader = read_shader(fshader_path, ShaderStage::Fragment)?;
+        let mut effect = Effect::new_from_str_with_defines(&vshader, &fshader, defines)?;
+        hot_reload::watch_shader(vshader_path);
+        hot_reload::watch_shader(fshader_path);
+        effect.source = Some(EffectSource {
+            vshader: vshader_path.to_path_buf(),
+            fshader: fshader_path.to_path_buf(),
+            defines: defines.clone(),
+            generation: hot_reload::generation(),
+            uniforms: RefCell::new(Vec::new()),
+            attributes: RefCell::new(Vec::new()),
+        });
+
+        Ok(effect)
+    }
 
-        Some(Effect::new_from_str(&vshader[..], &fshader[..]))
+    /// Compiles shaders from their GLSL code.
+    ///
+    /// The code is preprocessed first: `#include "<chunk>"` directives are replaced by the chunks
+    /// registered with `shader_preprocessor::register_chunk`, and shaders written for GLSL ES 1.00
+    /// are compiled with the GLSL version of the context, see `shader_preprocessor::preprocess`.
+    ///
+    /// Compilation errors refer to the lines of `vshader`, `fshader` and the chunks they include.
+    pub fn new_from_str(vshader: &str, fshader: &str) -> Result<Effect, ShaderError> {
+        Effect::new_from_str_with_defines(vshader, fshader, &ShaderDefines::new())
     }
 
-    pub fn new_from_str(vshader: &str, fshader: &str) -> Effect {
-        let (program, vshader, fshader) = load_shader_program(vshader, fshader);
+    /// Like `new_from_str`, with macros defined at the top of the shaders.
+    pub fn new_from_str_with_defines(
+        vshader: &str,
+        fshader: &str,
+        defines: &ShaderDefines,
+    ) -> Result<Effect, ShaderError> {
+        let (vshader, fshader) = preprocess_shaders(vshader, fshader, defines)?;
+        let (program, vshader, fshader) = load_shader_program(&vshader, &fshader, &[])?;
 
-        Effect {
+        Ok(Effect {
             program,
             vshader,
             fshader,
-        }
+            source: None,
+        })
     }
 
     pub fn get_uniform<T: GLPrimitive>(&self, name: &str) -> Option<ShaderUniform<T>> {
@@ -56,6 +109,15 @@ impl Effect {
 
         if ctxt.get_error() == 0 {
             if let Some(id) = location {
+                let id = Rc::new(RefCell::new(Some(id)));
+
+                if let Some(source) = &self.source {
+                    source
+                        .uniforms
+                        .borrow_mut()
+                        .push((name.to_string(), Rc::downgrade(&id)));
+                }
+
                 let data_type = PhantomData;
                 return Some(ShaderUniform { id, data_type });
             }
@@ -70,6 +132,11 @@ impl Effect {
 
         if ctxt.get_error() == 0 && location != -1 {
             let id = location as u32;
+
+            if let Some(source) = &self.source {
+                source.attributes.borrow_mut().push((name.to_string(), id));
+            }
+
             let data_type = PhantomData;
             return Some(ShaderAttribute { id, data_type });
         }
@@ -77,13 +144,165 @@ impl Effect {
         None
     }
 
+    /// Like `get_uniform`, but returns an error naming the uniform if the program lacks it.
+    pub fn require_uniform<T: GLPrimitive>(
+        &self,
+        name: &str,
+    ) -> Result<ShaderUniform<T>, ShaderError> {
+        self.get_uniform(name)
+            .ok_or_else(|| missing_variable("uniform", name))
+    }
+
+    /// Like `get_attrib`, but returns an error naming the attribute if the program lacks it.
+    pub fn require_attrib<T: GLPrimitive>(
+        &self,
+        name: &str,
+    ) -> Result<ShaderAttribute<T>, ShaderError> {
+        self.get_attrib(name)
+            .ok_or_else(|| missing_variable("attribute", name))
+    }
+
+    /// The uniforms used by the program, as reported by the driver. Unused uniforms are optimized
+    /// out, and arrays are named after their first element, like `lights[0]`.
+    pub fn active_uniforms(&self) -> Vec<ActiveVariable> {
+        let ctxt = Context::get();
+        (0..ctxt.get_active_uniforms(&self.program))
+            .filter_map(|i| ctxt.get_active_uniform(&self.program, i))
+            .collect()
+    }
+
+    /// The attributes used by the program, as reported by the driver.
+    pub fn active_attributes(&self) -> Vec<ActiveVariable> {
+        let ctxt = Context::get();
+        (0..ctxt.get_active_attributes(&self.program))
+            .filter_map(|i| ctxt.get_active_attribute(&self.program, i))
+            .collect()
+    }
+
+    /// Uses the program of this effect, after reloading its shaders if their files changed.
     pub fn use_program(&mut self) {
+        let changed = self.source.as_ref().is_some_and(|source| {
+            hot_reload::shader_changed_since(&source.vshader, source.generation)
+                || hot_reload::shader_changed_since(&source.fshader, source.generation)
+        });
+
+        if changed {
+            self.reload();
+        }
+
         verify!(Context::get().use_program(Some(&self.program)));
     }
+
+    // Compiles the shaders read again from their files, and keeps the current program if they
+    // do not compile. Errors are reported to be shown in the window.
+    fn reload(&mut self) {
+        let source = match &mut self.source {
+            Some(source) => source,
+            None => return,
+        };
+        source.generation = hot_reload::generation();
+
+        let name = format!("{}, {}", source.vshader.display(), source.fshader.display());
+        let result = read_shader(&source.vshader, ShaderStage::Vertex)
+            .and_then(|v| Ok((v, read_shader(&source.fshader, ShaderStage::Fragment)?)))
+            .and_then(|(v, f)| preprocess_shaders(&v, &f, &source.defines))
+            .and_then(|(v, f)| load_shader_program(&v, &f, &source.attributes.borrow()));
+
+        let (program, vshader, fshader) = match result {
+            Ok(loaded) => loaded,
+            Err(e) => return hot_reload::report(&name, Err(e.to_string())),
+        };
+
+        let ctxt = Context::get();
+        let mut uniforms = source.uniforms.borrow_mut();
+        uniforms.retain(|(_, location)| location.strong_count() > 0);
+
+        // The previous program is kept if the new one lacks variables handed out to materials.
+        let missing_uniform = uniforms
+            .iter()
+            .find(|(name, _)| ctxt.get_uniform_location(&program, name).is_none())
+            .map(|(name, _)| missing_variable("uniform", name));
+        let missing_attribute = source
+            .attributes
+            .borrow()
+            .iter()
+            .find(|(name, _)| ctxt.get_attrib_location(&program, name) == -1)
+            .map(|(name, _)| missing_variable("attribute", name));
+
+        if let Some(e) = missing_uniform.or(missing_attribute) {
+            verify!(ctxt.delete_program(Some(&program)));
+            verify!(ctxt.delete_shader(Some(&vshader)));
+            verify!(ctxt.delete_shader(Some(&fshader)));
+            return hot_reload::report(&name, Err(e.to_string()));
+        }
+
+        for (name, location) in uniforms.iter() {
+            if let Some(location) = location.upgrade() {
+                *location.borrow_mut() = ctxt.get_uniform_location(&program, name);
+            }
+        }
+
+        let old = (
+            mem::replace(&mut self.program, program),
+            mem::replace(&mut self.vshader, vshader),
+            mem::replace(&mut self.fshader, fshader),
+        );
+        verify!(ctxt.delete_program(Some(&old.0)));
+        verify!(ctxt.delete_shader(Some(&old.1)));
+        verify!(ctxt.delete_shader(Some(&old.2)));
+        hot_reload::report(&name, Ok(()));
+    }
+}
+
+/// The effects compiled from the same shaders with different defines, each compiled the first
+/// time it is requested.
+pub struct EffectPermutations {
+    vshader: String,
+    fshader: String,
+    effects: HashMap<ShaderDefines, Effect>,
+}
+
+impl EffectPermutations {
+    pub fn new(vshader: &str, fshader: &str) -> EffectPermutations {
+        EffectPermutations {
+            vshader: vshader.to_string(),
+            fshader: fshader.to_string(),
+            effects: HashMap::new(),
+        }
+    }
+
+    /// The effect compiled with `defines`.
+    pub fn get(&mut self, defines: &ShaderDefines) -> Result<&mut Effect, ShaderError> {
+        if !self.effects.contains_key(defines) {
+            let effect = Effect::new_from_str_with_defines(&self.vshader, &self.fshader, defines)?;
+            let _ = self.effects.insert(defines.clone(), effect);
+        }
+
+        Ok(self.effects.get_mut(defines).unwrap())
+    }
+
+    /// The number of permutations compiled so far.
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Deletes the compiled permutations.
+    pub fn clear(&mut self) {
+        self.effects.clear()
+    }
 }
 
 impl Drop for Effect {
     fn drop(&mut self) {
+        if let Some(source) = &self.source {
+            hot_reload::unwatch_shader(&source.vshader);
+            hot_reload::unwatch_shader(&source.fshader);
+        }
+
         let ctxt = Context::get();
         if verify!(ctxt.is_program(Some(&self.program))) {
             verify!(ctxt.delete_program(Some(&self.program)));
@@ -98,13 +317,27 @@ impl Drop for Effect {
 }
 
 pub struct ShaderUniform<T> {
-    id: UniformLocation,
+    // Shared with the effect, which updates it when its shaders are reloaded. `None` if the
+    // reloaded shaders do not use the uniform anymore.
+    id: Rc<LocationCell>,
     data_type: PhantomData<T>,
 }
 
+impl<T> ShaderUniform<T> {
+    // A uniform the program does not use, whose uploads are ignored.
+    pub(crate) fn unused() -> ShaderUniform<T> {
+        ShaderUniform {
+            id: Rc::new(RefCell::new(None)),
+            data_type: PhantomData,
+        }
+    }
+}
+
 impl<T: GLPrimitive> ShaderUniform<T> {
     pub fn upload(&mut self, value: &T) {
-        value.upload(&self.id)
+        if let Some(id) = &*self.id.borrow() {
+            value.upload(id)
+        }
     }
 }
 
@@ -170,41 +403,95 @@ impl<T: GLPrimitive> ShaderAttribute<T> {
     }
 }
 
-fn load_shader_program(vertex_shader: &str, fragment_shader: &str) -> (Program, Shader, Shader) {
-    let ctxt = Context::get();
-    let vshader = verify!(ctxt
-        .create_shader(Context::VERTEX_SHADER)
-        .expect("Could not create vertex shader."));
+fn read_shader(path: &Path, stage: ShaderStage) -> Result<String, ShaderError> {
+    fs::read_to_string(path).map_err(|e| {
+        let diagnostic = ShaderDiagnostic {
+            location: None,
+            message: format!("{}: {}", path.display(), e),
+        };
+        ShaderError::new(ShaderErrorKind::Io, Some(stage), diagnostic)
+    })
+}
 
-    verify!(ctxt.shader_source(&vshader, vertex_shader));
-    verify!(ctxt.compile_shader(&vshader));
-    check_shader_error(&vshader);
+fn preprocess_shaders(
+    vshader: &str,
+    fshader: &str,
+    defines: &ShaderDefines,
+) -> Result<(PreprocessedShader, PreprocessedShader), ShaderError> {
+    let version = GlslVersion::current();
+    let preprocess = |source, stage| {
+        shader_preprocessor::preprocess(source, stage, defines, version)
+            .map_err(|e| ShaderError::new(ShaderErrorKind::Preprocess, Some(stage), e))
+    };
+
+    Ok((
+        preprocess(vshader, ShaderStage::Vertex)?,
+        preprocess(fshader, ShaderStage::Fragment)?,
+    ))
+}
 
-    let fshader = verify!(ctxt
-        .create_shader(Context::FRAGMENT_SHADER)
-        .expect("Could not create fragment shader."));
-    verify!(ctxt.shader_source(&fshader, fragment_shader));
-    verify!(ctxt.compile_shader(&fshader));
-    check_shader_error(&fshader);
+fn missing_variable(kind: &str, name: &str) -> ShaderError {
+    let diagnostic = ShaderDiagnostic {
+        location: None,
+        message: format!("missing {} `{}`", kind, name),
+    };
+    ShaderError::new(ShaderErrorKind::Interface, None, diagnostic)
+}
+
+// Compiles and links the shaders, with the attributes bound to the given locations. Nothing is
+// left allocated on error.
+fn load_shader_program(
+    vertex_shader: &PreprocessedShader,
+    fragment_shader: &PreprocessedShader,
+    attributes: &[(String, u32)],
+) -> Result<(Program, Shader, Shader), ShaderError> {
+    let ctxt = Context::get();
+    let compile = |stage, source: &PreprocessedShader| {
+        let ty = match stage {
+            ShaderStage::Vertex => Context::VERTEX_SHADER,
+            ShaderStage::Fragment => Context::FRAGMENT_SHADER,
+        };
+        let shader = verify!(ctxt.create_shader(ty).expect("Could not create shader."));
+        verify!(ctxt.shader_source(&shader, &source.code));
+        verify!(ctxt.compile_shader(&shader));
+
+        if ctxt.get_shader_parameter_int(&shader, Context::COMPILE_STATUS) == Some(0) {
+            let log = ctxt.get_shader_info_log(&shader).unwrap_or_default();
+            verify!(ctxt.delete_shader(Some(&shader)));
+            let kind = ShaderErrorKind::Compile;
+            Err(ShaderError::from_log(kind, Some(stage), log, Some(source)))
+        } else {
+            Ok(shader)
+        }
+    };
+
+    let vshader = compile(ShaderStage::Vertex, vertex_shader)?;
+    let fshader = match compile(ShaderStage::Fragment, fragment_shader) {
+        Ok(fshader) => fshader,
+        Err(e) => {
+            verify!(ctxt.delete_shader(Some(&vshader)));
+            return Err(e);
+        }
+    };
 
     let program = verify!(ctxt.create_program().expect("Could not create program."));
     verify!(ctxt.attach_shader(&program, &vshader));
     verify!(ctxt.attach_shader(&program, &fshader));
-    verify!(ctxt.link_program(&program));
-    (program, vshader, fshader)
-}
 
-fn check_shader_error(shader: &Shader) {
-    let ctxt = Context::get();
-    let compiles = ctxt.get_shader_parameter_int(shader, Context::COMPILE_STATUS);
+    for (name, location) in attributes {
+        verify!(ctxt.bind_attrib_location(&program, *location, name));
+    }
 
-    let log = ctxt.get_shader_info_log(&shader);
+    verify!(ctxt.link_program(&program));
 
-    if compiles == Some(0) {
-        if let Some(log) = ctxt.get_shader_info_log(shader) {
-            panic!("Shader compilation failed: {}", log);
-        } else {
-            println!("Shader compilation failed.");
-        }
+    if ctxt.get_program_parameter_int(&program, Context::LINK_STATUS) == Some(0) {
+        let log = ctxt.get_program_info_log(&program).unwrap_or_default();
+        verify!(ctxt.delete_program(Some(&program)));
+        verify!(ctxt.delete_shader(Some(&vshader)));
+        verify!(ctxt.delete_shader(Some(&fshader)));
+        let kind = ShaderErrorKind::Link;
+        return Err(ShaderError::from_log(kind, None, log, None));
     }
+
+    Ok((program, vshader, fshader))
 }
@@ -182,6 +182,11 @@ unsafe impl GLPrimitive for Point2<i32> {
     }
 }
 
+unsafe impl GLPrimitive for u8 {
+    type Element = u8;
+    const GLTYPE: u32 = Context::UNSIGNED_BYTE;
+}
+
 unsafe impl GLPrimitive for Point2<u16> {
     type Element = u16;
     const GLTYPE: u32 = Context::UNSIGNED_SHORT;
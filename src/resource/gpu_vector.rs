@@ -35,6 +35,12 @@ impl<T: GLPrimitive> GPUVec<T> {
         }
     }
 
+    /// The size of the elements, in bytes.
+    #[inline]
+    pub fn num_bytes(&self) -> usize {
+        self.len() * std::mem::size_of::<T>()
+    }
+
     #[inline]
     pub fn data_mut(&mut self) -> &mut Option<Vec<T>> {
         self.trash = true;
@@ -129,6 +135,14 @@ impl<T: Clone + GLPrimitive> GPUVec<T> {
     }
 }
 
+impl<T> Drop for GPUVec<T> {
+    fn drop(&mut self) {
+        if let Some((_, ref buffer)) = self.buffer {
+            verify!(Context::get().delete_buffer(Some(buffer)));
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum BufferType {
     Array,
@@ -0,0 +1,265 @@
+//! Reloading of shaders, textures and models when the files they were loaded from change.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Weak;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::loader::model::{self, ModelData};
+use crate::resource::mesh::Mesh;
+use crate::resource::mesh_manager::MeshManager;
+use crate::resource::texture_manager::TextureManager;
+
+// The time between two checks of the watched files.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+thread_local!(static HOT_RELOAD: RefCell<HotReload> = RefCell::new(HotReload::default()));
+
+// The modification times of files, to tell when they change.
+#[derive(Default)]
+pub(crate) struct FileWatcher {
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    // Whether the file changed since the last call with the same path. Files seen for the first
+    // time, or missing while being rewritten, do not count as changed.
+    pub(crate) fn changed(&mut self, path: &Path) -> bool {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        match self.modified.insert(path.to_path_buf(), modified) {
+            Some(previous) => modified.is_some() && previous != modified,
+            None => false,
+        }
+    }
+
+    pub(crate) fn retain(&mut self, paths: &HashSet<PathBuf>) {
+        self.modified.retain(|path, _| paths.contains(path));
+    }
+}
+
+// How the meshes of a model are read again.
+pub(crate) enum ModelSource {
+    // An OBJ file, with the directory its materials are looked up in.
+    Obj(PathBuf),
+    // A glTF, STL or PLY file.
+    Indexed,
+}
+
+// The meshes of a model, in the order they were read from its file.
+struct WatchedModel {
+    path: PathBuf,
+    source: ModelSource,
+    meshes: Vec<Weak<RefCell<Mesh>>>,
+}
+
+impl WatchedModel {
+    fn is_alive(&self) -> bool {
+        self.meshes.iter().any(|mesh| mesh.strong_count() > 0)
+    }
+
+    // Replaces the content of the meshes with the ones read again from the file, as long as the
+    // file still has as many meshes.
+    fn reload(&self) -> Result<(), String> {
+        let meshes: Vec<Mesh> = match &self.source {
+            ModelSource::Obj(mtl_dir) => {
+                let basename = self.path.to_str().ok_or("The path is not valid unicode")?;
+                MeshManager::load_obj(&self.path, mtl_dir, basename)
+                    .map_err(|e| e.to_string())?
+                    .into_iter()
+                    .map(|obj| obj.mesh)
+                    .collect()
+            }
+            ModelSource::Indexed => match model::read_model(&self.path, &|_| ())? {
+                ModelData::Indexed(meshes) => meshes.into_iter().map(|(_, mesh)| mesh).collect(),
+                ModelData::Obj { meshes, .. } => meshes.into_iter().map(|obj| obj.mesh).collect(),
+                ModelData::Gltf(nodes) => {
+                    let mut meshes = Vec::new();
+
+                    for node in nodes {
+                        node.collect_meshes(&mut meshes);
+                    }
+
+                    meshes
+                }
+            },
+        };
+
+        if meshes.len() != self.meshes.len() {
+            return Err(format!(
+                "The file now has {} meshes instead of {}, add it again to see the changes",
+                meshes.len(),
+                self.meshes.len()
+            ));
+        }
+
+        for (old, new) in self.meshes.iter().zip(meshes) {
+            if let Some(old) = old.upgrade() {
+                old.borrow_mut().replace_geometry(new);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct HotReload {
+    enabled: bool,
+    last_poll: Option<Instant>,
+    // The number of polls so far. Effects compare it with the poll their files changed at.
+    generation: u64,
+    watcher: FileWatcher,
+    // The number of effects using each shader file, and the poll it last changed at.
+    shaders: HashMap<PathBuf, (usize, u64)>,
+    models: Vec<WatchedModel>,
+    // The error of each resource that failed to reload, sorted by name for display.
+    errors: BTreeMap<String, String>,
+}
+
+impl HotReload {
+    fn report(&mut self, name: &str, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                let _ = self.errors.remove(name);
+            }
+            Err(e) => {
+                let _ = self.errors.insert(name.to_string(), e);
+            }
+        }
+    }
+
+    fn poll(&mut self) {
+        if !self.enabled || self.last_poll.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return;
+        }
+
+        self.last_poll = Some(Instant::now());
+        self.generation += 1;
+        self.models.retain(WatchedModel::is_alive);
+
+        let textures = TextureManager::get_global_manager(|tm| tm.sources());
+        let paths: HashSet<PathBuf> = self
+            .shaders
+            .keys()
+            .cloned()
+            .chain(textures.iter().map(|(_, path)| path.clone()))
+            .chain(self.models.iter().map(|model| model.path.clone()))
+            .collect();
+        let watcher = &mut self.watcher;
+        let changed: HashSet<&PathBuf> = paths.iter().filter(|p| watcher.changed(p)).collect();
+        watcher.retain(&paths);
+
+        for (path, (_, changed_at)) in self.shaders.iter_mut() {
+            if changed.contains(path) {
+                *changed_at = self.generation;
+            }
+        }
+
+        for (name, path) in textures.iter().filter(|(_, path)| changed.contains(path)) {
+            let result = TextureManager::get_global_manager(|tm| tm.reload(name));
+            self.report(&path.display().to_string(), result);
+        }
+
+        let models: Vec<_> = self
+            .models
+            .iter()
+            .filter(|model| changed.contains(&model.path))
+            .map(|model| (model.path.display().to_string(), model.reload()))
+            .collect();
+
+        for (name, result) in models {
+            self.report(&name, result);
+        }
+    }
+}
+
+pub(crate) fn set_enabled(enabled: bool) {
+    HOT_RELOAD.with(|h| {
+        let mut h = h.borrow_mut();
+        h.enabled = enabled;
+        h.last_poll = None;
+
+        if !enabled {
+            h.errors.clear();
+        }
+    })
+}
+
+pub(crate) fn is_enabled() -> bool {
+    HOT_RELOAD.with(|h| h.borrow().enabled)
+}
+
+// The errors of the resources that failed to reload, as `<name>: <error>`.
+pub(crate) fn errors() -> Vec<String> {
+    HOT_RELOAD.with(|h| {
+        h.borrow()
+            .errors
+            .iter()
+            .map(|(name, e)| format!("{}: {}", name, e))
+            .collect()
+    })
+}
+
+// Checks the watched files if enough time passed since the last check, and reloads the
+// textures and models whose files changed. Effects reload their shaders when they are used.
+pub(crate) fn poll() {
+    HOT_RELOAD.with(|h| h.borrow_mut().poll())
+}
+
+pub(crate) fn report(name: &str, result: Result<(), String>) {
+    HOT_RELOAD.with(|h| h.borrow_mut().report(name, result))
+}
+
+pub(crate) fn generation() -> u64 {
+    HOT_RELOAD.with(|h| h.borrow().generation)
+}
+
+pub(crate) fn watch_shader(path: &Path) {
+    HOT_RELOAD.with(|h| {
+        h.borrow_mut()
+            .shaders
+            .entry(path.to_path_buf())
+            .or_insert((0, 0))
+            .0 += 1
+    })
+}
+
+pub(crate) fn unwatch_shader(path: &Path) {
+    // The effects of other thread-locals may be dropped after this one on thread exit.
+    let _ = HOT_RELOAD.try_with(|h| {
+        let mut h = h.borrow_mut();
+
+        if let Some((effects, _)) = h.shaders.get_mut(path) {
+            *effects -= 1;
+
+            if *effects == 0 {
+                let _ = h.shaders.remove(path);
+            }
+        }
+    });
+}
+
+// Whether the shader file changed after the poll `generation`.
+pub(crate) fn shader_changed_since(path: &Path, generation: u64) -> bool {
+    HOT_RELOAD.with(|h| {
+        h.borrow()
+            .shaders
+            .get(path)
+            .is_some_and(|(_, changed_at)| *changed_at > generation)
+    })
+}
+
+pub(crate) fn watch_model(path: &Path, source: ModelSource, meshes: Vec<Weak<RefCell<Mesh>>>) {
+    HOT_RELOAD.with(|h| {
+        let mut h = h.borrow_mut();
+        h.models.retain(WatchedModel::is_alive);
+        h.models.push(WatchedModel {
+            path: path.to_path_buf(),
+            source,
+            meshes,
+        });
+    })
+}
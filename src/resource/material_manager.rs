@@ -2,6 +2,7 @@
 
 use crate::builtin::{normals_material::NormalsMaterial, object_material::ObjectMaterial, uvs_material::UvsMaterial};
 use crate::resource::material::Material;
+use crate::resource::resource_usage::{ResourceUsage, UsageTracker};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -9,6 +10,7 @@ use std::rc::Rc;
 pub struct MaterialManager {
     default_material: Rc<RefCell<Box<dyn Material + 'static>>>,
     materials: HashMap<String, Rc<RefCell<Box<dyn Material + 'static>>>>,
+    tracker: UsageTracker,
 }
 
 impl MaterialManager {
@@ -30,9 +32,16 @@ impl MaterialManager {
         ));
         let _ = materials.insert("uvs".to_string(), um.clone());
 
+        let mut tracker = UsageTracker::default();
+
+        for name in ["object", "normals", "uvs"] {
+            tracker.set_pinned(name, true);
+        }
+
         MaterialManager {
             default_material: om,
             materials,
+            tracker,
         }
     }
 
@@ -46,14 +55,71 @@ impl MaterialManager {
     }
 
     pub fn get(&mut self, name: &str) -> Option<Rc<RefCell<Box<dyn Material + 'static>>>> {
-        self.materials.get(&name.to_string()).cloned()
+        self.tracker.touch(name);
+        self.materials.get(name).cloned()
     }
 
     pub fn add(&mut self, material: Rc<RefCell<Box<dyn Material + 'static>>>, name: &str) {
         let _ = self.materials.insert(name.to_string(), material);
+        self.tracker.touch(name);
     }
 
     pub fn remove(&mut self, name: &str) {
         let _ = self.materials.remove(&name.to_string());
+        self.tracker.remove(name);
+    }
+
+    /// Usage statistics of the registered materials. The size of their shaders is not known,
+    /// and counted as zero.
+    pub fn usage(&self) -> Vec<ResourceUsage> {
+        self.materials
+            .iter()
+            .map(|(name, m)| {
+                // The default material is also held by the manager.
+                let owned = 1 + Rc::ptr_eq(m, &self.default_material) as usize;
+                self.tracker.usage(name, 0, Rc::strong_count(m) - owned)
+            })
+            .collect()
+    }
+
+    /// Removes the materials not referenced outside of this manager, except pinned ones, and
+    /// returns their names. The built-in materials are pinned.
+    pub fn purge_unreferenced(&mut self) -> Vec<String> {
+        let names: Vec<_> = self
+            .usage()
+            .into_iter()
+            .filter(|u| u.is_evictable())
+            .map(|u| u.name)
+            .collect();
+
+        for name in &names {
+            self.remove(name);
+        }
+
+        names
+    }
+
+    /// Sets whether the material registered with `name` is kept by purges.
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) {
+        self.tracker.set_pinned(name, pinned);
+    }
+
+    // Counts the materials still referenced as used.
+    pub(crate) fn next_frame(&mut self) {
+        let usage = self.usage();
+        self.tracker.next_frame(
+            usage
+                .iter()
+                .filter(|u| u.references > 0)
+                .map(|u| u.name.as_str()),
+        );
+    }
+
+    /// The name a material was registered with, if any.
+    pub fn name_of(&self, material: &Rc<RefCell<Box<dyn Material + 'static>>>) -> Option<String> {
+        self.materials
+            .iter()
+            .find(|(_, m)| Rc::ptr_eq(m, material))
+            .map(|(name, _)| name.clone())
     }
 }
@@ -0,0 +1,103 @@
+//! The parameters of the look of each object, read by the material rendering it.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use nalgebra::Point3;
+
+use crate::context::context::Texture;
+use crate::resource::uniform_value::UniformValue;
+
+/// The look of an object beside its color and texture, so objects sharing a material can still
+/// look different.
+///
+/// `ObjectMaterial` uses the specular color, the shininess and the emissive color. Materials
+/// reading their uniforms by name, like `ShaderMaterial`, find every parameter with `uniform`.
+#[derive(Clone)]
+pub struct MaterialParameters {
+    /// The color of the specular highlights, the `specular_color` uniform.
+    pub specular_color: Point3<f32>,
+    /// The exponent of the specular highlights, the `shininess` uniform. The higher, the smaller
+    /// and sharper the highlights.
+    pub shininess: f32,
+    /// The color emitted regardless of lighting, the `emissive` uniform.
+    pub emissive: Point3<f32>,
+    /// The roughness of the surface for physically based materials, from 0 for a mirror to 1,
+    /// the `roughness` uniform.
+    pub roughness: f32,
+    /// From 0 for dielectrics to 1 for metals, the `metalness` uniform.
+    pub metalness: f32,
+    textures: HashMap<String, Rc<Texture>>,
+    uniforms: HashMap<String, UniformValue>,
+}
+
+impl Default for MaterialParameters {
+    /// The parameters the default material always used.
+    fn default() -> Self {
+        MaterialParameters {
+            specular_color: Point3::new(0.4, 0.4, 0.4),
+            shininess: 30.0,
+            emissive: Point3::origin(),
+            roughness: 1.0,
+            metalness: 0.0,
+            textures: HashMap::new(),
+            uniforms: HashMap::new(),
+        }
+    }
+}
+
+impl MaterialParameters {
+    /// The texture other than the main one read by the sampler `name`, like `normal_map`.
+    pub fn texture(&self, name: &str) -> Option<&Rc<Texture>> {
+        self.textures.get(name)
+    }
+
+    pub fn set_texture(&mut self, name: &str, texture: Rc<Texture>) {
+        let _ = self.textures.insert(name.to_string(), texture);
+    }
+
+    pub fn remove_texture(&mut self, name: &str) {
+        let _ = self.textures.remove(name);
+    }
+
+    /// The textures set with `set_texture`, by sampler name.
+    pub fn textures(&self) -> &HashMap<String, Rc<Texture>> {
+        &self.textures
+    }
+
+    /// Sets a uniform without a field of its own. It takes precedence over the fields and the
+    /// textures with the same name.
+    pub fn set_uniform<V: Into<UniformValue>>(&mut self, name: &str, value: V) {
+        let _ = self.uniforms.insert(name.to_string(), value.into());
+    }
+
+    pub fn remove_uniform(&mut self, name: &str) {
+        let _ = self.uniforms.remove(name);
+    }
+
+    /// The uniforms set with `set_uniform`, by name.
+    pub fn uniforms(&self) -> &HashMap<String, UniformValue> {
+        &self.uniforms
+    }
+
+    /// The value of the uniform `name`: a uniform set with `set_uniform`, a texture, or one of
+    /// the fields, by the name of their uniform.
+    pub fn uniform(&self, name: &str) -> Option<UniformValue> {
+        if let Some(value) = self.uniforms.get(name) {
+            return Some(value.clone());
+        }
+
+        if let Some(texture) = self.textures.get(name) {
+            return Some(UniformValue::Texture(texture.clone()));
+        }
+
+        match name {
+            "specular_color" => Some(self.specular_color.into()),
+            "shininess" => Some(self.shininess.into()),
+            "emissive" => Some(self.emissive.into()),
+            "roughness" => Some(self.roughness.into()),
+            "metalness" => Some(self.metalness.into()),
+            _ => None,
+        }
+    }
+}
@@ -1,20 +1,63 @@
 //! Data structure of a scene node geometry.
 use std::iter;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec};
+use crate::resource::mesh_bvh::{MeshBvh, MeshRayHit};
 use crate::resource::vertex_index::VertexIndex;
 use crate::resource::effect::ShaderAttribute;
 use nalgebra::{self, Point2, Point3, Vector3};
 use ncollide3d::procedural::{IndexBuffer, TriMesh};
+use ncollide3d::bounding_volume::{BoundingSphere, AABB};
+use ncollide3d::query::Ray;
 use num_traits::Zero;
 
+/// The kind of elements a mesh is made of.
+///
+/// Segments and points are stored in the faces as degenerate triangles, `[a, b, b]` and
+/// `[a, a, a]`, so they are drawn by the wireframe and points rendering of the materials.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum Primitive {
+    #[default]
+    Triangles,
+    Lines,
+    Points,
+}
+
+impl Primitive {
+    /// The number of distinct vertices of each element: 3 for triangles, 2 for segments and 1
+    /// for points. They are the first ones of each face.
+    pub fn num_vertices(self) -> usize {
+        match self {
+            Primitive::Triangles => 3,
+            Primitive::Lines => 2,
+            Primitive::Points => 1,
+        }
+    }
+
+    /// The three vertices of the face storing an element made of the given vertices, see
+    /// `num_vertices`.
+    pub fn face<N: Copy>(self, vertices: &[N]) -> [N; 3] {
+        match self {
+            Primitive::Triangles => [vertices[0], vertices[1], vertices[2]],
+            Primitive::Lines => [vertices[0], vertices[1], vertices[1]],
+            Primitive::Points => [vertices[0], vertices[0], vertices[0]],
+        }
+    }
+}
+
 pub struct Mesh {
     coords: Arc<RwLock<GPUVec<Point3<f32>>>>,
     faces: Arc<RwLock<GPUVec<Point3<VertexIndex>>>>,
     normals: Arc<RwLock<GPUVec<Vector3<f32>>>>,
     uvs: Arc<RwLock<GPUVec<Point2<f32>>>>,
     edges: Option<Arc<RwLock<GPUVec<Point2<VertexIndex>>>>>,
+    primitive: Primitive,
+    bvh: Option<MeshBvh>,
+    bounds: Option<(AABB<f32>, BoundingSphere<f32>)>,
+    revision: u64,
+    source: Option<(PathBuf, usize, u64)>,
 }
 
 impl Mesh {
@@ -171,6 +214,11 @@ impl Mesh {
             normals,
             uvs,
             edges: None,
+            primitive: Primitive::Triangles,
+            bvh: None,
+            bounds: None,
+            revision: 0,
+            source: None,
         }
     }
 
@@ -218,6 +266,118 @@ impl Mesh {
         self.edges.as_mut().unwrap().write().unwrap().bind();
     }
 
+    /// The kind of elements stored in the faces of this mesh.
+    pub fn primitive(&self) -> Primitive {
+        self.primitive
+    }
+
+    /// Tells how the faces of this mesh are to be read, see `Primitive`.
+    pub fn set_primitive(&mut self, primitive: Primitive) {
+        self.primitive = primitive;
+    }
+
+    /// Casts a ray expressed in the mesh local space.
+    ///
+    /// The acceleration structure is built on the first call and reused until
+    /// `invalidate_cached_geometry` is called. Returns `None` if the geometry is not on RAM, or
+    /// if the mesh is made of lines or points, which have no surface to hit.
+    pub fn cast_ray(&mut self, ray: &Ray<f32>, max_toi: f32) -> Option<MeshRayHit> {
+        if self.primitive != Primitive::Triangles {
+            return None;
+        }
+
+        let coords = self.coords.read().unwrap();
+        let faces = self.faces.read().unwrap();
+        let coords = coords.data().as_ref()?;
+        let faces = faces.data().as_ref()?;
+
+        self.bvh
+            .get_or_insert_with(|| MeshBvh::new(coords, faces))
+            .cast_ray(coords, faces, ray, max_toi)
+    }
+
+    /// Drops the data derived from the vertices and faces, to be recomputed lazily.
+    pub fn invalidate_cached_geometry(&mut self) {
+        self.bvh = None;
+        self.edges = None;
+        self.bounds = None;
+        self.revision += 1;
+    }
+
+    /// A counter incremented each time `invalidate_cached_geometry` or `replace_geometry` is
+    /// called.
+    pub fn geometry_revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Replaces the content of this mesh with the one of `mesh`, e.g., after its file changed.
+    ///
+    /// The revision keeps increasing, so the data cached from the previous geometry, like the
+    /// bounds of the objects using this mesh, is recomputed. The asset source is kept.
+    pub fn replace_geometry(&mut self, mesh: Mesh) {
+        let revision = self.revision + 1;
+        let source = self.asset_source().map(|(p, i)| (p.to_path_buf(), i, revision));
+
+        *self = mesh;
+        self.revision = revision;
+        self.source = source;
+    }
+
+    /// Records that this mesh is the `index`-th mesh loaded from the model file at `path`.
+    pub fn set_asset_source(&mut self, path: &Path, index: usize) {
+        self.source = Some((path.to_path_buf(), index, self.revision));
+    }
+
+    /// The model file this mesh was loaded from and its index in that file, see
+    /// `set_asset_source`.
+    ///
+    /// Returns `None` if the geometry was modified since the mesh was loaded, see
+    /// `invalidate_cached_geometry`.
+    pub fn asset_source(&self) -> Option<(&Path, usize)> {
+        match self.source {
+            Some((ref path, index, revision)) if revision == self.revision => Some((path, index)),
+            _ => None,
+        }
+    }
+
+    /// The axis-aligned bounding box of the mesh vertices, in the mesh local space.
+    ///
+    /// Returns `None` if the mesh is empty or its vertices are not on RAM.
+    pub fn local_aabb(&mut self) -> Option<AABB<f32>> {
+        self.bounds().map(|b| b.0)
+    }
+
+    /// The bounding sphere of the mesh vertices, in the mesh local space.
+    ///
+    /// Returns `None` if the mesh is empty or its vertices are not on RAM.
+    pub fn local_bounding_sphere(&mut self) -> Option<BoundingSphere<f32>> {
+        self.bounds().map(|b| b.1)
+    }
+
+    fn bounds(&mut self) -> Option<(AABB<f32>, BoundingSphere<f32>)> {
+        if self.bounds.is_none() {
+            let coords = self.coords.read().unwrap();
+            let coords = coords.data().as_ref()?;
+            let first = coords.first()?;
+            let mut aabb = AABB::new(*first, *first);
+
+            for pt in coords.iter() {
+                aabb.take_point(*pt);
+            }
+
+            let center = aabb.center();
+            let radius = coords
+                .iter()
+                .map(|pt| nalgebra::distance_squared(&center, pt))
+                .fold(0.0f32, f32::max)
+                .sqrt();
+
+            self.bounds = Some((aabb, BoundingSphere::new(center, radius)));
+        }
+
+        self.bounds
+    }
+
     pub fn unbind(&self) {
         self.coords.write().unwrap().unbind();
         self.normals.write().unwrap().unbind();
@@ -229,6 +389,17 @@ impl Mesh {
         self.faces.read().unwrap().len() * 3
     }
 
+    /// The size of the vertex, index and edge buffers, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        let edges = self.edges.as_ref().map(|e| e.read().unwrap().num_bytes());
+
+        self.coords.read().unwrap().num_bytes()
+            + self.normals.read().unwrap().num_bytes()
+            + self.uvs.read().unwrap().num_bytes()
+            + self.faces.read().unwrap().num_bytes()
+            + edges.unwrap_or(0)
+    }
+
     pub fn recompute_normals(&mut self) {
         Mesh::compute_normals(
             &self.coords.read().unwrap().data().as_ref().unwrap()[..],
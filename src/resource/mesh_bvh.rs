@@ -0,0 +1,95 @@
+//! Bounding volume hierarchy used to accelerate ray casts against a mesh.
+
+use crate::resource::vertex_index::VertexIndex;
+use nalgebra::{Point3, Vector3};
+use ncollide3d::bounding_volume::{BoundingVolume, AABB};
+use ncollide3d::partitioning::{BVH, BVT};
+use ncollide3d::query::visitors::RayInterferencesCollector;
+use ncollide3d::query::{self, Ray};
+
+pub struct MeshRayHit {
+    pub face: usize,
+    pub toi: f32,
+    pub normal: Vector3<f32>,
+    pub barycentric: Vector3<f32>,
+}
+
+pub struct MeshBvh {
+    bvt: Option<BVT<usize, AABB<f32>>>,
+}
+
+impl MeshBvh {
+    pub fn new(coords: &[Point3<f32>], faces: &[Point3<VertexIndex>]) -> MeshBvh {
+        let leaves: Vec<_> = faces
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| {
+                let (a, b, c) = triangle(coords, f)?;
+                // Flat triangles produce flat boxes, loosen them so axis-aligned rays still hit.
+                let aabb = AABB::new(a.inf(&b).inf(&c), a.sup(&b).sup(&c)).loosened(1.0e-5);
+                Some((i, aabb))
+            })
+            .collect();
+
+        let bvt = if leaves.is_empty() {
+            None
+        } else {
+            Some(BVT::new_balanced(leaves))
+        };
+
+        MeshBvh { bvt }
+    }
+
+    /// Casts a ray expressed in the mesh local space, returning the closest hit.
+    pub fn cast_ray(
+        &self,
+        coords: &[Point3<f32>],
+        faces: &[Point3<VertexIndex>],
+        ray: &Ray<f32>,
+        max_toi: f32,
+    ) -> Option<MeshRayHit> {
+        let bvt = self.bvt.as_ref()?;
+        let mut candidates = Vec::new();
+
+        {
+            let mut visitor = RayInterferencesCollector::new(ray, max_toi, &mut candidates);
+            bvt.visit(&mut visitor);
+        }
+
+        let mut best: Option<MeshRayHit> = None;
+
+        for i in candidates {
+            let (a, b, c) = match faces.get(i).and_then(|f| triangle(coords, f)) {
+                Some(tri) => tri,
+                None => continue,
+            };
+
+            let (inter, barycentric) = match query::ray_intersection_with_triangle(&a, &b, &c, ray) {
+                Some(res) => res,
+                None => continue,
+            };
+
+            if inter.toi <= max_toi && best.as_ref().is_none_or(|h| inter.toi < h.toi) {
+                best = Some(MeshRayHit {
+                    face: i,
+                    toi: inter.toi,
+                    normal: inter.normal,
+                    barycentric,
+                });
+            }
+        }
+
+        best
+    }
+}
+
+fn triangle(
+    coords: &[Point3<f32>],
+    face: &Point3<VertexIndex>,
+) -> Option<(Point3<f32>, Point3<f32>, Point3<f32>)> {
+    Some((
+        *coords.get(face.x as usize)?,
+        *coords.get(face.y as usize)?,
+        *coords.get(face.z as usize)?,
+    ))
+}
@@ -1,8 +1,8 @@
 //! A resource manager to load meshes.
 
-use crate::loader::mtl::MtlMaterial;
 use crate::loader::obj;
 use crate::resource::mesh::Mesh;
+use crate::resource::resource_usage::{ResourceUsage, UsageTracker};
 use ncollide3d::procedural;
 use ncollide3d::procedural::TriMesh;
 use std::cell::RefCell;
@@ -13,12 +13,14 @@ use std::rc::Rc;
 
 pub struct MeshManager {
     meshes: HashMap<String, Rc<RefCell<Mesh>>>,
+    tracker: UsageTracker,
 }
 
 impl MeshManager {
     pub fn new() -> MeshManager {
         let mut res = MeshManager {
             meshes: HashMap::new(),
+            tracker: UsageTracker::default(),
         };
 
         let _ = res.add_trimesh(procedural::unit_sphere(50, 50, true), false, "sphere");
@@ -26,6 +28,10 @@ impl MeshManager {
         let _ = res.add_trimesh(procedural::unit_cone(50), false, "cone");
         let _ = res.add_trimesh(procedural::unit_cylinder(50), false, "cylinder");
 
+        for name in ["sphere", "cube", "cone", "cylinder"] {
+            res.set_pinned(name, true);
+        }
+
         res
     }
 
@@ -35,11 +41,13 @@ impl MeshManager {
     }
 
     pub fn get(&mut self, name: &str) -> Option<Rc<RefCell<Mesh>>> {
-        self.meshes.get(&name.to_string()).cloned()
+        self.tracker.touch(name);
+        self.meshes.get(name).cloned()
     }
 
     pub fn add(&mut self, mesh: Rc<RefCell<Mesh>>, name: &str) {
         let _ = self.meshes.insert(name.to_string(), mesh);
+        self.tracker.touch(name);
     }
 
     pub fn add_trimesh(
@@ -58,23 +66,80 @@ impl MeshManager {
 
     pub fn remove(&mut self, name: &str) {
         let _ = self.meshes.remove(&name.to_string());
+        self.tracker.remove(name);
+    }
+
+    /// Usage statistics of the registered meshes.
+    pub fn usage(&self) -> Vec<ResourceUsage> {
+        self.meshes
+            .iter()
+            .map(|(name, m)| {
+                self.tracker
+                    .usage(name, m.borrow().num_bytes(), Rc::strong_count(m) - 1)
+            })
+            .collect()
+    }
+
+    /// The estimated GPU memory used by the registered meshes, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.meshes.values().map(|m| m.borrow().num_bytes()).sum()
+    }
+
+    /// Removes the meshes not referenced outside of this manager, except pinned ones, and
+    /// returns their names. The built-in meshes are pinned.
+    pub fn purge_unreferenced(&mut self) -> Vec<String> {
+        let names: Vec<_> = self
+            .usage()
+            .into_iter()
+            .filter(|u| u.is_evictable())
+            .map(|u| u.name)
+            .collect();
+
+        for name in &names {
+            self.remove(name);
+        }
+
+        names
+    }
+
+    /// Sets the GPU memory the registered meshes may use. Past it, the least recently used
+    /// meshes not referenced outside of this manager are removed at the next frame.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) {
+        self.tracker.set_budget(bytes);
+    }
+
+    /// Sets whether the mesh registered with `name` is kept by purges and evictions.
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) {
+        self.tracker.set_pinned(name, pinned);
+    }
+
+    // Counts the meshes still referenced as used, and evicts meshes over the budget.
+    pub(crate) fn next_frame(&mut self) {
+        self.tracker.next_frame(
+            self.meshes
+                .iter()
+                .filter(|(_, m)| Rc::strong_count(m) > 1)
+                .map(|(name, _)| name.as_str()),
+        );
+
+        for name in self.tracker.evictions(&self.usage()) {
+            self.remove(&name);
+        }
+    }
+
+    /// The name a mesh was registered with, if any.
+    pub fn name_of(&self, mesh: &Rc<RefCell<Mesh>>) -> Option<String> {
+        self.meshes
+            .iter()
+            .find(|(_, m)| Rc::ptr_eq(m, mesh))
+            .map(|(name, _)| name.clone())
     }
 
     pub fn load_obj(
         path: &Path,
         mtl_dir: &Path,
         geometry_name: &str,
-    ) -> IoResult<Vec<(String, Rc<RefCell<Mesh>>, Option<MtlMaterial>)>> {
-        obj::parse_file(path, mtl_dir, geometry_name).map(|ms| {
-            let mut res = Vec::new();
-
-            for (n, m, mat) in ms.into_iter() {
-                let m = Rc::new(RefCell::new(m));
-
-                res.push((n, m, mat));
-            }
-
-            res
-        })
+    ) -> IoResult<Vec<obj::ObjMesh>> {
+        obj::parse_file(path, mtl_dir, geometry_name)
     }
 }
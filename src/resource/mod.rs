@@ -1,11 +1,19 @@
 pub mod vertex_index;
 pub mod mesh;
+pub mod mesh_bvh;
 pub mod gpu_vector;
 pub mod gl_primitive;
 pub mod effect;
+pub mod uniform_value;
+pub mod shader_preprocessor;
+pub mod shader_diagnostics;
 pub mod texture_manager;
+pub mod dynamic_texture;
+pub mod resource_usage;
+pub mod hot_reload;
 pub mod mesh_manager;
 pub mod material;
+pub mod material_parameters;
 pub mod material_manager;
 pub mod framebuffer_manager;
 pub mod planar_mesh;
@@ -0,0 +1,106 @@
+//! Usage statistics and eviction of the resources registered in managers.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Usage statistics of a resource registered in a manager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub name: String,
+    /// The estimated size of its GPU buffers or textures, in bytes.
+    pub bytes: usize,
+    /// The number of references to it held outside of its manager.
+    pub references: usize,
+    /// The last frame it was added, requested or referenced at.
+    pub last_used_frame: u64,
+    /// Whether it is kept by purges and evictions.
+    pub pinned: bool,
+}
+
+impl ResourceUsage {
+    /// Whether purges and evictions may remove it from its manager.
+    pub fn is_evictable(&self) -> bool {
+        self.references == 0 && !self.pinned
+    }
+}
+
+// The frame the resources of a manager were last used at, their pins, and the memory budget of
+// the manager.
+#[derive(Default)]
+pub(crate) struct UsageTracker {
+    frame: u64,
+    last_used: HashMap<String, u64>,
+    pinned: HashSet<String>,
+    budget: Option<usize>,
+}
+
+impl UsageTracker {
+    pub(crate) fn touch(&mut self, name: &str) {
+        let _ = self.last_used.insert(name.to_string(), self.frame);
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        let _ = self.last_used.remove(name);
+        let _ = self.pinned.remove(name);
+    }
+
+    pub(crate) fn set_pinned(&mut self, name: &str, pinned: bool) {
+        if pinned {
+            let _ = self.pinned.insert(name.to_string());
+        } else {
+            let _ = self.pinned.remove(name);
+        }
+    }
+
+    pub(crate) fn set_budget(&mut self, bytes: Option<usize>) {
+        self.budget = bytes;
+    }
+
+    pub(crate) fn usage(&self, name: &str, bytes: usize, references: usize) -> ResourceUsage {
+        ResourceUsage {
+            name: name.to_string(),
+            bytes,
+            references,
+            last_used_frame: self.last_used.get(name).cloned().unwrap_or(0),
+            pinned: self.pinned.contains(name),
+        }
+    }
+
+    // Starts a new frame. The resources still referenced outside of the manager are used at
+    // every frame.
+    pub(crate) fn next_frame<'a>(&mut self, referenced: impl Iterator<Item = &'a str>) {
+        self.frame += 1;
+
+        for name in referenced {
+            self.touch(name);
+        }
+    }
+
+    // The resources to remove for the total size to fit in the budget, least recently used
+    // first, and the largest first among those last used at the same frame.
+    pub(crate) fn evictions(&self, usages: &[ResourceUsage]) -> Vec<String> {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return Vec::new(),
+        };
+        let mut total: usize = usages.iter().map(|u| u.bytes).sum();
+        let mut candidates: Vec<_> = usages
+            .iter()
+            .filter(|u| u.is_evictable() && u.bytes > 0)
+            .collect();
+        candidates.sort_by_key(|u| (u.last_used_frame, Reverse(u.bytes)));
+
+        let mut evicted = Vec::new();
+
+        for usage in candidates {
+            if total <= budget {
+                break;
+            }
+
+            total -= usage.bytes;
+            evicted.push(usage.name.clone());
+        }
+
+        evicted
+    }
+}
@@ -0,0 +1,165 @@
+//! Errors of shaders failing to build, with the lines of code they are about.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::resource::shader_preprocessor::{PreprocessedShader, ShaderStage, SourceLine};
+
+/// The step at which building a shader program failed.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ShaderErrorKind {
+    /// A shader file could not be read.
+    Io,
+    /// An `#include` could not be expanded.
+    Preprocess,
+    Compile,
+    Link,
+    /// The program lacks a uniform or an attribute its material uses.
+    Interface,
+}
+
+/// A message about a shader, from the driver or the preprocessor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    /// The line of the shader, or of an included chunk, the message is about, if any.
+    pub location: Option<SourceLine>,
+    pub message: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.location {
+            Some(SourceLine {
+                chunk: Some(chunk),
+                line,
+            }) => write!(f, "{}:{}: {}", chunk, line, self.message),
+            Some(SourceLine { chunk: None, line }) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The error of a shader program that could not be built.
+///
+/// Its `Display` output lists the diagnostics with the lines they are about, as written in the
+/// shaders and chunks given to the preprocessor.
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub kind: ShaderErrorKind,
+    /// The stage of the shader at fault, `None` for link errors.
+    pub stage: Option<ShaderStage>,
+    /// The info log of the driver, empty for the errors raised before compiling.
+    pub log: String,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl ShaderError {
+    pub(crate) fn new(
+        kind: ShaderErrorKind,
+        stage: Option<ShaderStage>,
+        diagnostic: ShaderDiagnostic,
+    ) -> ShaderError {
+        ShaderError {
+            kind,
+            stage,
+            log: String::new(),
+            diagnostics: vec![diagnostic],
+        }
+    }
+
+    // An error with the info log of the driver. Line numbers of compilation errors are mapped
+    // back to the code given to the preprocessor.
+    pub(crate) fn from_log(
+        kind: ShaderErrorKind,
+        stage: Option<ShaderStage>,
+        log: String,
+        shader: Option<&PreprocessedShader>,
+    ) -> ShaderError {
+        let diagnostics = log
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && l.chars().any(|c| c != '\0'))
+            .map(|l| {
+                let (line, message) = parse_log_line(l);
+                let location = line.and_then(|line| shader?.source_line(line).cloned());
+                ShaderDiagnostic { location, message }
+            })
+            .collect();
+
+        ShaderError {
+            kind,
+            stage,
+            log,
+            diagnostics,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stage = match self.stage {
+            Some(ShaderStage::Vertex) => "Vertex shader",
+            Some(ShaderStage::Fragment) => "Fragment shader",
+            None => "Shader",
+        };
+
+        match self.kind {
+            ShaderErrorKind::Io => write!(f, "{} could not be read", stage)?,
+            ShaderErrorKind::Preprocess => write!(f, "{} preprocessing failed", stage)?,
+            ShaderErrorKind::Compile => write!(f, "{} compilation failed", stage)?,
+            ShaderErrorKind::Link => write!(f, "{} linking failed", stage)?,
+            ShaderErrorKind::Interface => write!(f, "{} does not match its material", stage)?,
+        }
+
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n  {}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ShaderError {}
+
+// The line a message of an info log is about, and the message without its location. Drivers
+// write locations as `ERROR: 0:12:` (ANGLE, AMD, Apple), `0:12(5):` (Mesa), or `0(12) :`
+// (NVIDIA).
+fn parse_log_line(text: &str) -> (Option<usize>, String) {
+    for severity in ["ERROR:", "WARNING:"] {
+        if let Some(rest) = text.strip_prefix(severity) {
+            if let Some((line, message)) = colon_location(rest.trim_start()) {
+                return (Some(line), format!("{} {}", severity, message));
+            }
+        }
+    }
+
+    match colon_location(text).or_else(|| paren_location(text)) {
+        Some((line, message)) => (Some(line), message.to_string()),
+        None => (None, text.to_string()),
+    }
+}
+
+// `<source>:<line>: <message>` or `<source>:<line>(<column>): <message>`.
+fn colon_location(text: &str) -> Option<(usize, &str)> {
+    let (source, rest) = text.split_once(':')?;
+    let _: u32 = source.trim().parse().ok()?;
+    let end = rest.find(|c: char| !c.is_ascii_digit())?;
+    let line = rest[..end].parse().ok()?;
+    let mut rest = &rest[end..];
+
+    if rest.starts_with('(') {
+        rest = &rest[rest.find(')')? + 1..];
+    }
+
+    Some((line, rest.strip_prefix(':')?.trim()))
+}
+
+// `<source>(<line>) : <message>`.
+fn paren_location(text: &str) -> Option<(usize, &str)> {
+    let (source, rest) = text.split_once('(')?;
+    let _: u32 = source.trim().parse().ok()?;
+    let (line, rest) = rest.split_once(')')?;
+    let line = line.parse().ok()?;
+
+    Some((line, rest.trim_start().strip_prefix(':')?.trim()))
+}
@@ -0,0 +1,308 @@
+//! Preprocessing of GLSL sources: includes of shared chunks, defines, and version selection.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::context::context::Context;
+use crate::resource::shader_diagnostics::ShaderDiagnostic;
+
+thread_local!(static CHUNKS: RefCell<HashMap<String, Rc<str>>> = RefCell::new(builtin_chunks()));
+
+fn builtin_chunks() -> HashMap<String, Rc<str>> {
+    let mut chunks = HashMap::new();
+    let _ = chunks.insert("lighting.glsl".to_string(), Rc::from(LIGHTING_CHUNK));
+    chunks
+}
+
+/// Registers GLSL code included by shaders with `#include "<name>"`, in place of any chunk with
+/// that name.
+///
+/// The built-in chunks are `lighting.glsl`, with the Blinn-Phong lighting of the default object
+/// material.
+pub fn register_chunk(name: &str, source: &str) {
+    CHUNKS.with(|chunks| {
+        let _ = chunks
+            .borrow_mut()
+            .insert(name.to_string(), Rc::from(source));
+    })
+}
+
+/// The code of the chunk registered with `name`, if any.
+pub fn chunk(name: &str) -> Option<String> {
+    CHUNKS.with(|chunks| chunks.borrow().get(name).map(|c| c.to_string()))
+}
+
+/// The stage of the pipeline a shader runs at.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+/// The GLSL version shaders written for GLSL ES 1.00 are compiled as.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum GlslVersion {
+    /// GLSL ES 1.00, for OpenGL ES 2 and WebGL.
+    Es100,
+    /// GLSL 1.20, for OpenGL 2.1.
+    Glsl120,
+    /// GLSL 1.50, for OpenGL 3.2 and later, where `attribute`, `varying`, `texture2D` and
+    /// `gl_FragColor` are replaced by their core profile equivalents.
+    Glsl150,
+}
+
+impl GlslVersion {
+    /// The version supported by the current context.
+    pub fn current() -> GlslVersion {
+        let ctxt = Context::get();
+
+        if ctxt.is_embedded() {
+            GlslVersion::Es100
+        } else if ctxt.version() >= (3, 2) {
+            GlslVersion::Glsl150
+        } else {
+            GlslVersion::Glsl120
+        }
+    }
+
+    fn number(self) -> &'static str {
+        match self {
+            GlslVersion::Es100 => "100",
+            GlslVersion::Glsl120 => "120",
+            GlslVersion::Glsl150 => "150",
+        }
+    }
+
+    // The code put at the top of the shaders written for GLSL ES 1.00, after the version and
+    // the extensions.
+    fn prelude(self, stage: ShaderStage) -> &'static str {
+        match (self, stage) {
+            (GlslVersion::Es100, ShaderStage::Vertex) | (GlslVersion::Glsl120, _) => "",
+            (GlslVersion::Es100, ShaderStage::Fragment) => ES_FRAGMENT_PRELUDE,
+            (GlslVersion::Glsl150, ShaderStage::Vertex) => GLSL150_VERTEX_PRELUDE,
+            (GlslVersion::Glsl150, ShaderStage::Fragment) => GLSL150_FRAGMENT_PRELUDE,
+        }
+    }
+}
+
+/// Macros defined at the top of a shader, for example to compile the permutations of a
+/// material with `HAS_NORMAL_MAP` or `NUM_LIGHTS 4`.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct ShaderDefines {
+    defines: BTreeMap<String, String>,
+}
+
+impl ShaderDefines {
+    pub fn new() -> ShaderDefines {
+        ShaderDefines::default()
+    }
+
+    /// Defines `name` without a value.
+    pub fn with(mut self, name: &str) -> ShaderDefines {
+        self.set(name, "");
+        self
+    }
+
+    /// Defines `name` as `value`.
+    pub fn with_value<T: ToString>(mut self, name: &str, value: T) -> ShaderDefines {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set<T: ToString>(&mut self, name: &str, value: T) {
+        let _ = self.defines.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        let _ = self.defines.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defines.is_empty()
+    }
+}
+
+/// A line of a shader given to the preprocessor, or of a chunk it included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    /// The chunk the line is from, `None` for the shader itself.
+    pub chunk: Option<String>,
+    /// The number of the line, starting at 1.
+    pub line: usize,
+}
+
+/// The code of a preprocessed shader, with the origin of its lines.
+pub struct PreprocessedShader {
+    pub code: String,
+    // `None` for the lines added by the preprocessor.
+    lines: Vec<Option<SourceLine>>,
+}
+
+impl PreprocessedShader {
+    /// Where the line `line` of the code, starting at 1, comes from. `None` for the lines added
+    /// by the preprocessor.
+    pub fn source_line(&self, line: usize) -> Option<&SourceLine> {
+        self.lines.get(line.checked_sub(1)?)?.as_ref()
+    }
+}
+
+/// Expands the `#include "<chunk>"` directives of a shader and adds its defines.
+///
+/// Shaders starting with `#version 100`, or without `#version`, are written for GLSL ES 1.00 and
+/// compiled as `version`. Other versions are kept as they are. Each chunk is included once, and
+/// `#extension` directives are moved right after the version.
+pub fn preprocess(
+    source: &str,
+    stage: ShaderStage,
+    defines: &ShaderDefines,
+    version: GlslVersion,
+) -> Result<PreprocessedShader, ShaderDiagnostic> {
+    let mut expansion = Expansion::default();
+    let mut version_line = None;
+
+    for (i, line) in source.lines().enumerate() {
+        if let Some(v) = directive(line, "version") {
+            if version_line.is_none() && expansion.body.code.trim().is_empty() {
+                version_line = Some(v.trim().to_string());
+                continue;
+            }
+        }
+
+        let origin = SourceLine {
+            chunk: None,
+            line: i + 1,
+        };
+        expansion.expand_line(line, origin)?;
+    }
+
+    let (number, prelude) = match version_line.as_deref() {
+        None | Some("100") => (version.number(), version.prelude(stage)),
+        Some(number) => (number, ""),
+    };
+    let mut result = Lines::default();
+    result.push(&format!("#version {}", number), None);
+    result.append(expansion.extensions);
+
+    for line in prelude.lines() {
+        result.push(line, None);
+    }
+
+    for (name, value) in &defines.defines {
+        if value.is_empty() {
+            result.push(&format!("#define {}", name), None);
+        } else {
+            result.push(&format!("#define {} {}", name, value), None);
+        }
+    }
+
+    result.append(expansion.body);
+
+    Ok(PreprocessedShader {
+        code: result.code,
+        lines: result.origins,
+    })
+}
+
+// Code being preprocessed, with the origin of its lines.
+#[derive(Default)]
+struct Lines {
+    code: String,
+    origins: Vec<Option<SourceLine>>,
+}
+
+impl Lines {
+    fn push(&mut self, line: &str, origin: Option<SourceLine>) {
+        self.code.push_str(line);
+        self.code.push('\n');
+        self.origins.push(origin);
+    }
+
+    fn append(&mut self, other: Lines) {
+        self.code.push_str(&other.code);
+        self.origins.extend(other.origins);
+    }
+}
+
+// The lines of a shader with its includes expanded, and the extensions they enable.
+#[derive(Default)]
+struct Expansion {
+    body: Lines,
+    extensions: Lines,
+    included: HashSet<String>,
+}
+
+impl Expansion {
+    fn expand_line(&mut self, line: &str, origin: SourceLine) -> Result<(), ShaderDiagnostic> {
+        if let Some(name) = directive(line, "include") {
+            let error = |message| ShaderDiagnostic {
+                location: Some(origin.clone()),
+                message,
+            };
+            let name = name.trim();
+            let name = name
+                .strip_prefix('"')
+                .and_then(|n| n.strip_suffix('"'))
+                .or_else(|| name.strip_prefix('<').and_then(|n| n.strip_suffix('>')))
+                .ok_or_else(|| error(format!("Invalid include: {}", line.trim())))?;
+
+            if self.included.insert(name.to_string()) {
+                let chunk = CHUNKS
+                    .with(|chunks| chunks.borrow().get(name).cloned())
+                    .ok_or_else(|| error(format!("Unknown shader chunk \"{}\"", name)))?;
+
+                for (i, line) in chunk.lines().enumerate() {
+                    if directive(line, "version").is_none() {
+                        let origin = SourceLine {
+                            chunk: Some(name.to_string()),
+                            line: i + 1,
+                        };
+                        self.expand_line(line, origin)?;
+                    }
+                }
+            }
+        } else if directive(line, "extension").is_some() {
+            self.extensions.push(line.trim(), Some(origin));
+        } else {
+            self.body.push(line, Some(origin));
+        }
+
+        Ok(())
+    }
+}
+
+// The arguments of a preprocessor directive, if `line` is the directive `name`.
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let args = rest.strip_prefix(name)?;
+
+    if args.is_empty() || args.starts_with(char::is_whitespace) {
+        Some(args)
+    } else {
+        None
+    }
+}
+
+static ES_FRAGMENT_PRELUDE: &str = "#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+";
+
+static GLSL150_VERTEX_PRELUDE: &str = "#define attribute in
+#define varying out
+#define texture2D texture
+";
+
+static GLSL150_FRAGMENT_PRELUDE: &str = "#define varying in
+#define texture2D texture
+#define gl_FragColor starman_frag_color
+out vec4 starman_frag_color;
+";
+
+static LIGHTING_CHUNK: &str = include_str!("../../shaders/lighting.glsl");
@@ -1,11 +1,14 @@
 use image::{self, imageops::FilterType, DynamicImage, GenericImageView};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::{
     context::context::{Context, Texture},
+    loader::texture_file::{self, TextureFile, TextureFormat},
+    resource::dynamic_texture::{DynamicTexture, DynamicTextureFormat},
+    resource::resource_usage::{ResourceUsage, UsageTracker},
     verify,
 };
 
@@ -15,6 +18,10 @@ pub enum TextureWrapping {
     Repeat,
     MirroredRepeat,
     ClampToEdge,
+    /// Coordinates outside of the texture read the border color of its `SamplerOptions`.
+    ///
+    /// Needs desktop OpenGL or OpenGL ES 3.2. Samplers fall back to `ClampToEdge` otherwise.
+    ClampToBorder,
 }
 
 impl From<TextureWrapping> for u32 {
@@ -24,6 +31,151 @@ impl From<TextureWrapping> for u32 {
             TextureWrapping::Repeat => Context::REPEAT,
             TextureWrapping::MirroredRepeat => Context::MIRRORED_REPEAT,
             TextureWrapping::ClampToEdge => Context::CLAMP_TO_EDGE,
+            TextureWrapping::ClampToBorder => Context::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+/// How texels are combined when a texture is sampled.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// The closest texel is read, keeping the edges of pixel art sharp.
+    Nearest,
+    /// The closest texels are interpolated.
+    Linear,
+}
+
+/// How a texture is sampled by shaders.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SamplerOptions {
+    /// The filter used when the texture is minified.
+    pub min_filter: TextureFilter,
+    /// The filter used when the texture is magnified.
+    pub mag_filter: TextureFilter,
+    /// The filter between mip levels, or `None` to only sample the full-size level. Ignored by
+    /// textures without mip levels.
+    pub mip_filter: Option<TextureFilter>,
+    pub wrap_s: TextureWrapping,
+    pub wrap_t: TextureWrapping,
+    /// The maximum anisotropy of the filtering, clamped to what the context supports. Values
+    /// above 1 sharpen textures seen at grazing angles, like floors.
+    pub anisotropy: f32,
+    /// The RGBA color read outside of the texture with `TextureWrapping::ClampToBorder`.
+    pub border_color: [f32; 4],
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        SamplerOptions {
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Linear,
+            mip_filter: Some(TextureFilter::Linear),
+            wrap_s: TextureWrapping::ClampToEdge,
+            wrap_t: TextureWrapping::ClampToEdge,
+            anisotropy: 1.0,
+            border_color: [0.0; 4],
+        }
+    }
+}
+
+impl SamplerOptions {
+    /// Nearest filtering without mipmapping, for pixel art.
+    pub fn nearest() -> Self {
+        SamplerOptions {
+            min_filter: TextureFilter::Nearest,
+            mag_filter: TextureFilter::Nearest,
+            mip_filter: None,
+            ..Default::default()
+        }
+    }
+
+    /// Linear filtering repeating the texture in both directions, for tiled surfaces.
+    pub fn repeat() -> Self {
+        SamplerOptions {
+            wrap_s: TextureWrapping::Repeat,
+            wrap_t: TextureWrapping::Repeat,
+            ..Default::default()
+        }
+    }
+
+    // Sets the filters, wrapping, anisotropy and border color of the bound texture.
+    // `mipmapped` tells whether the texture has mip levels to sample.
+    pub(crate) fn apply(&self, ctxt: &Context, mipmapped: bool) {
+        let (major, minor) = ctxt.version();
+        let embedded = ctxt.is_embedded();
+        let extension = |names: &[&str]| names.iter().any(|name| ctxt.supports_extension(name));
+
+        let border = !embedded
+            || (major, minor) >= (3, 2)
+            || extension(&["GL_EXT_texture_border_clamp", "GL_OES_texture_border_clamp"]);
+        let wrap = |wrapping| match wrapping {
+            TextureWrapping::ClampToBorder if !border => Context::CLAMP_TO_EDGE,
+            _ => u32::from(wrapping),
+        };
+
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_WRAP_S,
+            wrap(self.wrap_s) as i32
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_WRAP_T,
+            wrap(self.wrap_t) as i32
+        ));
+
+        let mip_filter = self.mip_filter.filter(|_| mipmapped);
+        let min_filter = match (self.min_filter, mip_filter) {
+            (TextureFilter::Nearest, None) => Context::NEAREST,
+            (TextureFilter::Linear, None) => Context::LINEAR,
+            (TextureFilter::Nearest, Some(TextureFilter::Nearest)) => {
+                Context::NEAREST_MIPMAP_NEAREST
+            }
+            (TextureFilter::Linear, Some(TextureFilter::Nearest)) => Context::LINEAR_MIPMAP_NEAREST,
+            (TextureFilter::Nearest, Some(TextureFilter::Linear)) => Context::NEAREST_MIPMAP_LINEAR,
+            (TextureFilter::Linear, Some(TextureFilter::Linear)) => Context::LINEAR_MIPMAP_LINEAR,
+        };
+        let mag_filter = match self.mag_filter {
+            TextureFilter::Nearest => Context::NEAREST,
+            TextureFilter::Linear => Context::LINEAR,
+        };
+
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_MIN_FILTER,
+            min_filter as i32
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_MAG_FILTER,
+            mag_filter as i32
+        ));
+
+        // Anisotropic filtering is core in OpenGL 4.6, and an extension everywhere else.
+        let anisotropic = (!embedded && (major, minor) >= (4, 6))
+            || extension(&[
+                "GL_EXT_texture_filter_anisotropic",
+                "GL_ARB_texture_filter_anisotropic",
+                "EXT_texture_filter_anisotropic",
+            ]);
+
+        if anisotropic {
+            let max = verify!(ctxt.get_parameter_f32(Context::MAX_TEXTURE_MAX_ANISOTROPY));
+            verify!(ctxt.tex_parameterf(
+                Context::TEXTURE_2D,
+                Context::TEXTURE_MAX_ANISOTROPY,
+                self.anisotropy.clamp(1.0, max.max(1.0))
+            ));
+        }
+
+        let clamp_to_border = [self.wrap_s, self.wrap_t].contains(&TextureWrapping::ClampToBorder);
+
+        if border && clamp_to_border {
+            verify!(ctxt.tex_parameterfv(
+                Context::TEXTURE_2D,
+                Context::TEXTURE_BORDER_COLOR,
+                &self.border_color
+            ));
         }
     }
 }
@@ -63,10 +215,273 @@ impl Drop for Texture {
 }
 
 
+/// How the color channels of a texture are encoded.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Values are used as they are. Suited to data such as height, roughness or normal maps.
+    Linear,
+    /// Values are sRGB-encoded colors, converted to linear values when sampled.
+    ///
+    /// Only 8-bit RGB and RGBA images are stored as sRGB. Contexts older than OpenGL 3 and
+    /// OpenGL ES 3 ignore it.
+    Srgb,
+}
+
+// How the pixels of an image are stored in a texture.
+pub(crate) struct PixelFormat {
+    pub(crate) internal: u32,
+    pub(crate) format: u32,
+    pub(crate) ty: u32,
+    // The sources of the red, green, blue and alpha channels read by shaders.
+    pub(crate) swizzle: Option<[u32; 4]>,
+}
+
+impl PixelFormat {
+    // The size of a pixel uploaded with this format.
+    pub(crate) fn bytes_per_pixel(&self) -> usize {
+        let channels = match self.format {
+            Context::RGBA => 4,
+            Context::RGB => 3,
+            Context::RG | Context::LUMINANCE_ALPHA => 2,
+            _ => 1,
+        };
+        let channel_bytes = match self.ty {
+            Context::FLOAT => 4,
+            Context::UNSIGNED_SHORT => 2,
+            _ => 1,
+        };
+
+        channels * channel_bytes
+    }
+
+    // Chooses how to store an image with the current context, converting it if the context
+    // cannot store it as it is.
+    //
+    // Luminance images are stored in one or two channels, read back as gray by shaders. 16-bit
+    // and floating point images keep their precision on desktop OpenGL, and are converted to
+    // 8 bits on OpenGL ES where they are not filterable. Other formats are converted to RGBA8.
+    pub(crate) fn select(
+        image: DynamicImage,
+        color_space: ColorSpace,
+    ) -> (DynamicImage, PixelFormat) {
+        let ctxt = Context::get();
+        let (major, _) = ctxt.version();
+        let embedded = ctxt.is_embedded();
+        // OpenGL 3 and OpenGL ES 3 have sized, sRGB and one or two-channel formats.
+        let gl3 = major >= 3;
+        let swizzle = supports_swizzle(&ctxt);
+        let high_precision = gl3 && !embedded;
+        let srgb = gl3 && color_space == ColorSpace::Srgb;
+
+        let format = |internal, format, ty| PixelFormat {
+            internal,
+            format,
+            ty,
+            swizzle: None,
+        };
+        let gray = |internal, format, ty, alpha| PixelFormat {
+            internal,
+            format,
+            ty,
+            swizzle: Some([Context::RED, Context::RED, Context::RED, alpha]),
+        };
+
+        match image {
+            DynamicImage::ImageRgb8(_) if srgb => {
+                (image, format(Context::SRGB8, Context::RGB, Context::UNSIGNED_BYTE))
+            }
+            DynamicImage::ImageRgba8(_) if srgb => (
+                image,
+                format(Context::SRGB8_ALPHA8, Context::RGBA, Context::UNSIGNED_BYTE),
+            ),
+            DynamicImage::ImageRgb8(_) => {
+                (image, format(Context::RGB, Context::RGB, Context::UNSIGNED_BYTE))
+            }
+            DynamicImage::ImageRgba8(_) => {
+                (image, format(Context::RGBA, Context::RGBA, Context::UNSIGNED_BYTE))
+            }
+            DynamicImage::ImageLuma8(_) if swizzle => (
+                image,
+                gray(Context::R8, Context::RED, Context::UNSIGNED_BYTE, Context::ONE),
+            ),
+            DynamicImage::ImageLumaA8(_) if swizzle => (
+                image,
+                gray(Context::RG8, Context::RG, Context::UNSIGNED_BYTE, Context::GREEN),
+            ),
+            DynamicImage::ImageLuma8(_) if embedded => (
+                image,
+                format(Context::LUMINANCE, Context::LUMINANCE, Context::UNSIGNED_BYTE),
+            ),
+            DynamicImage::ImageLumaA8(_) if embedded => (
+                image,
+                format(
+                    Context::LUMINANCE_ALPHA,
+                    Context::LUMINANCE_ALPHA,
+                    Context::UNSIGNED_BYTE,
+                ),
+            ),
+            DynamicImage::ImageLuma16(_) if high_precision && swizzle => (
+                image,
+                gray(Context::R16, Context::RED, Context::UNSIGNED_SHORT, Context::ONE),
+            ),
+            DynamicImage::ImageLumaA16(_) if high_precision && swizzle => (
+                image,
+                gray(Context::RG16, Context::RG, Context::UNSIGNED_SHORT, Context::GREEN),
+            ),
+            DynamicImage::ImageLuma16(_) | DynamicImage::ImageRgb16(_) if high_precision => (
+                DynamicImage::ImageRgb16(image.into_rgb16()),
+                format(Context::RGB16, Context::RGB, Context::UNSIGNED_SHORT),
+            ),
+            DynamicImage::ImageLumaA16(_) | DynamicImage::ImageRgba16(_) if high_precision => (
+                DynamicImage::ImageRgba16(image.into_rgba16()),
+                format(Context::RGBA16, Context::RGBA, Context::UNSIGNED_SHORT),
+            ),
+            DynamicImage::ImageRgb32F(_) if high_precision => {
+                (image, format(Context::RGB32F, Context::RGB, Context::FLOAT))
+            }
+            DynamicImage::ImageRgba32F(_) if high_precision => {
+                (image, format(Context::RGBA32F, Context::RGBA, Context::FLOAT))
+            }
+            DynamicImage::ImageLuma16(_) => {
+                PixelFormat::select(DynamicImage::ImageLuma8(image.into_luma8()), color_space)
+            }
+            DynamicImage::ImageLumaA16(_) => PixelFormat::select(
+                DynamicImage::ImageLumaA8(image.into_luma_alpha8()),
+                color_space,
+            ),
+            DynamicImage::ImageLuma8(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgb32F(_) => {
+                PixelFormat::select(DynamicImage::ImageRgb8(image.into_rgb8()), color_space)
+            }
+            _ => PixelFormat::select(DynamicImage::ImageRgba8(image.into_rgba8()), color_space),
+        }
+    }
+}
+
+// Whether the context can remap the channels read by shaders, which needs OpenGL 3.3 or
+// OpenGL ES 3.
+pub(crate) fn supports_swizzle(ctxt: &Context) -> bool {
+    let (major, minor) = ctxt.version();
+    major >= 3 && (ctxt.is_embedded() || minor >= 3 || major > 3)
+}
+
+// The OpenGL format of a block-compressed texture, if the context can sample it. BC4 textures
+// are read back as gray if the context supports swizzling, like decoded BC4 images.
+fn compressed_format(format: TextureFormat, srgb: bool) -> Option<(u32, Option<[u32; 4]>)> {
+    let ctxt = Context::get();
+    let (major, minor) = ctxt.version();
+    let embedded = ctxt.is_embedded();
+    let desktop = |version: (u32, u32)| !embedded && (major, minor) >= version;
+    let extension = |names: &[&str]| names.iter().any(|name| ctxt.supports_extension(name));
+
+    let s3tc = || {
+        extension(&[
+            "GL_EXT_texture_compression_s3tc",
+            "WEBGL_compressed_texture_s3tc",
+        ]) && (!srgb
+            || extension(&[
+                "GL_EXT_texture_sRGB",
+                "GL_EXT_texture_compression_s3tc_srgb",
+                "WEBGL_compressed_texture_s3tc_srgb",
+            ]))
+    };
+    let rgtc = || {
+        desktop((3, 0))
+            || extension(&[
+                "GL_ARB_texture_compression_rgtc",
+                "GL_EXT_texture_compression_rgtc",
+                "EXT_texture_compression_rgtc",
+            ])
+    };
+    let bptc = || {
+        desktop((4, 2))
+            || extension(&[
+                "GL_ARB_texture_compression_bptc",
+                "GL_EXT_texture_compression_bptc",
+                "EXT_texture_compression_bptc",
+            ])
+    };
+    let etc2 = || {
+        (embedded && major >= 3)
+            || desktop((4, 3))
+            || extension(&["GL_ARB_ES3_compatibility", "WEBGL_compressed_texture_etc"])
+    };
+    let pick = |linear, srgb_format| if srgb { srgb_format } else { linear };
+
+    let internal = match format {
+        TextureFormat::Rgb8 | TextureFormat::Rgba8 => return None,
+        TextureFormat::Bc1 if s3tc() => pick(
+            Context::COMPRESSED_RGB_S3TC_DXT1_EXT,
+            Context::COMPRESSED_SRGB_S3TC_DXT1_EXT,
+        ),
+        TextureFormat::Bc1Alpha if s3tc() => pick(
+            Context::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            Context::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+        ),
+        TextureFormat::Bc2 if s3tc() => pick(
+            Context::COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            Context::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,
+        ),
+        TextureFormat::Bc3 if s3tc() => pick(
+            Context::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            Context::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+        ),
+        TextureFormat::Bc4 if rgtc() => {
+            let gray = [Context::RED, Context::RED, Context::RED, Context::ONE];
+            let swizzle = Some(gray).filter(|_| supports_swizzle(&ctxt));
+            return Some((Context::COMPRESSED_RED_RGTC1, swizzle));
+        }
+        TextureFormat::Bc5 if rgtc() => Context::COMPRESSED_RG_RGTC2,
+        TextureFormat::Bc6hUfloat if bptc() => Context::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+        TextureFormat::Bc6hSfloat if bptc() => Context::COMPRESSED_RGB_BPTC_SIGNED_FLOAT,
+        TextureFormat::Bc7 if bptc() => pick(
+            Context::COMPRESSED_RGBA_BPTC_UNORM,
+            Context::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+        ),
+        TextureFormat::Etc2Rgb8 if etc2() => pick(
+            Context::COMPRESSED_RGB8_ETC2,
+            Context::COMPRESSED_SRGB8_ETC2,
+        ),
+        TextureFormat::Etc2Rgb8A1 if etc2() => pick(
+            Context::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            Context::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+        ),
+        TextureFormat::Etc2Rgba8 if etc2() => pick(
+            Context::COMPRESSED_RGBA8_ETC2_EAC,
+            Context::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        ),
+        _ => return None,
+    };
+
+    Some((internal, None))
+}
+
+// A texture uploaded to the GPU.
+struct LoadedTexture {
+    texture: Rc<Texture>,
+    size: (u32, u32),
+    // Whether its sampler can use mip levels.
+    mipmapped: bool,
+    // The size of its levels, in bytes.
+    bytes: usize,
+}
+
+// The file a texture was loaded from, and the options it was loaded with.
+struct TextureSource {
+    path: PathBuf,
+    color_space: ColorSpace,
+    sampler: SamplerOptions,
+}
+
 pub struct TextureManager {
     default_texture: Rc<Texture>,
-    textures: HashMap<String, (Rc<Texture>, (u32, u32))>,
+    textures: HashMap<String, LoadedTexture>,
+    // Files the textures added with `add` were loaded from, to reload them when they change.
+    sources: HashMap<String, TextureSource>,
     generate_mipmaps: bool,
+    sampler: SamplerOptions,
+    tracker: UsageTracker,
 }
 
 impl TextureManager {
@@ -112,8 +527,11 @@ impl TextureManager {
 
         TextureManager {
             textures: HashMap::new(),
+            sources: HashMap::new(),
             default_texture: default_tex,
             generate_mipmaps: false,
+            sampler: SamplerOptions::default(),
+            tracker: UsageTracker::default(),
         }
     }
 
@@ -127,31 +545,82 @@ impl TextureManager {
     }
 
     pub fn get(&mut self, name: &str) -> Option<Rc<Texture>> {
-        self.textures.get(&name.to_string()).map(|t| t.0.clone())
+        self.tracker.touch(name);
+        self.textures.get(name).map(|t| t.texture.clone())
     }
 
     pub fn get_with_size(&mut self, name: &str) -> Option<(Rc<Texture>, (u32, u32))> {
-        self.textures
-            .get(&name.to_string())
-            .map(|t| (t.0.clone(), t.1))
+        self.tracker.touch(name);
+        self.textures.get(name).map(|t| (t.texture.clone(), t.size))
     }
 
     pub fn add_empty(&mut self, name: &str) -> Rc<Texture> {
+        self.tracker.touch(name);
+
         match self.textures.entry(name.to_string()) {
-            Entry::Occupied(entry) => entry.into_mut().0.clone(),
-            Entry::Vacant(entry) => entry.insert((Texture::new(), (0, 0))).0.clone(),
+            Entry::Occupied(entry) => entry.into_mut().texture.clone(),
+            Entry::Vacant(entry) => entry
+                .insert(LoadedTexture {
+                    texture: Texture::new(),
+                    size: (0, 0),
+                    mipmapped: false,
+                    bytes: 0,
+                })
+                .texture
+                .clone(),
         }
     }
 
     pub fn add_image(&mut self, image: DynamicImage, name: &str) -> Rc<Texture> {
+        self.add_image_with_color_space(image, name, ColorSpace::Linear)
+    }
+
+    /// Like `add_image`, with the color space of the image.
+    pub fn add_image_with_color_space(
+        &mut self,
+        image: DynamicImage,
+        name: &str,
+        color_space: ColorSpace,
+    ) -> Rc<Texture> {
+        let sampler = self.sampler;
+        self.add_image_with_options(image, name, color_space, sampler)
+    }
+
+    /// Like `add_image`, with the sampler of the texture instead of the default one.
+    pub fn add_image_with_sampler(
+        &mut self,
+        image: DynamicImage,
+        name: &str,
+        sampler: SamplerOptions,
+    ) -> Rc<Texture> {
+        self.add_image_with_options(image, name, ColorSpace::Linear, sampler)
+    }
+
+    /// Like `add_image`, with the color space of the image and the sampler of the texture.
+    pub fn add_image_with_options(
+        &mut self,
+        image: DynamicImage,
+        name: &str,
+        color_space: ColorSpace,
+        sampler: SamplerOptions,
+    ) -> Rc<Texture> {
         let generate_mipmaps = self.generate_mipmaps;
-        self.textures
+        let texture = self
+            .textures
             .entry(name.to_string())
             .or_insert_with(|| {
-                TextureManager::load_texture_into_context(image, generate_mipmaps).unwrap()
+                TextureManager::load_texture_into_context(
+                    image,
+                    Texture::new(),
+                    color_space,
+                    generate_mipmaps,
+                    &sampler,
+                )
             })
-            .0
-            .clone()
+            .texture
+            .clone();
+        self.tracker.touch(name);
+        texture
     }
 
     pub fn add_image_from_memory(&mut self, image_data: &[u8], name: &str) -> Rc<Texture> {
@@ -161,104 +630,542 @@ impl TextureManager {
         )
     }
 
-    fn load_texture_from_file(path: &Path, generate_mipmaps: bool) -> (Rc<Texture>, (u32, u32)) {
+    // Loads the file at `path` into `texture`, which is left untouched if the file cannot be
+    // read.
+    fn load_texture_from_file(
+        path: &Path,
+        texture: Rc<Texture>,
+        color_space: ColorSpace,
+        generate_mipmaps: bool,
+        sampler: &SamplerOptions,
+    ) -> Result<LoadedTexture, String> {
+        if texture_file::is_texture_file(path) {
+            let file = texture_file::load_texture_file(path)
+                .map_err(|e| format!("Unable to load texture from file {:?}: {}", path, e))?;
+            return Ok(TextureManager::load_texture_file_into_context(
+                file,
+                texture,
+                color_space,
+                generate_mipmaps,
+                sampler,
+            ));
+        }
+
         let image = image::open(path)
-            .unwrap_or_else(|e| panic!("Unable to load texture from file {:?}: {:?}", path, e));
-        TextureManager::load_texture_into_context(image, generate_mipmaps)
-            .unwrap_or_else(|e| panic!("Unable to upload texture {:?}: {:?}", path, e))
+            .map_err(|e| format!("Unable to load texture from file {:?}: {:?}", path, e))?;
+        Ok(TextureManager::load_texture_into_context(
+            image,
+            texture,
+            color_space,
+            generate_mipmaps,
+            sampler,
+        ))
+    }
+
+    // Uploads the mip levels of a texture file, compressed if the context supports its format,
+    // and decoded otherwise. Mipmaps are only generated for files with a single level that had
+    // to be decoded.
+    fn load_texture_file_into_context(
+        file: TextureFile,
+        tex: Rc<Texture>,
+        color_space: ColorSpace,
+        generate_mipmaps: bool,
+        sampler: &SamplerOptions,
+    ) -> LoadedTexture {
+        let color_space = if file.srgb {
+            ColorSpace::Srgb
+        } else {
+            color_space
+        };
+        let srgb = color_space == ColorSpace::Srgb;
+
+        let (internal, swizzle) = match compressed_format(file.format, srgb) {
+            Some(format) => format,
+            None if file.levels.len() == 1 => {
+                return TextureManager::load_texture_into_context(
+                    file.decode_level(0),
+                    tex,
+                    color_space,
+                    generate_mipmaps,
+                    sampler,
+                )
+            }
+            None => {
+                return TextureManager::load_decoded_levels_into_context(
+                    &file,
+                    tex,
+                    color_space,
+                    sampler,
+                )
+            }
+        };
+
+        let ctxt = Context::get();
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*tex)));
+
+        for (level, pixels) in file.levels.iter().enumerate() {
+            let (width, height) = file.level_size(level);
+            verify!(ctxt.compressed_tex_image2d(
+                Context::TEXTURE_2D,
+                level as i32,
+                internal,
+                width as i32,
+                height as i32,
+                0,
+                pixels
+            ));
+        }
+
+        let mipmapped = TextureManager::set_texture_parameters(
+            &ctxt,
+            swizzle,
+            file.levels.len(),
+            file.has_full_mip_chain(),
+            sampler,
+        );
+
+        LoadedTexture {
+            texture: tex,
+            size: (file.width, file.height),
+            mipmapped,
+            bytes: file.num_bytes(),
+        }
+    }
+
+    // Decodes and uploads every mip level of a texture file.
+    fn load_decoded_levels_into_context(
+        file: &TextureFile,
+        tex: Rc<Texture>,
+        color_space: ColorSpace,
+        sampler: &SamplerOptions,
+    ) -> LoadedTexture {
+        let ctxt = Context::get();
+        let mut swizzle = None;
+        let mut bytes = 0;
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*tex)));
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+
+        for level in 0..file.levels.len() {
+            let (image, format) = PixelFormat::select(file.decode_level(level), color_space);
+            TextureManager::call_tex_image2d(&ctxt, &image, &format, level as i32);
+            swizzle = format.swizzle;
+            bytes += image.as_bytes().len();
+        }
+
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 4));
+        let mipmapped = TextureManager::set_texture_parameters(
+            &ctxt,
+            swizzle,
+            file.levels.len(),
+            file.has_full_mip_chain(),
+            sampler,
+        );
+
+        LoadedTexture {
+            texture: tex,
+            size: (file.width, file.height),
+            mipmapped,
+            bytes,
+        }
     }
 
     fn load_texture_into_context(
         image: DynamicImage,
+        tex: Rc<Texture>,
+        color_space: ColorSpace,
         generate_mipmaps: bool,
-    ) -> Result<(Rc<Texture>, (u32, u32)), &'static str> {
+        sampler: &SamplerOptions,
+    ) -> LoadedTexture {
         let ctxt = Context::get();
-        let tex = Texture::new();
         let (width, height) = image.dimensions();
+        let (image, format) = PixelFormat::select(image, color_space);
 
-        unsafe {
-            verify!(ctxt.active_texture(Context::TEXTURE0));
-            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*tex)));
-            TextureManager::call_tex_image2d(&ctxt, &image, 0)?;
-
-            let mut min_filter = Context::LINEAR;
-            if generate_mipmaps {
-                let (mut w, mut h) = (width, height);
-                let mut image = image;
-
-                for level in 1.. {
-                    if w == 1 && h == 1 {
-                        break;
-                    }
-                    w = (w + 1) / 2;
-                    h = (h + 1) / 2;
-                    image = image.resize_exact(w, h, FilterType::CatmullRom);
-                    TextureManager::call_tex_image2d(&ctxt, &image, level)?;
-                }
-                min_filter = Context::LINEAR_MIPMAP_LINEAR;
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*tex)));
+        // Rows of single-channel or RGB images are not necessarily aligned to 4 bytes.
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+        TextureManager::call_tex_image2d(&ctxt, &image, &format, 0);
+
+        let mut num_levels = 1;
+        let mut bytes = image.as_bytes().len();
+        if generate_mipmaps {
+            let (mut w, mut h) = (width, height);
+            let mut image = image;
+
+            while w > 1 || h > 1 {
+                w = (w + 1) / 2;
+                h = (h + 1) / 2;
+                image = image.resize_exact(w, h, FilterType::CatmullRom);
+                TextureManager::call_tex_image2d(&ctxt, &image, &format, num_levels);
+                num_levels += 1;
+                bytes += image.as_bytes().len();
             }
+        }
 
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 4));
+        let mipmapped = TextureManager::set_texture_parameters(
+            &ctxt,
+            format.swizzle,
+            num_levels as usize,
+            true,
+            sampler,
+        );
+
+        LoadedTexture {
+            texture: tex,
+            size: (width, height),
+            mipmapped,
+            bytes,
+        }
+    }
+
+    // Sets the swizzle and the sampler of the bound texture, and returns whether it is
+    // mipmapped. Textures with several levels use mipmapping if their levels go down to 1x1, or
+    // if the context can limit the levels sampled.
+    pub(crate) fn set_texture_parameters(
+        ctxt: &Context,
+        swizzle: Option<[u32; 4]>,
+        num_levels: usize,
+        full_mip_chain: bool,
+        sampler: &SamplerOptions,
+    ) -> bool {
+        let limit_levels = ctxt.version().0 >= 3;
+        let mipmapped = num_levels > 1 && (full_mip_chain || limit_levels);
+
+        if mipmapped && limit_levels {
             verify!(ctxt.tex_parameteri(
                 Context::TEXTURE_2D,
-                Context::TEXTURE_WRAP_S,
-                Context::CLAMP_TO_EDGE as i32
-            ));
-            verify!(ctxt.tex_parameteri(
-                Context::TEXTURE_2D,
-                Context::TEXTURE_WRAP_T,
-                Context::CLAMP_TO_EDGE as i32
-            ));
-            verify!(ctxt.tex_parameteri(
-                Context::TEXTURE_2D,
-                Context::TEXTURE_MIN_FILTER,
-                min_filter as i32,
-            ));
-            verify!(ctxt.tex_parameteri(
-                Context::TEXTURE_2D,
-                Context::TEXTURE_MAG_FILTER,
-                Context::LINEAR as i32
+                Context::TEXTURE_MAX_LEVEL,
+                num_levels as i32 - 1
             ));
         }
-        Ok((tex, (width, height)))
-    }
 
-    fn call_tex_image2d(
-        ctxt: &Context,
-        dynamic_image: &DynamicImage,
-        level: i32,
-    ) -> Result<(), &'static str> {
-        let (pixel_format, pixels) = match dynamic_image {
-            DynamicImage::ImageRgb8(image) => (Context::RGB, &image.as_raw()[..]),
-            DynamicImage::ImageRgba8(image) => (Context::RGBA, &image.as_raw()[..]),
-            _ => {
-                return Err("Failed to load texture, unsuported pixel format.");
+        // Textures reloaded in place may keep the swizzle of their previous content otherwise.
+        let identity = [Context::RED, Context::GREEN, Context::BLUE, Context::ALPHA];
+        let swizzle = swizzle.or_else(|| supports_swizzle(ctxt).then_some(identity));
+
+        if let Some(swizzle) = swizzle {
+            let channels = [
+                Context::TEXTURE_SWIZZLE_R,
+                Context::TEXTURE_SWIZZLE_G,
+                Context::TEXTURE_SWIZZLE_B,
+                Context::TEXTURE_SWIZZLE_A,
+            ];
+
+            for (channel, source) in channels.iter().zip(swizzle.iter()) {
+                verify!(ctxt.tex_parameteri(Context::TEXTURE_2D, *channel, *source as i32));
             }
-        };
-        let (width, height) = dynamic_image.dimensions();
+        }
 
-        verify!(ctxt.tex_image2d(
+        sampler.apply(ctxt, mipmapped);
+        mipmapped
+    }
+
+    fn call_tex_image2d(ctxt: &Context, image: &DynamicImage, format: &PixelFormat, level: i32) {
+        let (width, height) = image.dimensions();
+
+        verify!(ctxt.tex_image2d_with_type(
             Context::TEXTURE_2D,
             level,
-            pixel_format as i32,
+            format.internal as i32,
             width as i32,
             height as i32,
             0,
-            pixel_format,
-            Some(pixels)
+            format.format,
+            format.ty,
+            Some(image.as_bytes())
         ));
-        Ok(())
     }
 
+    /// Loads the image file at `path`, or the KTX2 or DDS texture file with its mip levels.
     pub fn add(&mut self, path: &Path, name: &str) -> Rc<Texture> {
+        self.add_with_color_space(path, name, ColorSpace::Linear)
+    }
+
+    /// Like `add`, with the color space of the image file.
+    pub fn add_with_color_space(
+        &mut self,
+        path: &Path,
+        name: &str,
+        color_space: ColorSpace,
+    ) -> Rc<Texture> {
+        let sampler = self.sampler;
+        self.add_with_options(path, name, color_space, sampler)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `add`, with the sampler of the texture instead of the default one.
+    pub fn add_with_sampler(
+        &mut self,
+        path: &Path,
+        name: &str,
+        sampler: SamplerOptions,
+    ) -> Rc<Texture> {
+        self.add_with_options(path, name, ColorSpace::Linear, sampler)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `add`, with the color space of the image file and the sampler of the texture.
+    ///
+    /// Returns an error if the file cannot be loaded, where `add` panics.
+    pub fn add_with_options(
+        &mut self,
+        path: &Path,
+        name: &str,
+        color_space: ColorSpace,
+        sampler: SamplerOptions,
+    ) -> Result<Rc<Texture>, String> {
+        if !self.textures.contains_key(name) {
+            let loaded = TextureManager::load_texture_from_file(
+                path,
+                Texture::new(),
+                color_space,
+                self.generate_mipmaps,
+                &sampler,
+            )?;
+            self.add_source(name, path, color_space, sampler);
+            let _ = self.textures.insert(name.to_string(), loaded);
+        }
+
+        let texture = self.textures[name].texture.clone();
+        self.tracker.touch(name);
+        Ok(texture)
+    }
+
+    /// Like `add`, with the image already decoded from the file at `path`.
+    pub fn add_decoded(&mut self, image: DynamicImage, path: &Path, name: &str) -> Rc<Texture> {
         let generate_mipmaps = self.generate_mipmaps;
-        self.textures
+        let sampler = self.sampler;
+        if !self.textures.contains_key(name) {
+            self.add_source(name, path, ColorSpace::Linear, sampler);
+        }
+
+        let texture = self
+            .textures
             .entry(name.to_string())
-            .or_insert_with(|| TextureManager::load_texture_from_file(path, generate_mipmaps))
-            .0
-            .clone()
+            .or_insert_with(|| {
+                TextureManager::load_texture_into_context(
+                    image,
+                    Texture::new(),
+                    ColorSpace::Linear,
+                    generate_mipmaps,
+                    &sampler,
+                )
+            })
+            .texture
+            .clone();
+        self.tracker.touch(name);
+        texture
+    }
+
+    /// Like `add`, with the texture file already read from `path`.
+    pub fn add_texture_file(&mut self, file: TextureFile, path: &Path, name: &str) -> Rc<Texture> {
+        let generate_mipmaps = self.generate_mipmaps;
+        let sampler = self.sampler;
+        if !self.textures.contains_key(name) {
+            self.add_source(name, path, ColorSpace::Linear, sampler);
+        }
+
+        let texture = self
+            .textures
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                TextureManager::load_texture_file_into_context(
+                    file,
+                    Texture::new(),
+                    ColorSpace::Linear,
+                    generate_mipmaps,
+                    &sampler,
+                )
+            })
+            .texture
+            .clone();
+        self.tracker.touch(name);
+        texture
+    }
+
+    /// Creates a texture of the given size updated from memory with `DynamicTexture::update`,
+    /// and registers it with `name` in place of any texture with that name.
+    pub fn add_dynamic(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        format: DynamicTextureFormat,
+    ) -> DynamicTexture {
+        self.add_dynamic_with_color_space(name, width, height, format, ColorSpace::Linear)
+    }
+
+    /// Like `add_dynamic`, with the color space of the pixels.
+    pub fn add_dynamic_with_color_space(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        format: DynamicTextureFormat,
+        color_space: ColorSpace,
+    ) -> DynamicTexture {
+        let texture = DynamicTexture::new(width, height, format, color_space, &self.sampler);
+        let _ = self.sources.remove(name);
+        let loaded = LoadedTexture {
+            texture: texture.texture(),
+            size: (width, height),
+            mipmapped: false,
+            bytes: texture.num_bytes(),
+        };
+        let _ = self.textures.insert(name.to_string(), loaded);
+        self.tracker.touch(name);
+        texture
+    }
+
+    /// The name a texture was registered with, if any.
+    pub fn name_of(&self, texture: &Rc<Texture>) -> Option<String> {
+        self.textures
+            .iter()
+            .find(|(_, t)| Rc::ptr_eq(&t.texture, texture))
+            .map(|(name, _)| name.clone())
+    }
+
+    /// The file a texture was loaded from, if it was added with `add`.
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.sources.get(name).map(|s| s.path.as_path())
+    }
+
+    fn add_source(
+        &mut self,
+        name: &str,
+        path: &Path,
+        color_space: ColorSpace,
+        sampler: SamplerOptions,
+    ) {
+        let source = TextureSource {
+            path: path.to_path_buf(),
+            color_space,
+            sampler,
+        };
+        let _ = self.sources.insert(name.to_string(), source);
+    }
+
+    // The names and files of the textures loaded from files.
+    pub(crate) fn sources(&self) -> Vec<(String, PathBuf)> {
+        self.sources
+            .iter()
+            .map(|(name, source)| (name.clone(), source.path.clone()))
+            .collect()
+    }
+
+    // Loads the texture registered with `name` again from its file, into the same GL texture.
+    // The texture is left as it was if the file cannot be read.
+    pub(crate) fn reload(&mut self, name: &str) -> Result<(), String> {
+        let (source, loaded) = match (self.sources.get(name), self.textures.get_mut(name)) {
+            (Some(source), Some(loaded)) => (source, loaded),
+            _ => return Ok(()),
+        };
+
+        *loaded = TextureManager::load_texture_from_file(
+            &source.path,
+            loaded.texture.clone(),
+            source.color_space,
+            self.generate_mipmaps,
+            &source.sampler,
+        )?;
+        Ok(())
     }
 
     pub fn set_generate_mipmaps(&mut self, enabled: bool) {
         self.generate_mipmaps = enabled;
     }
+
+    /// Usage statistics of the registered textures.
+    pub fn usage(&self) -> Vec<ResourceUsage> {
+        self.textures
+            .iter()
+            .map(|(name, t)| {
+                self.tracker
+                    .usage(name, t.bytes, Rc::strong_count(&t.texture) - 1)
+            })
+            .collect()
+    }
+
+    /// The estimated GPU memory used by the registered textures, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.textures.values().map(|t| t.bytes).sum()
+    }
+
+    /// Removes the textures not referenced outside of this manager, except pinned ones, and
+    /// returns their names.
+    pub fn purge_unreferenced(&mut self) -> Vec<String> {
+        let names: Vec<_> = self
+            .usage()
+            .into_iter()
+            .filter(|u| u.is_evictable())
+            .map(|u| u.name)
+            .collect();
+        self.remove_all(&names);
+        names
+    }
+
+    /// Sets the GPU memory the registered textures may use. Past it, the least recently used
+    /// textures not referenced outside of this manager are removed at the next frame.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) {
+        self.tracker.set_budget(bytes);
+    }
+
+    /// Sets whether the texture registered with `name` is kept by purges and evictions.
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) {
+        self.tracker.set_pinned(name, pinned);
+    }
+
+    // Counts the textures still referenced as used, and evicts textures over the budget.
+    pub(crate) fn next_frame(&mut self) {
+        self.tracker.next_frame(
+            self.textures
+                .iter()
+                .filter(|(_, t)| Rc::strong_count(&t.texture) > 1)
+                .map(|(name, _)| name.as_str()),
+        );
+
+        let evicted = self.tracker.evictions(&self.usage());
+        self.remove_all(&evicted);
+    }
+
+    fn remove_all(&mut self, names: &[String]) {
+        for name in names {
+            let _ = self.textures.remove(name);
+            let _ = self.sources.remove(name);
+            self.tracker.remove(name);
+        }
+    }
+
+    /// Sets the sampler of the textures added afterwards without one.
+    pub fn set_default_sampler(&mut self, sampler: SamplerOptions) {
+        self.sampler = sampler;
+    }
+
+    /// The sampler of the textures added without one.
+    pub fn default_sampler(&self) -> SamplerOptions {
+        self.sampler
+    }
+
+    /// Changes the sampler of the texture registered with `name`. Returns `false` if there is
+    /// no such texture.
+    pub fn set_sampler(&mut self, name: &str, sampler: &SamplerOptions) -> bool {
+        if let Some(source) = self.sources.get_mut(name) {
+            source.sampler = *sampler;
+        }
+
+        match self.textures.get(name) {
+            Some(loaded) => {
+                let ctxt = Context::get();
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*loaded.texture)));
+                sampler.apply(&ctxt, loaded.mipmapped);
+                true
+            }
+            None => false,
+        }
+    }
 }
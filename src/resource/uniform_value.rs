@@ -0,0 +1,131 @@
+//! Values of shader uniforms, set per object and read by materials.
+
+use std::rc::Rc;
+
+use nalgebra::{Matrix2, Matrix3, Matrix4, Point3, Vector2, Vector3, Vector4};
+
+use crate::context::context::{Context, GLenum, Texture};
+
+/// The GLSL type of a uniform a `UniformValue` can be given to.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum UniformType {
+    Float,
+    Int,
+    Vec2,
+    Vec3,
+    Vec4,
+    Mat2,
+    Mat3,
+    Mat4,
+    /// A `sampler2D`.
+    Texture,
+}
+
+impl UniformType {
+    /// The type of uniforms reported by the driver with the GL type `type_`, if supported.
+    pub fn from_gl(type_: GLenum) -> Option<UniformType> {
+        match type_ {
+            Context::FLOAT => Some(UniformType::Float),
+            Context::INT => Some(UniformType::Int),
+            Context::FLOAT_VEC2 => Some(UniformType::Vec2),
+            Context::FLOAT_VEC3 => Some(UniformType::Vec3),
+            Context::FLOAT_VEC4 => Some(UniformType::Vec4),
+            Context::FLOAT_MAT2 => Some(UniformType::Mat2),
+            Context::FLOAT_MAT3 => Some(UniformType::Mat3),
+            Context::FLOAT_MAT4 => Some(UniformType::Mat4),
+            Context::SAMPLER_2D => Some(UniformType::Texture),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a uniform.
+#[derive(Clone)]
+pub enum UniformValue {
+    Float(f32),
+    Int(i32),
+    Vec2(Vector2<f32>),
+    Vec3(Vector3<f32>),
+    Vec4(Vector4<f32>),
+    Mat2(Matrix2<f32>),
+    Mat3(Matrix3<f32>),
+    Mat4(Matrix4<f32>),
+    Texture(Rc<Texture>),
+}
+
+impl UniformValue {
+    pub fn uniform_type(&self) -> UniformType {
+        match self {
+            UniformValue::Float(_) => UniformType::Float,
+            UniformValue::Int(_) => UniformType::Int,
+            UniformValue::Vec2(_) => UniformType::Vec2,
+            UniformValue::Vec3(_) => UniformType::Vec3,
+            UniformValue::Vec4(_) => UniformType::Vec4,
+            UniformValue::Mat2(_) => UniformType::Mat2,
+            UniformValue::Mat3(_) => UniformType::Mat3,
+            UniformValue::Mat4(_) => UniformType::Mat4,
+            UniformValue::Texture(_) => UniformType::Texture,
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> UniformValue {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> UniformValue {
+        UniformValue::Int(value)
+    }
+}
+
+impl From<Vector2<f32>> for UniformValue {
+    fn from(value: Vector2<f32>) -> UniformValue {
+        UniformValue::Vec2(value)
+    }
+}
+
+impl From<Vector3<f32>> for UniformValue {
+    fn from(value: Vector3<f32>) -> UniformValue {
+        UniformValue::Vec3(value)
+    }
+}
+
+/// Colors are given as points, like with `SceneNode::set_color`.
+impl From<Point3<f32>> for UniformValue {
+    fn from(value: Point3<f32>) -> UniformValue {
+        UniformValue::Vec3(value.coords)
+    }
+}
+
+impl From<Vector4<f32>> for UniformValue {
+    fn from(value: Vector4<f32>) -> UniformValue {
+        UniformValue::Vec4(value)
+    }
+}
+
+impl From<Matrix2<f32>> for UniformValue {
+    fn from(value: Matrix2<f32>) -> UniformValue {
+        UniformValue::Mat2(value)
+    }
+}
+
+impl From<Matrix3<f32>> for UniformValue {
+    fn from(value: Matrix3<f32>) -> UniformValue {
+        UniformValue::Mat3(value)
+    }
+}
+
+impl From<Matrix4<f32>> for UniformValue {
+    fn from(value: Matrix4<f32>) -> UniformValue {
+        UniformValue::Mat4(value)
+    }
+}
+
+impl From<Rc<Texture>> for UniformValue {
+    fn from(value: Rc<Texture>) -> UniformValue {
+        UniformValue::Texture(value)
+    }
+}
@@ -0,0 +1,27 @@
+//! Selection outlines drawn around scene nodes.
+
+use nalgebra::Point3;
+
+/// The largest outline width, in pixels, supported by the outline renderer.
+pub const MAX_HIGHLIGHT_WIDTH: f32 = 8.0;
+
+/// The outline drawn around the silhouette of a highlighted scene node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Highlight {
+    /// The color of the outline.
+    pub color: Point3<f32>,
+    /// The width of the outline, in pixels. Clamped to `[1, MAX_HIGHLIGHT_WIDTH]` when drawn.
+    pub width: f32,
+}
+
+impl Highlight {
+    pub fn new(color: Point3<f32>, width: f32) -> Highlight {
+        Highlight { color, width }
+    }
+}
+
+impl Default for Highlight {
+    fn default() -> Highlight {
+        Highlight::new(Point3::new(1.0, 0.6, 0.0), 3.0)
+    }
+}
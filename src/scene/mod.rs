@@ -1,4 +1,7 @@
 pub mod object;
 pub mod planar_object;
+pub mod scene_description;
 pub mod scene_node;
-pub mod planar_scene_node;
\ No newline at end of file
+pub mod planar_scene_node;
+pub mod ray_cast;
+pub mod highlight;
\ No newline at end of file
@@ -3,8 +3,10 @@ use crate::context::context::Texture;
 use crate::light::Light;
 use crate::resource::material::Material;
 use crate::resource::vertex_index::VertexIndex;
-use crate::resource::mesh::Mesh;
+use crate::resource::mesh::{Mesh, Primitive};
 use crate::resource::texture_manager::TextureManager;
+use crate::resource::material_parameters::MaterialParameters;
+use crate::resource::uniform_value::UniformValue;
 use nalgebra::{Isometry3, Point2, Point3, Vector3};
 use std::any::Any;
 use std::cell::RefCell;
@@ -20,6 +22,7 @@ pub struct ObjectData {
     wpoints: f32,
     draw_surface: bool,
     cull: bool,
+    parameters: MaterialParameters,
     user_data: Box<dyn Any + 'static>,
 }
 
@@ -59,6 +62,11 @@ impl ObjectData {
         self.cull
     }
 
+    #[inline]
+    pub fn material_parameters(&self) -> &MaterialParameters {
+        &self.parameters
+    }
+
     #[inline]
     pub fn user_data(&self) -> &dyn Any {
         &*self.user_data
@@ -71,7 +79,9 @@ pub struct Object {
 }
 
 impl Object {
-    #[doc(hidden)]
+    /// Creates an object drawing `mesh`.
+    ///
+    /// Meshes of lines or points are drawn with lines or points instead of surfaces.
     pub fn new(
         mesh: Rc<RefCell<Mesh>>,
         r: f32,
@@ -81,14 +91,20 @@ impl Object {
         material: Rc<RefCell<Box<dyn Material + 'static>>>,
     ) -> Object {
         let user_data = ();
+        let (wlines, wpoints) = match mesh.borrow().primitive() {
+            Primitive::Triangles => (0.0, 0.0),
+            Primitive::Lines => (1.0, 0.0),
+            Primitive::Points => (0.0, 3.0),
+        };
         let data = ObjectData {
             color: Point3::new(r, g, b),
             lines_color: None,
             texture,
-            wlines: 0.0,
-            wpoints: 0.0,
-            draw_surface: true,
+            wlines,
+            wpoints,
+            draw_surface: wlines == 0.0 && wpoints == 0.0,
             cull: true,
+            parameters: MaterialParameters::default(),
             material,
             user_data: Box::new(user_data),
         };
@@ -193,7 +209,8 @@ impl Object {
 
     #[inline(always)]
     pub fn modify_vertices<F: FnMut(&mut Vec<Point3<f32>>)>(&mut self, f: &mut F) {
-        let bmesh = self.mesh.borrow_mut();
+        let mut bmesh = self.mesh.borrow_mut();
+        bmesh.invalidate_cached_geometry();
         let _ = bmesh
             .coords()
             .write()
@@ -222,7 +239,8 @@ impl Object {
 
     #[inline(always)]
     pub fn modify_normals<F: FnMut(&mut Vec<Vector3<f32>>)>(&mut self, f: &mut F) {
-        let bmesh = self.mesh.borrow_mut();
+        let mut bmesh = self.mesh.borrow_mut();
+        bmesh.invalidate_cached_geometry();
         let _ = bmesh
             .normals()
             .write()
@@ -246,7 +264,8 @@ impl Object {
 
     #[inline(always)]
     pub fn modify_faces<F: FnMut(&mut Vec<Point3<VertexIndex>>)>(&mut self, f: &mut F) {
-        let bmesh = self.mesh.borrow_mut();
+        let mut bmesh = self.mesh.borrow_mut();
+        bmesh.invalidate_cached_geometry();
         let _ = bmesh
             .faces()
             .write()
@@ -270,7 +289,8 @@ impl Object {
 
     #[inline(always)]
     pub fn modify_uvs<F: FnMut(&mut Vec<Point2<f32>>)>(&mut self, f: &mut F) {
-        let bmesh = self.mesh.borrow_mut();
+        let mut bmesh = self.mesh.borrow_mut();
+        bmesh.invalidate_cached_geometry();
         let _ = bmesh
             .uvs()
             .write()
@@ -299,6 +319,33 @@ impl Object {
         self.data.color.z = b;
     }
 
+    #[inline]
+    pub fn material_parameters(&self) -> &MaterialParameters {
+        &self.data.parameters
+    }
+
+    #[inline]
+    pub fn material_parameters_mut(&mut self) -> &mut MaterialParameters {
+        &mut self.data.parameters
+    }
+
+    #[inline]
+    pub fn set_material_parameters(&mut self, parameters: MaterialParameters) {
+        self.data.parameters = parameters
+    }
+
+    /// Sets the value of the uniform `name` for this object only. Materials that read it, like
+    /// `ShaderMaterial`, use it instead of their default.
+    #[inline]
+    pub fn set_uniform<V: Into<UniformValue>>(&mut self, name: &str, value: V) {
+        self.data.parameters.set_uniform(name, value)
+    }
+
+    #[inline]
+    pub fn remove_uniform(&mut self, name: &str) {
+        self.data.parameters.remove_uniform(name)
+    }
+
     #[inline]
     pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
         let texture = TextureManager::get_global_manager(|tm| tm.add(path, name));
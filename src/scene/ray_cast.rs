@@ -0,0 +1,51 @@
+//! Results of ray casts against the scene graph.
+
+use crate::resource::mesh::Mesh;
+use crate::scene::object::Object;
+use crate::scene::scene_node::SceneNode;
+use nalgebra::{Point3, Vector3};
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::Rc;
+
+/// The closest intersection between a ray and the objects of a scene node hierarchy.
+#[derive(Clone)]
+pub struct PickHit {
+    /// The scene node holding the object that was hit.
+    pub node: SceneNode,
+    /// The object that was hit.
+    pub object: ObjectHandle,
+    /// The mesh of the object that was hit.
+    pub mesh: Rc<RefCell<Mesh>>,
+    /// Index of the hit triangle in the mesh faces.
+    pub triangle: usize,
+    /// Barycentric coordinates of the hit point wrt. the vertices of the hit triangle.
+    pub barycentric: Vector3<f32>,
+    /// The hit point, in world space.
+    pub point: Point3<f32>,
+    /// The normal of the hit triangle, in world space.
+    pub normal: Vector3<f32>,
+    /// The time of impact along the ray direction.
+    pub toi: f32,
+}
+
+/// A handle to the object held by a scene node, valid as long as the node keeps this object.
+#[derive(Clone)]
+pub struct ObjectHandle {
+    pub(crate) node: SceneNode,
+}
+
+impl ObjectHandle {
+    /// Borrows the object.
+    ///
+    /// Panics if its node no longer holds an object.
+    pub fn borrow(&self) -> Ref<'_, Object> {
+        Ref::map(self.node.data(), |data| data.get_object())
+    }
+
+    /// Mutably borrows the object.
+    ///
+    /// Panics if its node no longer holds an object.
+    pub fn borrow_mut(&mut self) -> RefMut<'_, Object> {
+        RefMut::map(self.node.data_mut(), |data| data.get_object_mut())
+    }
+}
@@ -0,0 +1,625 @@
+//! Serializable description of a whole scene, see `Window::save_scene` and
+//! `Window::load_scene`.
+
+use crate::context::context::Texture;
+use crate::light::Light;
+use crate::resource::material_manager::MaterialManager;
+use crate::resource::material_parameters::MaterialParameters;
+use crate::resource::mesh::{Mesh, Primitive};
+use crate::resource::mesh_manager::MeshManager;
+use crate::resource::texture_manager::{ColorSpace, TextureManager};
+use crate::resource::uniform_value::UniformValue;
+use crate::resource::vertex_index::VertexIndex;
+use crate::scene::object::Object;
+use crate::scene::scene_node::SceneNode;
+use nalgebra::{
+    Isometry3, Matrix2, Matrix3, Matrix4, Point2, Point3, Quaternion, Translation3, UnitQuaternion,
+    Vector3,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneDescription {
+    pub background: [f32; 3],
+    pub light: LightDescription,
+    /// The state of the window default camera.
+    pub camera: CameraDescription,
+    pub root: NodeDescription,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LightDescription {
+    Absolute([f32; 3]),
+    StickToCamera,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraDescription {
+    pub eye: [f32; 3],
+    pub at: [f32; 3],
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub translation: [f32; 3],
+    /// The local rotation, as a unit quaternion `[i, j, k, w]`.
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub visible: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object: Option<ObjectDescription>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<NodeDescription>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectDescription {
+    pub mesh: MeshDescription,
+    pub color: [f32; 3],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<TextureDescription>,
+    /// The name of the material in the `MaterialManager`, `None` for the default material.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+    pub lines_width: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines_color: Option<[f32; 3]>,
+    pub points_size: f32,
+    pub surface_rendering: bool,
+    pub backface_culling: bool,
+    #[serde(default, skip_serializing_if = "ParametersDescription::is_default")]
+    pub parameters: ParametersDescription,
+}
+
+/// The material parameters of an object, see `MaterialParameters`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParametersDescription {
+    pub specular_color: [f32; 3],
+    pub shininess: f32,
+    pub emissive: [f32; 3],
+    pub roughness: f32,
+    pub metalness: f32,
+    /// The textures other than the main one, by sampler name. Only textures registered in the
+    /// `TextureManager` are described.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures: BTreeMap<String, TextureDescription>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub uniforms: BTreeMap<String, UniformDescription>,
+}
+
+/// The value of a uniform set with `MaterialParameters::set_uniform`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UniformDescription {
+    Float(f32),
+    Int(i32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    /// The columns of the matrix, one after the other.
+    Mat2([f32; 4]),
+    Mat3([f32; 9]),
+    Mat4([f32; 16]),
+    Texture(TextureDescription),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MeshDescription {
+    /// A mesh registered in the `MeshManager`, e.g., `"cube"`.
+    Named(String),
+    /// The `index`-th mesh of a model file loaded with `SceneNode::add_model`, whose path is
+    /// relative to the directory of the scene file.
+    Asset { path: PathBuf, index: usize },
+    /// The geometry itself, for meshes built or modified by the application.
+    Inline {
+        coords: Vec<[f32; 3]>,
+        faces: Vec<[u32; 3]>,
+        normals: Vec<[f32; 3]>,
+        uvs: Vec<[f32; 2]>,
+        /// How the faces are to be read, see `Primitive`.
+        #[serde(default, skip_serializing_if = "is_triangles")]
+        primitive: Primitive,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextureDescription {
+    /// The name of the texture in the `TextureManager`.
+    pub name: String,
+    /// The file the texture was loaded from, if any, relative to the directory of the scene file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl From<&Light> for LightDescription {
+    fn from(light: &Light) -> LightDescription {
+        match *light {
+            Light::Absolute(p) => LightDescription::Absolute(p.coords.into()),
+            Light::StickToCamera => LightDescription::StickToCamera,
+        }
+    }
+}
+
+impl From<LightDescription> for Light {
+    fn from(light: LightDescription) -> Light {
+        match light {
+            LightDescription::Absolute(p) => Light::Absolute(p.into()),
+            LightDescription::StickToCamera => Light::StickToCamera,
+        }
+    }
+}
+
+impl NodeDescription {
+    /// Describes a node and all its descendants.
+    ///
+    /// File paths are written relative to `dir`, the directory of the scene file.
+    pub fn from_node(node: &SceneNode, dir: &Path) -> NodeDescription {
+        let data = node.data();
+        let transform = data.local_transformation();
+        let mut tags: Vec<String> = data.tags().iter().cloned().collect();
+        tags.sort();
+
+        NodeDescription {
+            name: data.name().map(|n| n.to_string()),
+            translation: transform.translation.vector.into(),
+            rotation: transform.rotation.coords.into(),
+            scale: data.local_scale().into(),
+            visible: data.is_visible(),
+            tags,
+            object: data
+                .object()
+                .map(|o| ObjectDescription::from_object(o, dir)),
+            children: data
+                .children()
+                .iter()
+                .map(|c| NodeDescription::from_node(c, dir))
+                .collect(),
+        }
+    }
+
+    fn local_transformation(&self) -> Isometry3<f32> {
+        let [i, j, k, w] = self.rotation;
+        let rotation = UnitQuaternion::from_quaternion(Quaternion::new(w, i, j, k));
+        Isometry3::from_parts(
+            Translation3::from(Vector3::from(self.translation)),
+            rotation,
+        )
+    }
+
+    /// Creates the described node and its descendants, and adds it to `parent`.
+    ///
+    /// The paths of the description are relative to `dir`, the directory of the scene file.
+    pub fn instantiate(&self, parent: &mut SceneNode, dir: &Path) -> Result<SceneNode, String> {
+        self.do_instantiate(parent, dir, &mut HashMap::new())
+    }
+
+    /// Gives `node` the transform, name, visibility and tags of this description, and replaces
+    /// its children with the described ones.
+    ///
+    /// This is meant for nodes that cannot be replaced, like the root of a window scene. Any
+    /// object of this description is added as a new child of `node`. The children are built
+    /// before `node` is modified, so `node` is left untouched if this fails. The paths of the
+    /// description are relative to `dir`.
+    pub fn apply_to(&self, node: &mut SceneNode, dir: &Path) -> Result<(), String> {
+        let mut assets = HashMap::new();
+        let mut staged = SceneNode::new_empty();
+
+        if let Some(ref object) = self.object {
+            let object = object.to_object(dir, &mut assets)?;
+            let _ = staged.add_object(Vector3::from_element(1.0), Isometry3::identity(), object);
+        }
+
+        for child in self.children.iter() {
+            let _ = child.do_instantiate(&mut staged, dir, &mut assets)?;
+        }
+
+        for mut child in node.children() {
+            child.unlink();
+        }
+
+        for mut child in staged.children() {
+            child.unlink();
+            node.add_child(child);
+        }
+
+        node.set_local_transformation(self.local_transformation());
+        node.set_local_scale(self.scale[0], self.scale[1], self.scale[2]);
+        node.set_visible(self.visible);
+
+        if let Some(ref name) = self.name {
+            node.set_name(name);
+        }
+
+        node.clear_tags();
+
+        for tag in self.tags.iter() {
+            node.add_tag(tag);
+        }
+
+        Ok(())
+    }
+
+    fn do_instantiate(
+        &self,
+        parent: &mut SceneNode,
+        dir: &Path,
+        assets: &mut AssetCache,
+    ) -> Result<SceneNode, String> {
+        let object = match self.object {
+            Some(ref object) => Some(object.to_object(dir, assets)?),
+            None => None,
+        };
+
+        let mut node = SceneNode::new(
+            Vector3::from(self.scale),
+            self.local_transformation(),
+            object,
+        );
+
+        if let Some(ref name) = self.name {
+            node.set_name(name);
+        }
+
+        for tag in self.tags.iter() {
+            node.add_tag(tag);
+        }
+
+        node.set_visible(self.visible);
+        parent.add_child(node.clone());
+
+        for child in self.children.iter() {
+            let _ = child.do_instantiate(&mut node, dir, assets)?;
+        }
+
+        Ok(node)
+    }
+}
+
+// The meshes of the model files already loaded, so that nodes referencing the same file share
+// their meshes.
+type AssetCache = HashMap<PathBuf, Vec<Rc<RefCell<Mesh>>>>;
+
+impl ObjectDescription {
+    pub fn from_object(object: &Object, dir: &Path) -> ObjectDescription {
+        let data = object.data();
+
+        let texture = TextureManager::get_global_manager(|tm| {
+            if Rc::ptr_eq(data.texture(), &tm.get_default()) {
+                return None;
+            }
+
+            TextureDescription::from_texture(tm, data.texture(), dir)
+        });
+
+        let material = MaterialManager::get_global_manager(|mm| {
+            if Rc::ptr_eq(&object.material(), &mm.get_default()) {
+                None
+            } else {
+                mm.name_of(&object.material())
+            }
+        });
+
+        ObjectDescription {
+            mesh: MeshDescription::from_object(object, dir),
+            color: data.color().coords.into(),
+            texture,
+            material,
+            lines_width: data.lines_width(),
+            lines_color: data.lines_color().map(|c| c.coords.into()),
+            points_size: data.points_size(),
+            surface_rendering: data.surface_rendering_active(),
+            backface_culling: data.backface_culling_enabled(),
+            parameters: ParametersDescription::from_parameters(object.material_parameters(), dir),
+        }
+    }
+
+    fn to_object(&self, dir: &Path, assets: &mut AssetCache) -> Result<Object, String> {
+        let mesh = self.mesh.to_mesh(dir, assets)?;
+
+        let texture = TextureManager::get_global_manager(|tm| match self.texture {
+            None => Ok(tm.get_default()),
+            Some(ref texture) => texture.to_texture(tm, dir),
+        })?;
+
+        let material = MaterialManager::get_global_manager(|mm| match self.material {
+            None => Ok(mm.get_default()),
+            Some(ref name) => mm
+                .get(name)
+                .ok_or_else(|| format!("Material not found: {}", name)),
+        })?;
+
+        let [r, g, b] = self.color;
+        let mut object = Object::new(mesh, r, g, b, texture, material);
+        object.set_lines_width(self.lines_width);
+        object.set_lines_color(self.lines_color.map(Point3::from));
+        object.set_points_size(self.points_size);
+        object.set_surface_rendering_activation(self.surface_rendering);
+        object.enable_backface_culling(self.backface_culling);
+        object.set_material_parameters(self.parameters.to_parameters(dir)?);
+
+        Ok(object)
+    }
+}
+
+impl Default for ParametersDescription {
+    fn default() -> ParametersDescription {
+        let parameters = MaterialParameters::default();
+
+        ParametersDescription {
+            specular_color: parameters.specular_color.coords.into(),
+            shininess: parameters.shininess,
+            emissive: parameters.emissive.coords.into(),
+            roughness: parameters.roughness,
+            metalness: parameters.metalness,
+            textures: BTreeMap::new(),
+            uniforms: BTreeMap::new(),
+        }
+    }
+}
+
+impl ParametersDescription {
+    pub fn from_parameters(parameters: &MaterialParameters, dir: &Path) -> ParametersDescription {
+        TextureManager::get_global_manager(|tm| ParametersDescription {
+            specular_color: parameters.specular_color.coords.into(),
+            shininess: parameters.shininess,
+            emissive: parameters.emissive.coords.into(),
+            roughness: parameters.roughness,
+            metalness: parameters.metalness,
+            textures: parameters
+                .textures()
+                .iter()
+                .filter_map(|(name, t)| {
+                    Some((name.clone(), TextureDescription::from_texture(tm, t, dir)?))
+                })
+                .collect(),
+            uniforms: parameters
+                .uniforms()
+                .iter()
+                .filter_map(|(name, v)| {
+                    Some((name.clone(), UniformDescription::from_value(tm, v, dir)?))
+                })
+                .collect(),
+        })
+    }
+
+    pub fn to_parameters(&self, dir: &Path) -> Result<MaterialParameters, String> {
+        let mut parameters = MaterialParameters::default();
+        parameters.specular_color = Point3::from(self.specular_color);
+        parameters.shininess = self.shininess;
+        parameters.emissive = Point3::from(self.emissive);
+        parameters.roughness = self.roughness;
+        parameters.metalness = self.metalness;
+
+        TextureManager::get_global_manager(|tm| -> Result<(), String> {
+            for (name, texture) in self.textures.iter() {
+                parameters.set_texture(name, texture.to_texture(tm, dir)?);
+            }
+
+            for (name, value) in self.uniforms.iter() {
+                parameters.set_uniform(name, value.to_value(tm, dir)?);
+            }
+
+            Ok(())
+        })?;
+
+        Ok(parameters)
+    }
+
+    fn is_default(&self) -> bool {
+        *self == ParametersDescription::default()
+    }
+}
+
+impl UniformDescription {
+    // `None` for textures not registered in the texture manager.
+    fn from_value(
+        tm: &TextureManager,
+        value: &UniformValue,
+        dir: &Path,
+    ) -> Option<UniformDescription> {
+        let description = match *value {
+            UniformValue::Float(v) => UniformDescription::Float(v),
+            UniformValue::Int(v) => UniformDescription::Int(v),
+            UniformValue::Vec2(v) => UniformDescription::Vec2(v.into()),
+            UniformValue::Vec3(v) => UniformDescription::Vec3(v.into()),
+            UniformValue::Vec4(v) => UniformDescription::Vec4(v.into()),
+            UniformValue::Mat2(m) => UniformDescription::Mat2(columns(m.as_slice())),
+            UniformValue::Mat3(m) => UniformDescription::Mat3(columns(m.as_slice())),
+            UniformValue::Mat4(m) => UniformDescription::Mat4(columns(m.as_slice())),
+            UniformValue::Texture(ref texture) => {
+                UniformDescription::Texture(TextureDescription::from_texture(tm, texture, dir)?)
+            }
+        };
+
+        Some(description)
+    }
+
+    fn to_value(&self, tm: &mut TextureManager, dir: &Path) -> Result<UniformValue, String> {
+        let value = match *self {
+            UniformDescription::Float(v) => UniformValue::Float(v),
+            UniformDescription::Int(v) => UniformValue::Int(v),
+            UniformDescription::Vec2(v) => UniformValue::Vec2(v.into()),
+            UniformDescription::Vec3(v) => UniformValue::Vec3(v.into()),
+            UniformDescription::Vec4(v) => UniformValue::Vec4(v.into()),
+            UniformDescription::Mat2(ref m) => UniformValue::Mat2(Matrix2::from_column_slice(m)),
+            UniformDescription::Mat3(ref m) => UniformValue::Mat3(Matrix3::from_column_slice(m)),
+            UniformDescription::Mat4(ref m) => UniformValue::Mat4(Matrix4::from_column_slice(m)),
+            UniformDescription::Texture(ref texture) => {
+                UniformValue::Texture(texture.to_texture(tm, dir)?)
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+impl TextureDescription {
+    // Describes a texture by its name in the texture manager, `None` if it is not registered.
+    fn from_texture(
+        tm: &TextureManager,
+        texture: &Rc<Texture>,
+        dir: &Path,
+    ) -> Option<TextureDescription> {
+        tm.name_of(texture).map(|name| TextureDescription {
+            path: tm.path(&name).map(|p| relative_path(p, dir)),
+            name,
+        })
+    }
+
+    // The texture registered with this name, loaded from its file if it is not registered yet.
+    fn to_texture(&self, tm: &mut TextureManager, dir: &Path) -> Result<Rc<Texture>, String> {
+        match (tm.get(&self.name), &self.path) {
+            (Some(t), _) => Ok(t),
+            (None, Some(path)) => {
+                let sampler = tm.default_sampler();
+                tm.add_with_options(&dir.join(path), &self.name, ColorSpace::Linear, sampler)
+            }
+            (None, None) => Err(format!("Texture not found: {}", self.name)),
+        }
+    }
+}
+
+// `path` relative to `dir`, e.g., `../textures/wood.png`, so that scene files can be moved along
+// with their assets. Paths that cannot be made relative, e.g., on another drive, are kept whole.
+fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    let absolute = |p: &Path| fs::canonicalize(p).or_else(|_| std::path::absolute(p));
+    let (path, dir) = match (absolute(path), absolute(dir)) {
+        (Ok(path), Ok(dir)) => (path, dir),
+        _ => return path.to_path_buf(),
+    };
+
+    let common = path
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if common == 0 {
+        return path;
+    }
+
+    let mut relative = PathBuf::new();
+
+    for _ in dir.components().skip(common) {
+        relative.push("..");
+    }
+
+    relative.extend(path.components().skip(common));
+    relative
+}
+
+fn is_triangles(primitive: &Primitive) -> bool {
+    *primitive == Primitive::Triangles
+}
+
+fn columns<const N: usize>(values: &[f32]) -> [f32; N] {
+    let mut columns = [0.0; N];
+    columns.copy_from_slice(values);
+    columns
+}
+
+impl MeshDescription {
+    /// References the mesh of `object` by name or by asset path when possible, and copies its
+    /// geometry otherwise.
+    pub fn from_object(object: &Object, dir: &Path) -> MeshDescription {
+        if let Some(name) = MeshManager::get_global_manager(|mm| mm.name_of(object.mesh())) {
+            return MeshDescription::Named(name);
+        }
+
+        if let Some((path, index)) = object.mesh().borrow().asset_source() {
+            return MeshDescription::Asset {
+                path: relative_path(path, dir),
+                index,
+            };
+        }
+
+        let mut coords = Vec::new();
+        let mut faces = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+
+        object.read_vertices(&mut |vs| coords = vs.iter().map(|v| v.coords.into()).collect());
+        object.read_faces(&mut |fs| {
+            faces = fs
+                .iter()
+                .map(|f| [f.x as u32, f.y as u32, f.z as u32])
+                .collect()
+        });
+        object.read_normals(&mut |ns| normals = ns.iter().map(|n| (*n).into()).collect());
+        object.read_uvs(&mut |us| uvs = us.iter().map(|u| u.coords.into()).collect());
+
+        MeshDescription::Inline {
+            coords,
+            faces,
+            normals,
+            uvs,
+            primitive: object.mesh().borrow().primitive(),
+        }
+    }
+
+    fn to_mesh(&self, dir: &Path, assets: &mut AssetCache) -> Result<Rc<RefCell<Mesh>>, String> {
+        match *self {
+            MeshDescription::Named(ref name) => MeshManager::get_global_manager(|mm| mm.get(name))
+                .ok_or_else(|| format!("Mesh not found: {}", name)),
+            MeshDescription::Asset { ref path, index } => {
+                let path = dir.join(path);
+
+                if !assets.contains_key(&path) {
+                    let mut meshes = Vec::new();
+                    let node = SceneNode::new_empty().add_model(&path)?;
+                    node.data()
+                        .apply_to_objects(&mut |o| meshes.push(o.mesh().clone()));
+                    let _ = assets.insert(path.clone(), meshes);
+                }
+
+                assets[&path].get(index).cloned().ok_or_else(|| {
+                    format!("{}: there is no mesh at index {}", path.display(), index)
+                })
+            }
+            MeshDescription::Inline {
+                ref coords,
+                ref faces,
+                ref normals,
+                ref uvs,
+                primitive,
+            } => {
+                if faces
+                    .iter()
+                    .flatten()
+                    .any(|i| *i as usize >= coords.len() || *i > VertexIndex::MAX as u32)
+                {
+                    return Err("Inline mesh vertex index out of bounds".to_string());
+                }
+
+                let coords: Vec<Point3<f32>> = coords.iter().map(|c| Point3::from(*c)).collect();
+                let faces = faces
+                    .iter()
+                    .map(|f| {
+                        Point3::new(
+                            f[0] as VertexIndex,
+                            f[1] as VertexIndex,
+                            f[2] as VertexIndex,
+                        )
+                    })
+                    .collect();
+                // Missing normals are recomputed, missing uvs are set to zero.
+                let normals = Some(normals.iter().map(|n| Vector3::from(*n)).collect())
+                    .filter(|ns: &Vec<_>| ns.len() == coords.len());
+                let uvs = Some(uvs.iter().map(|u| Point2::from(*u)).collect())
+                    .filter(|us: &Vec<_>| us.len() == coords.len());
+
+                let mut mesh = Mesh::new(coords, faces, normals, uvs, false);
+                mesh.set_primitive(primitive);
+
+                Ok(Rc::new(RefCell::new(mesh)))
+            }
+        }
+    }
+}
@@ -1,18 +1,30 @@
 use crate::camera::camera::Camera;
+use crate::camera::frustum::Frustum;
 use crate::context::context::Texture;
 use crate::light::Light;
+use crate::resource::hot_reload::{self, ModelSource};
 use crate::resource::material::Material;
 use crate::resource::material_manager::MaterialManager;
+use crate::resource::material_parameters::MaterialParameters;
 use crate::resource::mesh::Mesh;
 use crate::resource::mesh_manager::MeshManager;
 use crate::resource::texture_manager::TextureManager;
+use crate::resource::uniform_value::UniformValue;
 use crate::resource::vertex_index::VertexIndex;
+use crate::scene::highlight::Highlight;
 use crate::scene::object::Object;
-use crate::loader::glb;
+use crate::scene::ray_cast::{ObjectHandle, PickHit};
+use crate::loader::mtl::MtlMaterial;
+use crate::loader::model::{self, ModelData, ModelNode};
+use crate::loader::{glb, obj};
 use nalgebra::{self as na, Isometry3, Point2, Point3, Translation3, UnitQuaternion, Vector3};
+use ncollide3d::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
 use ncollide3d::procedural;
 use ncollide3d::procedural::TriMesh;
+use ncollide3d::query::Ray;
 use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::io::Result as IoResult;
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -30,6 +42,11 @@ pub struct SceneNodeData {
     object: Option<Object>,
     parent: Option<Weak<RefCell<SceneNodeData>>>,
     fixed: bool,
+    name: Option<String>,
+    tags: HashSet<String>,
+    highlight: Option<Highlight>,
+    // World bounds of `object`, with the mesh geometry revision they were computed from.
+    world_bounds: Option<(u64, AABB<f32>, BoundingSphere<f32>)>,
 }
 
 #[derive(Clone)]
@@ -37,6 +54,21 @@ pub struct SceneNode {
     data: Rc<RefCell<SceneNodeData>>,
 }
 
+/// Depth-first iterator over the descendants of a scene node.
+pub struct Descendants {
+    stack: Vec<SceneNode>,
+}
+
+impl Iterator for Descendants {
+    type Item = SceneNode;
+
+    fn next(&mut self) -> Option<SceneNode> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.data().children.iter().rev().cloned());
+        Some(node)
+    }
+}
+
 impl SceneNodeData {
     fn set_parent(&mut self, parent: Weak<RefCell<SceneNodeData>>) {
         self.parent = Some(parent);
@@ -72,11 +104,27 @@ impl SceneNodeData {
 
     pub fn render(&mut self, pass: usize, camera: &mut dyn Camera, light: &Light) {
         if self.visible {
+            let frustum = camera.frustum();
+
             if self.fixed {
                 let camera_transform = camera.view_transform();
-                self.do_render(&camera_transform, &Vector3::from_element(1.0), pass, camera, light);
+                self.do_render(
+                    &camera_transform,
+                    &Vector3::from_element(1.0),
+                    pass,
+                    camera,
+                    light,
+                    &frustum,
+                );
             } else {
-                self.do_render(&na::one(), &Vector3::from_element(1.0), pass, camera, light);
+                self.do_render(
+                    &na::one(),
+                    &Vector3::from_element(1.0),
+                    pass,
+                    camera,
+                    light,
+                    &frustum,
+                );
             }
         }
     }
@@ -88,6 +136,7 @@ impl SceneNodeData {
         pass: usize,
         camera: &mut dyn Camera,
         light: &Light,
+        frustum: &Frustum,
     ) {
         if !self.up_to_date {
             self.up_to_date = true;
@@ -95,14 +144,24 @@ impl SceneNodeData {
             self.world_scale = scale.component_mul(&self.local_scale);
         }
 
+        // Objects without known bounds are always rendered.
+        let in_frustum = match self.object_world_bounds() {
+            Some((aabb, sphere)) => {
+                frustum.intersects_sphere(&sphere) && frustum.intersects_aabb(&aabb)
+            }
+            None => true,
+        };
+
         if let Some(ref o) = self.object {
-            o.render(
-                &self.world_transform,
-                &self.world_scale,
-                pass,
-                camera,
-                light,
-            )
+            if in_frustum {
+                o.render(
+                    &self.world_transform,
+                    &self.world_scale,
+                    pass,
+                    camera,
+                    light,
+                )
+            }
         }
 
         for c in self.children.iter_mut() {
@@ -114,11 +173,164 @@ impl SceneNodeData {
                     pass,
                     camera,
                     light,
+                    frustum,
                 )
             }
         }
     }
 
+    /// The world-space bounds of the object of this node, assuming its world transform is up
+    /// to date.
+    fn object_world_bounds(&mut self) -> Option<(AABB<f32>, BoundingSphere<f32>)> {
+        let object = self.object.as_ref()?;
+        let mut mesh = object.mesh().borrow_mut();
+        let revision = mesh.geometry_revision();
+
+        if let Some((bounds_revision, aabb, sphere)) = self.world_bounds {
+            if bounds_revision == revision {
+                return Some((aabb, sphere));
+            }
+        }
+
+        let local_aabb = mesh.local_aabb()?;
+        let local_sphere = mesh.local_bounding_sphere()?;
+        let scale = self.world_scale;
+
+        let mins = local_aabb.mins.coords.component_mul(&scale);
+        let maxs = local_aabb.maxs.coords.component_mul(&scale);
+        let aabb = AABB::new(mins.inf(&maxs).into(), mins.sup(&maxs).into())
+            .transform_by(&self.world_transform);
+
+        let center = Point3::from(local_sphere.center().coords.component_mul(&scale));
+        let sphere = BoundingSphere::new(
+            self.world_transform * center,
+            local_sphere.radius() * scale.abs().max(),
+        );
+
+        self.world_bounds = Some((revision, aabb, sphere));
+        Some((aabb, sphere))
+    }
+
+    /// The world-space bounds of the objects of this node and all its descendants, assuming
+    /// the world transform of this node is up to date.
+    fn subtree_world_bounds(&mut self) -> Option<(AABB<f32>, BoundingSphere<f32>)> {
+        let mut bounds = self.object_world_bounds();
+
+        for c in self.children.iter() {
+            let mut dc = c.data.borrow_mut();
+
+            if !dc.up_to_date {
+                dc.up_to_date = true;
+                dc.world_transform = self.world_transform * dc.local_transform;
+                dc.world_scale = self.world_scale.component_mul(&dc.local_scale);
+            }
+
+            if let Some(child_bounds) = dc.subtree_world_bounds() {
+                bounds = match bounds {
+                    Some(b) => Some((b.0.merged(&child_bounds.0), b.1.merged(&child_bounds.1))),
+                    None => Some(child_bounds),
+                };
+            }
+        }
+
+        bounds
+    }
+
+    /// The world-space axis-aligned bounding box of the objects of this node and its descendants.
+    ///
+    /// Returns `None` if none of these objects have vertices on RAM.
+    pub fn world_aabb(&mut self) -> Option<AABB<f32>> {
+        self.update();
+        self.subtree_world_bounds().map(|b| b.0)
+    }
+
+    /// The world-space bounding sphere of the objects of this node and its descendants.
+    ///
+    /// Returns `None` if none of these objects have vertices on RAM.
+    pub fn world_bounding_sphere(&mut self) -> Option<BoundingSphere<f32>> {
+        self.update();
+        self.subtree_world_bounds().map(|b| b.1)
+    }
+
+    fn apply_to_rendered_objects_with_transform<F>(
+        &self,
+        node: &SceneNode,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        f: &mut F,
+    ) where
+        F: FnMut(&SceneNode, &Object, &Isometry3<f32>, &Vector3<f32>),
+    {
+        if !self.visible || self.fixed {
+            return;
+        }
+
+        if let Some(ref o) = self.object {
+            f(node, o, transform, scale)
+        }
+
+        for c in self.children.iter() {
+            let dc = c.data();
+            dc.apply_to_rendered_objects_with_transform(
+                c,
+                &(transform * dc.local_transform),
+                &scale.component_mul(&dc.local_scale),
+                f,
+            );
+        }
+    }
+
+    #[inline]
+    pub fn children(&self) -> &[SceneNode] {
+        &self.children[..]
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    #[inline]
+    pub fn tags(&self) -> &HashSet<String> {
+        &self.tags
+    }
+
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    #[inline]
+    pub fn add_tag(&mut self, tag: &str) {
+        let _ = self.tags.insert(tag.to_string());
+    }
+
+    #[inline]
+    pub fn remove_tag(&mut self, tag: &str) {
+        let _ = self.tags.remove(tag);
+    }
+
+    #[inline]
+    pub fn clear_tags(&mut self) {
+        self.tags.clear();
+    }
+
+    /// The outline drawn around this node and its descendants, if any.
+    #[inline]
+    pub fn highlight(&self) -> Option<Highlight> {
+        self.highlight
+    }
+
+    #[inline]
+    pub fn set_highlight(&mut self, highlight: Option<Highlight>) {
+        self.highlight = highlight
+    }
+
     #[inline]
     pub fn object(&self) -> Option<&Object> {
         self.object.as_ref()
@@ -272,6 +484,43 @@ impl SceneNodeData {
         self.apply_to_objects_mut(&mut |o| o.set_texture(texture.clone()))
     }
 
+    /// Sets the material parameters of the objects of this node and its children.
+    pub fn set_material_parameters(&mut self, parameters: MaterialParameters) {
+        self.apply_to_objects_mut(&mut |o| o.set_material_parameters(parameters.clone()))
+    }
+
+    #[inline]
+    pub fn modify_material_parameters<F: FnMut(&mut MaterialParameters)>(&mut self, f: &mut F) {
+        self.apply_to_objects_mut(&mut |o| f(o.material_parameters_mut()))
+    }
+
+    #[inline]
+    pub fn set_specular_color(&mut self, r: f32, g: f32, b: f32) {
+        self.modify_material_parameters(&mut |p| p.specular_color = Point3::new(r, g, b))
+    }
+
+    #[inline]
+    pub fn set_shininess(&mut self, shininess: f32) {
+        self.modify_material_parameters(&mut |p| p.shininess = shininess)
+    }
+
+    #[inline]
+    pub fn set_emissive(&mut self, r: f32, g: f32, b: f32) {
+        self.modify_material_parameters(&mut |p| p.emissive = Point3::new(r, g, b))
+    }
+
+    /// Sets the value of the uniform `name` for the objects of this node and its children.
+    #[inline]
+    pub fn set_uniform<V: Into<UniformValue>>(&mut self, name: &str, value: V) {
+        let value = value.into();
+        self.apply_to_objects_mut(&mut |o| o.set_uniform(name, value.clone()))
+    }
+
+    #[inline]
+    pub fn remove_uniform(&mut self, name: &str) {
+        self.apply_to_objects_mut(&mut |o| o.remove_uniform(name))
+    }
+
     #[inline]
     pub fn apply_to_objects_mut<F: FnMut(&mut Object)>(&mut self, f: &mut F) {
         if let Some(ref mut o) = self.object {
@@ -338,7 +587,17 @@ impl SceneNodeData {
             let mself: &mut SceneNodeData = mem::transmute(self);
             mself.update();
         }
-        self.local_transform.inverse()
+        self.world_transform.inverse()
+    }
+
+    #[inline]
+    #[allow(mutable_transmutes)]
+    pub fn world_scale(&self) -> Vector3<f32> {
+        unsafe {
+            let mself: &mut SceneNodeData = mem::transmute(self);
+            mself.update();
+        }
+        self.world_scale
     }
 
     #[inline]
@@ -423,6 +682,7 @@ impl SceneNodeData {
 
     fn invalidate(&mut self) {
         self.up_to_date = false;
+        self.world_bounds = None;
 
         for c in self.children.iter_mut() {
             let mut dm = c.data_mut();
@@ -440,8 +700,8 @@ impl SceneNodeData {
                     if let Some(dp) = p.upgrade() {
                         let mut dp = dp.borrow_mut();
                         dp.update();
-                        self.world_transform = self.local_transform * dp.world_transform;
-                        self.world_scale = self.local_scale.component_mul(&dp.local_scale);
+                        self.world_transform = dp.world_transform * self.local_transform;
+                        self.world_scale = dp.world_scale.component_mul(&self.local_scale);
                         self.up_to_date = true;
                         return;
                     }
@@ -489,6 +749,10 @@ impl SceneNode {
             object,
             parent: None,
             fixed: false,
+            name: None,
+            tags: HashSet::new(),
+            highlight: None,
+            world_bounds: None,
         };
 
         SceneNode {
@@ -508,6 +772,171 @@ impl SceneNode {
         self.data().is_fixed()
     }
 
+    pub fn name(&self) -> Option<String> {
+        self.data().name().map(|n| n.to_string())
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.data_mut().set_name(Some(name.to_string()));
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.data().has_tag(tag)
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        self.data_mut().add_tag(tag)
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.data_mut().remove_tag(tag)
+    }
+
+    pub fn clear_tags(&mut self) {
+        self.data_mut().clear_tags()
+    }
+
+    /// The world-space axis-aligned bounding box of the objects of this node and its descendants.
+    ///
+    /// Returns `None` if none of these objects have vertices on RAM.
+    pub fn world_aabb(&self) -> Option<AABB<f32>> {
+        self.data.borrow_mut().world_aabb()
+    }
+
+    /// The world-space bounding sphere of the objects of this node and its descendants.
+    ///
+    /// Returns `None` if none of these objects have vertices on RAM.
+    pub fn world_bounding_sphere(&self) -> Option<BoundingSphere<f32>> {
+        self.data.borrow_mut().world_bounding_sphere()
+    }
+
+    pub fn highlight(&self) -> Option<Highlight> {
+        self.data().highlight()
+    }
+
+    /// Sets the outline drawn around the silhouette of this node and its descendants.
+    ///
+    /// Use `None` to remove the outline.
+    pub fn set_highlight(&mut self, highlight: Option<Highlight>) {
+        self.data_mut().set_highlight(highlight)
+    }
+
+    /// The highlight of this node, or of its closest highlighted ancestor.
+    pub fn inherited_highlight(&self) -> Option<Highlight> {
+        let mut curr = Some(self.clone());
+
+        while let Some(node) = curr {
+            if let Some(highlight) = node.highlight() {
+                return Some(highlight);
+            }
+
+            curr = node.parent();
+        }
+
+        None
+    }
+
+    pub fn children(&self) -> Vec<SceneNode> {
+        self.data().children.clone()
+    }
+
+    pub fn parent(&self) -> Option<SceneNode> {
+        self.data()
+            .parent
+            .as_ref()
+            .and_then(|p| p.upgrade())
+            .map(|data| SceneNode { data })
+    }
+
+    /// Iterates, depth-first, over every node below this one (excluding this node).
+    pub fn descendants(&self) -> Descendants {
+        Descendants {
+            stack: self.data().children.iter().rev().cloned().collect(),
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<SceneNode> {
+        self.descendants().find(|n| n.data().name() == Some(name))
+    }
+
+    /// Finds a node by following a `/`-separated list of child names, starting at this node.
+    ///
+    /// Empty segments are skipped, so `"car//wheel"` and `"/car/wheel"` are the same as
+    /// `"car/wheel"`.
+    pub fn find_path(&self, path: &str) -> Option<SceneNode> {
+        let mut curr = self.clone();
+
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            let next = curr
+                .data()
+                .children
+                .iter()
+                .find(|c| c.data().name() == Some(segment))
+                .cloned()?;
+            curr = next;
+        }
+
+        Some(curr)
+    }
+
+    /// Returns the closest intersection between `ray` (in world space) and the visible objects of
+    /// this subtree.
+    pub fn cast_ray(&self, ray: &Ray<f32>) -> Option<PickHit> {
+        let mut best: Option<PickHit> = None;
+
+        self.apply_to_rendered_objects(&mut |node, o, transform, scale| {
+            let max_toi = best.as_ref().map_or(f32::MAX, |h| h.toi);
+            // Map the ray to the unscaled mesh space without normalizing its direction, so the
+            // time of impact stays the same in both spaces.
+            let origin = transform.inverse_transform_point(&ray.origin);
+            let local_ray = Ray::new(
+                Point3::from(origin.coords.component_div(scale)),
+                transform.inverse_transform_vector(&ray.dir).component_div(scale),
+            );
+
+            if let Some(hit) = o.mesh().borrow_mut().cast_ray(&local_ray, max_toi) {
+                let normal = transform.transform_vector(&hit.normal.component_div(scale));
+
+                best = Some(PickHit {
+                    node: node.clone(),
+                    object: ObjectHandle { node: node.clone() },
+                    mesh: o.mesh().clone(),
+                    triangle: hit.face,
+                    barycentric: hit.barycentric,
+                    point: ray.point_at(hit.toi),
+                    normal: normal.normalize(),
+                    toi: hit.toi,
+                });
+            }
+        });
+
+        best
+    }
+
+    /// Applies `f` to every object of this subtree that would be rendered, i.e., visible and not
+    /// fixed to the camera, together with its world transformation and scale.
+    pub fn apply_to_rendered_objects<F>(&self, f: &mut F)
+    where
+        F: FnMut(&SceneNode, &Object, &Isometry3<f32>, &Vector3<f32>),
+    {
+        let data = self.data();
+
+        data.apply_to_rendered_objects_with_transform(
+            self,
+            &data.world_transformation(),
+            &data.world_scale(),
+            f,
+        );
+    }
+
+    pub fn find_all<F: FnMut(&SceneNode) -> bool>(&self, mut f: F) -> Vec<SceneNode> {
+        self.descendants().filter(|n| f(n)).collect()
+    }
+
+    pub fn find_with_tag(&self, tag: &str) -> Vec<SceneNode> {
+        self.find_all(|n| n.has_tag(tag))
+    }
+
     pub fn unlink(&mut self) {
         let self_self = self.clone();
         self.data_mut().remove_from_parent(&self_self);
@@ -634,12 +1063,47 @@ impl SceneNode {
     }
 
     pub fn add_obj(&mut self, path: &Path, mtl_dir: &Path, scale: Vector3<f32>, position: Vector3<f32>) -> SceneNode {
+        self.try_add_obj(path, mtl_dir, scale, position).unwrap()
+    }
+
+    /// Like `add_obj`, but returns an error instead of panicking if the file cannot be loaded.
+    ///
+    /// Each `o` object made of several groups or materials gets a node of its own. Polylines and
+    /// points are added as objects drawn with lines and points instead of surfaces.
+    pub fn try_add_obj(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: Vector3<f32>,
+        position: Vector3<f32>,
+    ) -> Result<SceneNode, String> {
+        let basename = path.to_str().ok_or("The path is not valid unicode")?;
+        let objs = MeshManager::load_obj(path, mtl_dir, basename).map_err(|e| e.to_string())?;
+
+        self.add_obj_meshes(path, mtl_dir, objs, scale, position)
+    }
+
+    // Adds the meshes read from the OBJ file at `path`, see `try_add_obj`.
+    fn add_obj_meshes(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        objs: Vec<obj::ObjMesh>,
+        scale: Vector3<f32>,
+        position: Vector3<f32>,
+    ) -> Result<SceneNode, String> {
         let tex = TextureManager::get_global_manager(|tm: &mut TextureManager| tm.get_default());
         let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
 
         let position = Translation3::from(position);
-    
-        let result = MeshManager::load_obj(path, mtl_dir, path.to_str().unwrap()).map(|objs| {
+        let basename = path.to_str().ok_or("The path is not valid unicode")?;
+        let file_name = file_stem(path);
+
+        if objs.is_empty() {
+            return Err("There was nothing on this obj file.".to_string());
+        }
+
+        let result = {
             let mut root;
     
             let self_root = objs.len() == 1;
@@ -651,17 +1115,30 @@ impl SceneNode {
                 child_scale = scale;
             } else {
                 root = SceneNode::new(scale, local_transform, None);
+                root.set_name(&file_name);
                 self.add_child(root.clone());
                 child_scale = Vector3::from_element(1.0);
             }
     
-            for (_, mesh, mtl) in objs.into_iter() {
+            // Objects made of several meshes get a node of their own, created on their first mesh.
+            let mut object_sizes: HashMap<String, usize> = HashMap::new();
+            let mut object_nodes: HashMap<String, SceneNode> = HashMap::new();
+            let mut watched = Vec::new();
+
+            for name in objs.iter().filter_map(|o| o.object.as_ref()) {
+                *object_sizes.entry(name.clone()).or_insert(0) += 1;
+            }
+
+            for obj in objs.into_iter() {
+                let mesh = Rc::new(RefCell::new(obj.mesh));
+                watched.push(Rc::downgrade(&mesh));
                 let mut object = Object::new(mesh, 1.0, 1.0, 1.0, tex.clone(), mat.clone());
     
-                match mtl {
+                match obj.material {
                     None => {}
                     Some(mtl) => {
                         object.set_color(mtl.diffuse.x, mtl.diffuse.y, mtl.diffuse.z);
+                        object.set_material_parameters(mtl_parameters(&mtl));
     
                         for t in mtl.diffuse_texture.iter() {
                             let mut tpath = PathBuf::new();
@@ -678,84 +1155,401 @@ impl SceneNode {
                         }
                     }
                 }
-    
-                let _ = root.add_object(child_scale, local_transform, object);
+
+                let group_name = model::obj_group_name(&obj.group, basename);
+                let (mut parent, name) = match obj.object {
+                    Some(ref object) if object_sizes[object] > 1 => {
+                        let parent = object_nodes
+                            .entry(object.clone())
+                            .or_insert_with(|| {
+                                let mut node = root.add_group();
+                                node.set_name(object);
+                                node
+                            })
+                            .clone();
+
+                        (parent, group_name.unwrap_or(object))
+                    }
+                    Some(ref object) => (root.clone(), object.as_str()),
+                    None => (root.clone(), group_name.unwrap_or(&file_name)),
+                };
+
+                let mut node = parent.add_object(child_scale, local_transform, object);
+                node.set_name(name);
             }
+
+            hot_reload::watch_model(path, ModelSource::Obj(mtl_dir.to_path_buf()), watched);
     
             if self_root {
                 root.data()
                     .children
                     .last()
-                    .expect("There was nothing on this obj file.")
+                    .expect("The obj file has at least one object.")
                     .clone()
             } else {
                 root
             }
+        };
+
+        Ok(result)
+    }
+
+    pub fn add_glb(&mut self, path: &Path, scale: Vector3<f32>) -> SceneNode {
+        self.try_add_glb(path, scale).unwrap()
+    }
+
+    /// Like `add_glb`, but returns an error instead of panicking if the file cannot be loaded.
+    ///
+    /// Both binary `.glb` files and `.gltf` files with external buffers are supported. The nodes
+    /// of the file keep their names, transforms and hierarchy, under a new node named after the
+    /// file.
+    pub fn try_add_glb(&mut self, path: &Path, scale: Vector3<f32>) -> Result<SceneNode, String> {
+        let nodes = model::read_glb(path)?;
+        self.add_gltf_nodes(path, nodes, scale)
+    }
+
+    /// Adds the content of an ASCII or binary STL file.
+    pub fn try_add_stl(&mut self, path: &Path, scale: Vector3<f32>) -> Result<SceneNode, String> {
+        let meshes = model::indexed_mesh_chunks(model::read_stl(path)?)?;
+        self.add_indexed_meshes(path, meshes, scale)
+    }
+
+    /// Adds the content of an ASCII or binary PLY file.
+    pub fn try_add_ply(&mut self, path: &Path, scale: Vector3<f32>) -> Result<SceneNode, String> {
+        let meshes = model::indexed_mesh_chunks(model::read_ply(path)?)?;
+        self.add_indexed_meshes(path, meshes, scale)
+    }
+
+    /// Adds a model file, choosing the loader from its extension.
+    ///
+    /// Supported extensions are `obj` (materials are looked up next to the file), `glb`, `gltf`,
+    /// `stl` and `ply`. Each mesh records the file it was loaded from, see `Mesh::asset_source`.
+    pub fn add_model(&mut self, path: &Path) -> Result<SceneNode, String> {
+        let data = model::read_model(path, &|_| ())?;
+        self.add_model_data(path, data)
+    }
+
+    /// Adds a model already read from the file at `path` with `loader::model::read_model`.
+    pub fn add_model_data(&mut self, path: &Path, data: ModelData) -> Result<SceneNode, String> {
+        let scale = Vector3::from_element(1.0);
+        let node = match data {
+            ModelData::Obj { meshes, mtl_dir } => {
+                self.add_obj_meshes(path, &mtl_dir, meshes, scale, na::zero())?
+            }
+            ModelData::Indexed(meshes) => {
+                self.add_indexed_meshes(path, meshes, scale)?
+            }
+            ModelData::Gltf(nodes) => self.add_gltf_nodes(path, nodes, scale)?,
+        };
+        let mut index = 0;
+
+        node.data().apply_to_objects(&mut |o| {
+            o.mesh().borrow_mut().set_asset_source(path, index);
+            index += 1;
         });
-    
-        result.unwrap()
-    }    
 
-    pub fn  add_glb(&mut self, path: &Path, scale: Vector3<f32>) -> SceneNode {
-        let tex = TextureManager::get_global_manager(|tm: &mut TextureManager| tm.get_default());
-        let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
-    
-        let result = glb::load_glb(path).map(|meshes| {
-            let mut root;
-    
-            let self_root = meshes.len() == 1;
-            let child_scale;
-    
-            if self_root {
-                root = self.clone();
-                child_scale = scale;
-            } else {
-                root = SceneNode::new(scale, na::one(), None);
-                self.add_child(root.clone());
-                child_scale = Vector3::from_element(1.0);
+        Ok(node)
+    }
+
+    /// Writes the objects of this subtree to an OBJ file, with their materials in a MTL file.
+    ///
+    /// Each object becomes a group with its own material, made of the object color, texture and
+    /// material parameters.
+    /// Texture files are copied next to the OBJ file. Vertices are written in world space if
+    /// `world_space` is `true`, and relative to this node otherwise. Invisible objects are skipped.
+    pub fn export_obj(&self, path: &Path, world_space: bool) -> IoResult<()> {
+        let out_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let (root_inverse, root_scale) = {
+            let data = self.data();
+            (data.inverse_world_transformation(), data.world_scale())
+        };
+        let mut groups = Vec::new();
+        let mut result = Ok(());
+
+        self.apply_to_rendered_objects(&mut |node, object, transform, scale| {
+            let i = groups.len();
+            let to_export_space = |p: Point3<f32>| {
+                let p = transform * Point3::from(p.coords.component_mul(scale));
+
+                if world_space {
+                    p
+                } else {
+                    Point3::from((root_inverse * p).coords.component_div(&root_scale))
+                }
+            };
+            let normal_to_export_space = |n: &Vector3<f32>| {
+                let n = transform.rotation * n.component_div(scale);
+                let n = if world_space {
+                    n
+                } else {
+                    (root_inverse.rotation * n).component_mul(&root_scale)
+                };
+
+                n.try_normalize(1.0e-12).unwrap_or_else(Vector3::zeros)
+            };
+
+            let mut group = obj::ObjGroup {
+                name: node.name().unwrap_or_else(|| format!("object_{}", i)),
+                coords: Vec::new(),
+                normals: Vec::new(),
+                uvs: Vec::new(),
+                faces: Vec::new(),
+                primitive: object.mesh().borrow().primitive(),
+                material: None,
+            };
+
+            object.read_vertices(&mut |vs| {
+                group.coords = vs.iter().map(|v| to_export_space(*v)).collect()
+            });
+            object.read_normals(&mut |ns| {
+                group.normals = ns.iter().map(normal_to_export_space).collect()
+            });
+            object.read_uvs(&mut |us| group.uvs = us.to_vec());
+            object.read_faces(&mut |fs| {
+                group.faces = fs
+                    .iter()
+                    .map(|f| Point3::new(f.x as u32, f.y as u32, f.z as u32))
+                    .collect()
+            });
+
+            if group.normals.len() != group.coords.len() {
+                group.normals.clear();
             }
-    
-            for mesh in meshes.into_iter() {
-                let indices: Vec<Point3<u16>> = mesh
-                    .indices
-                    .chunks(3)
-                    .map(|chunk| Point3::new(chunk[0] as u16, chunk[1] as u16, chunk[2] as u16))
-                    .collect();
-
-                let mesh_data = Mesh::new(
-                    mesh.vertices,              
-                    indices,                
-                    None,                       
-                    None,                       
-                    false,                  
-                );
-            
-                let mut object = Object::new(
-                    Rc::new(RefCell::new(mesh_data)),
-                    1.0,
-                    1.0,
-                    1.0,
-                    tex.clone(),
-                    mat.clone(),
-                );
-            
-                let _ = root.add_object(child_scale, na::one(), object);
+
+            if group.uvs.len() != group.coords.len() {
+                group.uvs.clear();
             }
-    
-            if self_root {
-                root.data()
-                    .children
-                    .last()
-                    .expect("There was nothing on this glb file.")
-                    .clone()
-            } else {
-                root
+
+            let color = object.data().color();
+            let parameters = object.material_parameters();
+            let mut material = MtlMaterial::new_default(format!("material_{}", i));
+            material.diffuse = color.coords;
+            material.specular = parameters.specular_color.coords;
+            material.shininess = parameters.shininess;
+            material.emissive = parameters.emissive.coords;
+            material.roughness = parameters.roughness;
+            material.metalness = parameters.metalness;
+
+            let texture_path = TextureManager::get_global_manager(|tm| {
+                tm.name_of(object.data().texture())
+                    .and_then(|name| tm.path(&name).map(|p| p.to_path_buf()))
+            });
+
+            if let Some(texture_path) = texture_path {
+                match obj::copy_texture(&texture_path, out_dir) {
+                    Ok(file_name) => material.diffuse_texture = Some(file_name),
+                    Err(e) => {
+                        if result.is_ok() {
+                            result = Err(e)
+                        }
+                    }
+                }
             }
+
+            group.material = Some(material);
+            groups.push(group);
         });
-    
-        result.unwrap()
+
+        result?;
+        obj::write_file(path, &groups)
+    }
+
+    /// Writes this subtree to a glTF 2.0 binary file.
+    ///
+    /// Each node keeps its name and local translation and rotation. Scales are baked into the
+    /// vertices, since scale does not propagate to the children translations in a scene graph
+    /// as it does in glTF. Objects colors and textures become base-color materials, with the
+    /// texture files embedded. Invisible nodes are skipped.
+    pub fn export_glb(&self, path: &Path) -> Result<(), String> {
+        glb::write_glb(path, &self.glb_node(&Vector3::from_element(1.0)))
+    }
+
+    fn glb_node(&self, parent_scale: &Vector3<f32>) -> glb::GlbNode {
+        let data = self.data();
+        let transform = data.local_transformation();
+        let scale = parent_scale.component_mul(&data.local_scale());
+
+        let mesh = data.object().map(|object| {
+            let mut mesh = glb::GlbMesh {
+                positions: Vec::new(),
+                normals: Vec::new(),
+                uvs: Vec::new(),
+                indices: Vec::new(),
+                primitive: object.mesh().borrow().primitive(),
+                base_color: [0.0, 0.0, 0.0, 1.0],
+                texture: None,
+            };
+
+            object.read_vertices(&mut |vs| {
+                mesh.positions = vs
+                    .iter()
+                    .map(|v| Point3::from(v.coords.component_mul(&scale)))
+                    .collect()
+            });
+            object.read_normals(&mut |ns| {
+                mesh.normals = ns
+                    .iter()
+                    .map(|n| {
+                        n.component_div(&scale)
+                            .try_normalize(1.0e-12)
+                            .unwrap_or_else(Vector3::zeros)
+                    })
+                    .collect()
+            });
+            object.read_uvs(&mut |us| mesh.uvs = us.to_vec());
+            object.read_faces(&mut |fs| {
+                mesh.indices = fs
+                    .iter()
+                    .flat_map(|f| [f.x as u32, f.y as u32, f.z as u32])
+                    .collect()
+            });
+
+            let color = object.data().color();
+            mesh.base_color = [color.x, color.y, color.z, 1.0];
+            mesh.texture = TextureManager::get_global_manager(|tm| {
+                tm.name_of(object.data().texture())
+                    .and_then(|name| tm.path(&name).map(|p| p.to_path_buf()))
+            });
+
+            mesh
+        });
+
+        glb::GlbNode {
+            name: data.name().map(|n| n.to_string()),
+            translation: transform.translation.vector.into(),
+            rotation: transform.rotation.coords.into(),
+            scale: [1.0, 1.0, 1.0],
+            mesh,
+            children: data
+                .children()
+                .iter()
+                .filter(|c| c.is_visible())
+                .map(|c| c.glb_node(&scale))
+                .collect(),
+        }
+    }
+
+    // Adds one object per mesh of the file at `path`, under a new node named after the file if
+    // there are several of them. The meshes are the ones of `model::indexed_mesh_chunks`.
+    fn add_indexed_meshes(
+        &mut self,
+        path: &Path,
+        chunks: Vec<(Option<String>, Mesh)>,
+        scale: Vector3<f32>,
+    ) -> Result<SceneNode, String> {
+        let tex = TextureManager::get_global_manager(|tm: &mut TextureManager| tm.get_default());
+        let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
+        let file_name = &file_stem(path)[..];
+
+        if chunks.is_empty() {
+            return Err(format!("There was nothing on the file {}.", file_name));
+        }
+
+        let self_root = chunks.len() == 1;
+        let mut root;
+        let child_scale;
+
+        if self_root {
+            root = self.clone();
+            child_scale = scale;
+        } else {
+            root = SceneNode::new(scale, na::one(), None);
+            root.set_name(file_name);
+            self.add_child(root.clone());
+            child_scale = Vector3::from_element(1.0);
+        }
+
+        let mut last = None;
+        let mut watched = Vec::new();
+
+        for (name, mesh) in chunks.into_iter() {
+            let mesh = Rc::new(RefCell::new(mesh));
+            watched.push(Rc::downgrade(&mesh));
+            let object = Object::new(
+                mesh,
+                1.0,
+                1.0,
+                1.0,
+                tex.clone(),
+                mat.clone(),
+            );
+
+            let mut node = root.add_object(child_scale, na::one(), object);
+            node.set_name(name.as_deref().unwrap_or(file_name));
+            last = Some(node);
+        }
+
+        hot_reload::watch_model(path, ModelSource::Indexed, watched);
+
+        if self_root {
+            Ok(last.expect("There is at least one mesh."))
+        } else {
+            Ok(root)
+        }
+    }
+
+    // Adds the nodes of a glTF scene under a new node named after the file at `path`. A node
+    // with a single mesh holds its object, and a node with several meshes gets one child object
+    // per mesh.
+    fn add_gltf_nodes(
+        &mut self,
+        path: &Path,
+        nodes: Vec<ModelNode>,
+        scale: Vector3<f32>,
+    ) -> Result<SceneNode, String> {
+        let file_name = &file_stem(path)[..];
+
+        if nodes.is_empty() {
+            return Err(format!("There was nothing on the file {}.", file_name));
+        }
+
+        let mut root = SceneNode::new(scale, na::one(), None);
+        root.set_name(file_name);
+        self.add_child(root.clone());
+
+        let mut watched = Vec::new();
+
+        for node in nodes {
+            root.add_gltf_node(node, &mut watched);
+        }
+
+        hot_reload::watch_model(path, ModelSource::Indexed, watched);
+        Ok(root)
+    }
+
+    fn add_gltf_node(&mut self, node: ModelNode, watched: &mut Vec<Weak<RefCell<Mesh>>>) {
+        let mut objects: Vec<Object> = node
+            .meshes
+            .into_iter()
+            .map(|mesh| {
+                let mesh = Rc::new(RefCell::new(mesh));
+                watched.push(Rc::downgrade(&mesh));
+                let tex = TextureManager::get_global_manager(|tm| tm.get_default());
+                let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
+                Object::new(mesh, 1.0, 1.0, 1.0, tex, mat)
+            })
+            .collect();
+
+        let object = if objects.len() == 1 {
+            objects.pop()
+        } else {
+            None
+        };
+        let mut child = SceneNode::new(node.scale, node.transform, object);
+        self.add_child(child.clone());
+
+        if let Some(ref name) = node.name {
+            child.set_name(name);
+        }
+
+        for object in objects {
+            let _ = child.add_object(Vector3::from_element(1.0), na::one(), object);
+        }
+
+        for grandchild in node.children {
+            child.add_gltf_node(grandchild, watched);
+        }
     }
-    
 
     #[inline]
     pub fn apply_to_scene_nodes_mut<F: FnMut(&mut SceneNode)>(&mut self, f: &mut F) {
@@ -892,6 +1686,46 @@ impl SceneNode {
         self.data_mut().set_texture(texture)
     }
 
+    /// Sets the material parameters of the objects of this node and its children, read by
+    /// their materials in addition to the color and texture.
+    #[inline]
+    pub fn set_material_parameters(&mut self, parameters: MaterialParameters) {
+        self.data_mut().set_material_parameters(parameters)
+    }
+
+    #[inline]
+    pub fn modify_material_parameters<F: FnMut(&mut MaterialParameters)>(&mut self, f: &mut F) {
+        self.data_mut().modify_material_parameters(f)
+    }
+
+    #[inline]
+    pub fn set_specular_color(&mut self, r: f32, g: f32, b: f32) {
+        self.data_mut().set_specular_color(r, g, b)
+    }
+
+    /// Sets the exponent of the specular highlights: the higher, the smaller and sharper.
+    #[inline]
+    pub fn set_shininess(&mut self, shininess: f32) {
+        self.data_mut().set_shininess(shininess)
+    }
+
+    /// Sets the color emitted regardless of lighting.
+    #[inline]
+    pub fn set_emissive(&mut self, r: f32, g: f32, b: f32) {
+        self.data_mut().set_emissive(r, g, b)
+    }
+
+    /// Sets the value of the uniform `name` for the objects of this node and its children.
+    #[inline]
+    pub fn set_uniform<V: Into<UniformValue>>(&mut self, name: &str, value: V) {
+        self.data_mut().set_uniform(name, value)
+    }
+
+    #[inline]
+    pub fn remove_uniform(&mut self, name: &str) {
+        self.data_mut().remove_uniform(name)
+    }
+
     #[inline]
     pub fn set_local_scale(&mut self, sx: f32, sy: f32, sz: f32) {
         self.data_mut().set_local_scale(sx, sy, sz)
@@ -952,3 +1786,95 @@ impl SceneNode {
         self.data_mut().set_local_rotation(r)
     }
 }
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// The material parameters given by a MTL material.
+fn mtl_parameters(mtl: &MtlMaterial) -> MaterialParameters {
+    let mut parameters = MaterialParameters::default();
+    parameters.specular_color = Point3::from(mtl.specular);
+    parameters.shininess = mtl.shininess;
+    parameters.emissive = Point3::from(mtl.emissive);
+    parameters.roughness = mtl.roughness;
+    parameters.metalness = mtl.metalness;
+    parameters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn glb_export_keeps_the_hierarchy_and_transforms() {
+        let mut car = SceneNode::new_empty();
+        car.set_name("car");
+        car.set_local_transformation(Isometry3::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(0.0, 0.5, 0.0),
+        ));
+        let mut axle = car.add_group();
+        axle.set_name("axle");
+        axle.set_local_transformation(Isometry3::new(
+            Vector3::new(0.0, -0.5, 1.5),
+            Vector3::new(0.3, 0.0, 0.0),
+        ));
+        let mut wheel = axle.add_group();
+        wheel.set_name("wheel_front_left");
+        wheel.set_local_transformation(Isometry3::new(
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.2),
+        ));
+
+        let path = std::env::temp_dir().join("starman_glb_hierarchy.glb");
+        car.export_glb(&path).unwrap();
+        let loaded = SceneNode::new_empty().add_model(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let nodes = [
+            ("car", &car),
+            ("car/axle", &axle),
+            ("car/axle/wheel_front_left", &wheel),
+        ];
+
+        for (name, node) in nodes {
+            let expected = node.data().world_transformation();
+            let actual = loaded
+                .find_path(name)
+                .unwrap()
+                .data()
+                .world_transformation();
+
+            assert!(
+                (actual.to_homogeneous() - expected.to_homogeneous()).norm() < 1.0e-5,
+                "{}: {} != {}",
+                name,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn find_path_resolves_nested_glb_nodes() {
+        let mut car = SceneNode::new_empty();
+        car.set_name("car");
+        car.add_group().set_name("wheel_front_left");
+
+        let path = std::env::temp_dir().join("starman_glb_find_path.glb");
+        car.export_glb(&path).unwrap();
+        let mut scene = SceneNode::new_empty();
+        let _ = scene.add_model(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let wheel = scene
+            .find_path("starman_glb_find_path/car/wheel_front_left")
+            .unwrap();
+        assert_eq!(wheel.data().name(), Some("wheel_front_left"));
+        assert!(scene.find_path("starman_glb_find_path/wheel_front_left").is_none());
+    }
+}
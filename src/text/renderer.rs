@@ -82,7 +82,8 @@ impl TextRenderer {
             Context::LINEAR as i32
         ));
 
-        let mut shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
         shader.use_program();
 
         TextRenderer {
@@ -1,6 +1,6 @@
 use std::sync::mpsc::Sender;
 
-use crate::{event::window_event::{Action, Key, MouseButton, WindowEvent}, scene::scene_node::SceneNode};
+use crate::{event::recorder::ReplayedInput, event::window_event::{Action, Key, MouseButton, WindowEvent}, scene::scene_node::SceneNode};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::window::gl_canvas::GLCanvas as CanvasImpl;
 #[cfg(target_arch = "wasm32")]
@@ -39,6 +39,7 @@ pub struct CanvasSetup {
 
 pub struct Canvas {
     canvas: CanvasImpl,
+    replayed_input: Option<ReplayedInput>,
 }
 
 impl Canvas {
@@ -52,6 +53,7 @@ impl Canvas {
     ) -> Self {
         Canvas {
             canvas: CanvasImpl::open(title, hide, width, height, canvas_setup, out_events),
+            replayed_input: None,
         }
     }
 
@@ -72,7 +74,10 @@ impl Canvas {
     }
 
     pub fn cursor_pos(&self) -> Option<(f64, f64)> {
-        self.canvas.cursor_pos()
+        match self.replayed_input {
+            Some(ref input) => input.cursor_pos(),
+            None => self.canvas.cursor_pos(),
+        }
     }
 
     pub fn scale_factor(&self) -> f64 {
@@ -108,11 +113,33 @@ impl Canvas {
     }
 
     pub fn get_mouse_button(&self, button: MouseButton) -> Action {
-        self.canvas.get_mouse_button(button)
+        match self.replayed_input {
+            Some(ref input) => input.get_mouse_button(button),
+            None => self.canvas.get_mouse_button(button),
+        }
     }
 
     pub fn get_key(&self, key: Key) -> Action {
-        self.canvas.get_key(key)
+        match self.replayed_input {
+            Some(ref input) => input.get_key(key),
+            None => self.canvas.get_key(key),
+        }
+    }
+
+    /// Makes the key, button and cursor states reflect replayed events instead of the live
+    /// input, or restores the live input if `replaying` is `false`.
+    pub(crate) fn set_replaying(&mut self, replaying: bool) {
+        self.replayed_input = if replaying {
+            Some(ReplayedInput::default())
+        } else {
+            None
+        };
+    }
+
+    pub(crate) fn replay_event(&mut self, event: &WindowEvent) {
+        if let Some(ref mut input) = self.replayed_input {
+            input.handle_event(event)
+        }
     }
 
     pub fn set_compass_node(&mut self, node: SceneNode) {
@@ -0,0 +1,318 @@
+//! Capture of rendered frames to image sequences and animations.
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbImage};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{
+    BufReader, BufWriter, Error, ErrorKind, Read, Result as IoResult, Seek, SeekFrom, Write,
+};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+enum Output {
+    PngSequence(PathBuf),
+    Gif(Box<GifEncoder<BufWriter<File>>>),
+    Apng(Apng),
+}
+
+// APNG files start with the number of frames. When it is known in advance, the frames are
+// encoded as they come. Otherwise they are spooled to a temporary file, and encoded once the
+// capture ends.
+struct Apng {
+    path: PathBuf,
+    expected_frames: Option<usize>,
+    writer: Option<png::Writer<BufWriter<File>>>,
+    spool: Option<Spool>,
+}
+
+// A temporary file holding raw RGB frames, removed when dropped.
+struct Spool {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+/// Writes the frames rendered by a window to a numbered PNG sequence, a GIF or an APNG.
+///
+/// Frames are captured with `Window::start_frame_capture`.
+pub struct FrameCapture {
+    output: Output,
+    frame_delay: Duration,
+    num_frames: usize,
+    size: Option<(u32, u32)>,
+}
+
+impl FrameCapture {
+    /// Writes each frame to `dir/frame_00000.png`, `dir/frame_00001.png`, etc.
+    ///
+    /// The directory is created if needed.
+    pub fn png_sequence<P: AsRef<Path>>(dir: P) -> IoResult<FrameCapture> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(FrameCapture::new(
+            Output::PngSequence(dir.as_ref().to_path_buf()),
+            0.0,
+        ))
+    }
+
+    /// Writes the frames to an animated GIF playing at `fps` frames per second, looping forever.
+    pub fn gif<P: AsRef<Path>>(path: P, fps: f64) -> IoResult<FrameCapture> {
+        let mut encoder = GifEncoder::new(BufWriter::new(File::create(path)?));
+        encoder.set_repeat(Repeat::Infinite).map_err(image_error)?;
+        Ok(FrameCapture::new(Output::Gif(Box::new(encoder)), fps))
+    }
+
+    /// Writes the frames to an animated PNG playing at `fps` frames per second, looping forever.
+    ///
+    /// Unless the number of frames is given with `set_expected_frames`, frames are spooled to a
+    /// temporary file until `finish` is called.
+    pub fn apng<P: AsRef<Path>>(path: P, fps: f64) -> IoResult<FrameCapture> {
+        // Fail early if the file cannot be created.
+        let _ = File::create(path.as_ref())?;
+        let apng = Apng {
+            path: path.as_ref().to_path_buf(),
+            expected_frames: None,
+            writer: None,
+            spool: None,
+        };
+        Ok(FrameCapture::new(Output::Apng(apng), fps))
+    }
+
+    fn new(output: Output, fps: f64) -> FrameCapture {
+        let frame_delay = if fps > 0.0 {
+            Duration::from_secs_f64(1.0 / fps)
+        } else {
+            Duration::from_secs(0)
+        };
+
+        FrameCapture {
+            output,
+            frame_delay,
+            num_frames: 0,
+            size: None,
+        }
+    }
+
+    /// The number of frames captured so far.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Sets the number of frames of the capture, when it is known before the first frame.
+    ///
+    /// APNG frames are then encoded as they are added instead of being spooled. A capture that
+    /// ends with fewer frames is still a valid APNG. Has no effect once a frame was added.
+    pub fn set_expected_frames(&mut self, num_frames: usize) {
+        if let Output::Apng(ref mut apng) = self.output {
+            if self.num_frames == 0 {
+                apng.expected_frames = Some(num_frames);
+            }
+        }
+    }
+
+    /// Appends a frame. Every frame of a capture must have the same size.
+    pub fn add_frame(&mut self, image: RgbImage) -> IoResult<()> {
+        let size = image.dimensions();
+
+        if *self.size.get_or_insert(size) != size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The size of the frames changed during the capture",
+            ));
+        }
+
+        match self.output {
+            Output::PngSequence(ref dir) => {
+                let path = dir.join(format!("frame_{:05}.png", self.num_frames));
+                image.save(path).map_err(image_error)?;
+            }
+            Output::Gif(ref mut encoder) => {
+                let delay = Delay::from_saturating_duration(self.frame_delay);
+                let rgba = DynamicImage::ImageRgb8(image).into_rgba8();
+                encoder
+                    .encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+                    .map_err(image_error)?;
+            }
+            Output::Apng(ref mut apng) => apng.add_frame(&image, self.frame_delay)?,
+        }
+
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    /// Completes the files of the capture.
+    pub fn finish(self) -> IoResult<()> {
+        match self.output {
+            Output::PngSequence(_) | Output::Gif(_) => Ok(()),
+            Output::Apng(apng) => apng.finish(self.num_frames, self.size, self.frame_delay),
+        }
+    }
+}
+
+impl Apng {
+    fn add_frame(&mut self, image: &RgbImage, frame_delay: Duration) -> IoResult<()> {
+        match self.expected_frames {
+            Some(num_frames) => {
+                if self.writer.is_none() {
+                    let file = File::create(&self.path)?;
+                    let writer = apng_writer(file, image.dimensions(), num_frames, frame_delay)?;
+                    self.writer = Some(writer);
+                }
+
+                let writer = self.writer.as_mut().unwrap();
+                writer.write_image_data(image.as_raw()).map_err(png_error)
+            }
+            None => {
+                if self.spool.is_none() {
+                    self.spool = Some(Spool::new()?);
+                }
+
+                self.spool.as_mut().unwrap().file.write_all(image.as_raw())
+            }
+        }
+    }
+
+    fn finish(
+        mut self,
+        num_frames: usize,
+        size: Option<(u32, u32)>,
+        frame_delay: Duration,
+    ) -> IoResult<()> {
+        if let Some(writer) = self.writer.take() {
+            if num_frames < self.expected_frames.unwrap_or(0) {
+                // Dropping the writer ends the file, which then announces too many frames.
+                drop(writer);
+                return set_apng_num_frames(&self.path, num_frames as u32);
+            }
+
+            return writer.finish().map_err(png_error);
+        }
+
+        let (mut spool, (width, height)) = match (self.spool.take(), size) {
+            (Some(spool), Some(size)) => (spool, size),
+            _ => return Ok(()),
+        };
+
+        spool.file.flush()?;
+        let file = spool.file.get_mut();
+        let _ = file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(file);
+        let mut frame = vec![0; width as usize * height as usize * 3];
+        let mut writer = apng_writer(
+            File::create(&self.path)?,
+            (width, height),
+            num_frames,
+            frame_delay,
+        )?;
+
+        for _ in 0..num_frames {
+            reader.read_exact(&mut frame)?;
+            writer.write_image_data(&frame).map_err(png_error)?;
+        }
+
+        writer.finish().map_err(png_error)
+    }
+}
+
+impl Spool {
+    fn new() -> IoResult<Spool> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("starman-capture-{}-{}.rgb", process::id(), id));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(Spool {
+            path,
+            file: BufWriter::new(file),
+        })
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn apng_writer(
+    file: File,
+    (width, height): (u32, u32),
+    num_frames: usize,
+    frame_delay: Duration,
+) -> IoResult<png::Writer<BufWriter<File>>> {
+    let delay_ms = frame_delay.as_millis().min(u16::MAX as u128) as u16;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(num_frames as u32, 0)
+        .map_err(png_error)?;
+    encoder.set_frame_delay(delay_ms, 1000).map_err(png_error)?;
+    encoder.write_header().map_err(png_error)
+}
+
+// Rewrites the number of frames in the `acTL` chunk of the APNG file at `path`.
+fn set_apng_num_frames(path: &Path, num_frames: u32) -> IoResult<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let _ = file.seek(SeekFrom::Start(8))?;
+
+    loop {
+        let mut header = [0; 8];
+        file.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+
+        if &header[4..] == b"acTL" {
+            let mut chunk = [0; 12];
+            chunk[..4].copy_from_slice(b"acTL");
+            file.read_exact(&mut chunk[4..])?;
+            chunk[4..8].copy_from_slice(&num_frames.to_be_bytes());
+
+            let _ = file.seek(SeekFrom::Current(-8))?;
+            file.write_all(&chunk[4..])?;
+            return file.write_all(&crc32(&chunk).to_be_bytes());
+        }
+
+        if &header[4..] == b"IEND" {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Missing APNG acTL chunk",
+            ));
+        }
+
+        let _ = file.seek(SeekFrom::Current(len as i64 + 4))?;
+    }
+}
+
+// The CRC-32 of a PNG chunk, computed over its type and data.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for b in bytes {
+        crc ^= *b as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn image_error(e: image::ImageError) -> Error {
+    Error::other(e.to_string())
+}
+
+fn png_error(e: png::EncodingError) -> Error {
+    Error::other(e.to_string())
+}
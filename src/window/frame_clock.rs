@@ -0,0 +1,61 @@
+//! Frame counter and clock of a window.
+
+use std::time::{Duration, Instant};
+
+/// Counts the frames rendered by a window and the time elapsed between them.
+///
+/// With a fixed timestep, every frame advances the clock by exactly that step regardless of the
+/// wall-clock time it took, which makes simulations driven by the clock deterministic.
+pub struct FrameClock {
+    timestep: Option<Duration>,
+    frame: u64,
+    last_tick: Instant,
+    elapsed: Duration,
+    delta: Duration,
+}
+
+impl FrameClock {
+    pub fn new() -> FrameClock {
+        FrameClock {
+            timestep: None,
+            frame: 0,
+            last_tick: Instant::now(),
+            elapsed: Duration::from_secs(0),
+            delta: Duration::from_secs(0),
+        }
+    }
+
+    /// Sets the duration of each frame, or `None` to follow the wall-clock time.
+    pub fn set_fixed_timestep(&mut self, timestep: Option<Duration>) {
+        self.timestep = timestep;
+    }
+
+    pub fn fixed_timestep(&self) -> Option<Duration> {
+        self.timestep
+    }
+
+    /// Advances the clock to the next frame.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+
+        self.delta = self.timestep.unwrap_or_else(|| now - self.last_tick);
+        self.elapsed += self.delta;
+        self.last_tick = now;
+        self.frame += 1;
+    }
+
+    /// The index of the current frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The time elapsed since the window was opened, as counted by this clock.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The duration of the last frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+}
@@ -200,6 +200,15 @@ impl AbstractCanvas for GLCanvas {
                     glutin::event::WindowEvent::ReceivedCharacter(c) => {
                         let _ = out_events.send(WindowEvent::Char(c));
                     }
+                    glutin::event::WindowEvent::HoveredFile(path) => {
+                        let _ = out_events.send(WindowEvent::FileHovered(path));
+                    }
+                    glutin::event::WindowEvent::HoveredFileCancelled => {
+                        let _ = out_events.send(WindowEvent::FileHoverCancelled);
+                    }
+                    glutin::event::WindowEvent::DroppedFile(path) => {
+                        let _ = out_events.send(WindowEvent::FileDropped(path));
+                    }
                     _ => {}
                 },
                 Event::RedrawEventsCleared => {
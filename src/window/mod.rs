@@ -2,4 +2,7 @@ pub mod window;
 pub mod gl_canvas;
 pub mod canvas;
 pub mod window_cache;
-pub mod state;
\ No newline at end of file
+pub mod state;
+pub mod frame_capture;
+pub mod frame_clock;
+pub mod model_loader;
\ No newline at end of file
@@ -0,0 +1,357 @@
+//! Loading of model files in the background.
+
+use std::cell::RefCell;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use image::DynamicImage;
+
+use crate::context::context::Texture;
+use crate::loader::model::{self, ModelData, ModelNode};
+use crate::loader::texture_file::{self, TextureFile};
+use crate::resource::mesh::Mesh;
+use crate::resource::texture_manager::TextureManager;
+use crate::scene::scene_node::SceneNode;
+
+/// The state of a model loaded with `Window::add_model_async`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum LoadStatus {
+    /// The file is being read on a worker thread, or waits for one. Holds the fraction of the file
+    /// read so far.
+    Reading(f32),
+    /// The model is being uploaded to the GPU. Holds the fraction uploaded so far.
+    Uploading(f32),
+    /// The model is in the scene.
+    Loaded,
+    /// The model could not be loaded, and its node was removed from the scene.
+    Failed(String),
+}
+
+/// A model loaded in the background.
+#[derive(Clone)]
+pub struct ModelHandle {
+    node: SceneNode,
+    status: Rc<RefCell<LoadStatus>>,
+}
+
+impl ModelHandle {
+    /// The node receiving the model. It is hidden until the model is completely loaded.
+    pub fn node(&self) -> &SceneNode {
+        &self.node
+    }
+
+    pub fn status(&self) -> LoadStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Whether the model is loaded or failed to load.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            *self.status.borrow(),
+            LoadStatus::Loaded | LoadStatus::Failed(_)
+        )
+    }
+}
+
+// A texture read by the worker thread.
+enum ReadTexture {
+    Image(DynamicImage),
+    File(TextureFile),
+}
+
+impl ReadTexture {
+    fn num_bytes(&self) -> usize {
+        match *self {
+            ReadTexture::Image(ref image) => image.as_bytes().len(),
+            ReadTexture::File(ref file) => file.num_bytes(),
+        }
+    }
+}
+
+struct ReadModel {
+    data: ModelData,
+    textures: Vec<(PathBuf, ReadTexture)>,
+}
+
+enum Stage {
+    Reading(Receiver<Result<ReadModel, String>>),
+    UploadingTextures(ModelData, Vec<(PathBuf, ReadTexture)>),
+    UploadingMeshes(Vec<Rc<RefCell<Mesh>>>),
+    Finished,
+}
+
+struct PendingModel {
+    path: PathBuf,
+    handle: ModelHandle,
+    // The fraction of the file read by the worker thread, as the bits of a `f32`.
+    read_progress: Arc<AtomicU32>,
+    stage: Stage,
+    // The textures registered so far. Holding them keeps the texture manager from evicting them
+    // before the objects using them are created.
+    textures: Vec<Rc<Texture>>,
+    bytes_to_upload: usize,
+    bytes_uploaded: usize,
+}
+
+// The number of threads reading model files. Models loaded while they are all busy wait for one
+// of them.
+const NUM_WORKERS: usize = 2;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Reads model files on worker threads, and spreads their GPU uploads over several frames.
+pub struct ModelLoader {
+    pending: Vec<PendingModel>,
+    upload_budget: usize,
+    // The queue of the worker threads, started on the first load.
+    jobs: Option<Sender<Job>>,
+}
+
+impl ModelLoader {
+    pub fn new() -> ModelLoader {
+        ModelLoader {
+            pending: Vec::new(),
+            upload_budget: 32 * 1024 * 1024,
+            jobs: None,
+        }
+    }
+
+    /// Sets the number of bytes of meshes and textures uploaded to the GPU at each frame.
+    ///
+    /// At least one mesh or texture is uploaded per frame, whatever its size.
+    pub fn set_upload_budget(&mut self, bytes: usize) {
+        self.upload_budget = bytes.max(1);
+    }
+
+    /// The number of models still being loaded.
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Starts loading the model file at `path` into a new hidden child of `parent`.
+    ///
+    /// See `SceneNode::add_model` for the supported formats.
+    pub fn load(&mut self, parent: &mut SceneNode, path: &Path) -> ModelHandle {
+        let mut node = parent.add_group();
+        node.set_name(&path.file_stem().unwrap_or_default().to_string_lossy());
+        node.set_visible(false);
+
+        let handle = ModelHandle {
+            node,
+            status: Rc::new(RefCell::new(LoadStatus::Reading(0.0))),
+        };
+        let read_progress = Arc::new(AtomicU32::new(0));
+        let (sender, receiver) = mpsc::channel();
+
+        let worker_path = path.to_path_buf();
+        let worker_progress = read_progress.clone();
+        self.queue(Box::new(move || {
+            let progress = |p: f32| worker_progress.store(p.to_bits(), Ordering::Relaxed);
+            let result = model::read_model(&worker_path, &progress).map(|mut data| {
+                let mut textures = Vec::new();
+
+                for path in data.texture_paths() {
+                    match decode_texture(&path) {
+                        Ok(texture) => textures.push((path, texture)),
+                        Err(e) => {
+                            println!("Unable to load texture from file {:?}: {}", path, e);
+                            data.remove_texture(&path);
+                        }
+                    }
+                }
+
+                ReadModel { data, textures }
+            });
+
+            let _ = sender.send(result);
+        }));
+
+        self.pending.push(PendingModel {
+            path: path.to_path_buf(),
+            handle: handle.clone(),
+            read_progress,
+            stage: Stage::Reading(receiver),
+            textures: Vec::new(),
+            bytes_to_upload: 0,
+            bytes_uploaded: 0,
+        });
+
+        handle
+    }
+
+    /// Adds the models read since the last call to the scene, and uploads part of them to the
+    /// GPU. Must be called once per frame, with the window context current.
+    pub fn update(&mut self) {
+        let mut budget = self.upload_budget;
+
+        self.pending.retain_mut(|model| !model.update(&mut budget));
+    }
+
+    // Runs `job` on the first idle worker thread. The threads stop once the loader is dropped.
+    fn queue(&mut self, job: Job) {
+        let jobs = self.jobs.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+
+            for _ in 0..NUM_WORKERS {
+                let receiver = receiver.clone();
+                let _ = thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                });
+            }
+
+            sender
+        });
+
+        let _ = jobs.send(job);
+    }
+}
+
+impl PendingModel {
+    // Advances the load as much as the budget allows. Returns `true` once it is finished.
+    fn update(&mut self, budget: &mut usize) -> bool {
+        loop {
+            self.stage = match mem::replace(&mut self.stage, Stage::Finished) {
+                Stage::Reading(receiver) => match receiver.try_recv() {
+                    Ok(Ok(read)) => {
+                        self.bytes_to_upload = data_bytes(&read.data)
+                            + read
+                                .textures
+                                .iter()
+                                .map(|t| t.1.num_bytes())
+                                .sum::<usize>();
+                        Stage::UploadingTextures(read.data, read.textures)
+                    }
+                    Ok(Err(e)) => return self.fail(&e),
+                    Err(TryRecvError::Disconnected) => {
+                        return self.fail("the loading thread stopped unexpectedly")
+                    }
+                    Err(TryRecvError::Empty) => {
+                        let progress = f32::from_bits(self.read_progress.load(Ordering::Relaxed));
+                        *self.handle.status.borrow_mut() = LoadStatus::Reading(progress);
+                        self.stage = Stage::Reading(receiver);
+                        return false;
+                    }
+                },
+                Stage::UploadingTextures(data, mut textures) => match textures.pop() {
+                    // The textures are registered, so adding the model does not decode them again.
+                    None => match self.handle.node.add_model_data(&self.path, data) {
+                        Ok(_) => {
+                            self.textures.clear();
+                            let mut meshes = Vec::new();
+                            self.handle
+                                .node
+                                .data()
+                                .apply_to_objects(&mut |o| meshes.push(o.mesh().clone()));
+                            Stage::UploadingMeshes(meshes)
+                        }
+                        Err(e) => return self.fail(&e),
+                    },
+                    Some(texture) if *budget == 0 => {
+                        textures.push(texture);
+                        self.stage = Stage::UploadingTextures(data, textures);
+                        return false;
+                    }
+                    Some((path, texture)) => {
+                        let bytes = texture.num_bytes();
+                        let name = path.to_string_lossy();
+                        let mut texture = Some(texture);
+                        let texture = TextureManager::get_global_manager(|tm| {
+                            match texture.take().unwrap() {
+                                ReadTexture::Image(image) => tm.add_decoded(image, &path, &name),
+                                ReadTexture::File(file) => tm.add_texture_file(file, &path, &name),
+                            }
+                        });
+                        self.textures.push(texture);
+
+                        self.uploaded(bytes, budget);
+                        Stage::UploadingTextures(data, textures)
+                    }
+                },
+                Stage::UploadingMeshes(mut meshes) => match meshes.pop() {
+                    None => {
+                        self.handle.node.set_visible(true);
+                        *self.handle.status.borrow_mut() = LoadStatus::Loaded;
+                        return true;
+                    }
+                    Some(mesh) if *budget == 0 => {
+                        meshes.push(mesh);
+                        self.stage = Stage::UploadingMeshes(meshes);
+                        return false;
+                    }
+                    Some(mesh) => {
+                        let bytes = upload_mesh(&mesh.borrow());
+                        self.uploaded(bytes, budget);
+                        Stage::UploadingMeshes(meshes)
+                    }
+                },
+                Stage::Finished => return true,
+            };
+        }
+    }
+
+    fn uploaded(&mut self, bytes: usize, budget: &mut usize) {
+        *budget = budget.saturating_sub(bytes);
+        self.bytes_uploaded += bytes;
+
+        let progress = if self.bytes_to_upload == 0 {
+            1.0
+        } else {
+            (self.bytes_uploaded as f32 / self.bytes_to_upload as f32).min(1.0)
+        };
+
+        *self.handle.status.borrow_mut() = LoadStatus::Uploading(progress);
+    }
+
+    fn fail(&mut self, error: &str) -> bool {
+        self.handle.node.unlink();
+        *self.handle.status.borrow_mut() = LoadStatus::Failed(error.to_string());
+        true
+    }
+}
+
+// Decodes a texture. KTX2 and DDS files are only read, as they are uploaded as they are when the
+// context supports their format.
+fn decode_texture(path: &Path) -> Result<ReadTexture, String> {
+    if texture_file::is_texture_file(path) {
+        texture_file::load_texture_file(path).map(ReadTexture::File)
+    } else {
+        image::open(path)
+            .map(ReadTexture::Image)
+            .map_err(|e| e.to_string())
+    }
+}
+
+// An estimate of the size of the GPU buffers of a model.
+fn data_bytes(data: &ModelData) -> usize {
+    match *data {
+        ModelData::Obj { ref meshes, .. } => meshes.iter().map(|m| m.mesh.num_bytes()).sum(),
+        ModelData::Indexed(ref meshes) => meshes.iter().map(|m| m.1.num_bytes()).sum(),
+        ModelData::Gltf(ref nodes) => nodes.iter().map(node_bytes).sum(),
+    }
+}
+
+fn node_bytes(node: &ModelNode) -> usize {
+    node.meshes.iter().map(Mesh::num_bytes).sum::<usize>()
+        + node.children.iter().map(node_bytes).sum::<usize>()
+}
+
+// Uploads the buffers of a mesh, and returns their size.
+fn upload_mesh(mesh: &Mesh) -> usize {
+    mesh.coords().write().unwrap().load_to_gpu();
+    mesh.normals().write().unwrap().load_to_gpu();
+    mesh.uvs().write().unwrap().load_to_gpu();
+    mesh.faces().write().unwrap().load_to_gpu();
+
+    mesh.num_bytes()
+}
@@ -2,7 +2,9 @@ use std::cell::RefCell;
 use std::iter::repeat;
 use std::path::Path;
 use std::rc::Rc;
-use std::sync::mpsc::{self, Receiver};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Result as IoResult};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::Duration;
 
 use nalgebra::{Isometry3, Point2, Point3, Translation3, Vector2, Vector3};
@@ -15,40 +17,69 @@ use crate::camera::camera::Camera;
 use crate::context::context::Context;
 use crate::context::context::Texture;
 use crate::event::event_manager::EventManager;
+use crate::event::input_map::{Binding, InputMap};
+use crate::event::recorder::{EventRecorder, EventReplay};
 use crate::event::window_event::{Action, Key, WindowEvent};
 use crate::light::Light;
 use crate::planar_camera::{FixedView, PlanarCamera};
 use crate::planar_line_renderer::PlanarLineRenderer;
 use crate::post_processing::post_processing_effect::PostProcessingEffect;
+use crate::renderer::id_picker::IdPicker;
 use crate::renderer::line_renderer::LineRenderer;
+use crate::renderer::outline_renderer::OutlineRenderer;
 use crate::renderer::point_renderer::PointRenderer;
 use crate::renderer::renderer::Renderer;
 use crate::resource::framebuffer_manager::{FramebufferManager, RenderTarget};
+use crate::resource::hot_reload;
+use crate::resource::material_manager::MaterialManager;
 use crate::resource::mesh::Mesh;
+use crate::resource::mesh_manager::MeshManager;
 use crate::resource::planar_mesh::PlanarMesh;
 use crate::resource::texture_manager::TextureManager;
 use crate::scene::planar_scene_node::PlanarSceneNode;
+use crate::scene::ray_cast::PickHit;
 use crate::scene::scene_node::SceneNode;
 use crate::text::font::Font;
 use crate::text::renderer::TextRenderer;
 use crate::verify;
 use crate::window::canvas::Canvas;
 use crate::window::canvas::CanvasSetup;
+use crate::scene::scene_description::{
+    CameraDescription, LightDescription, NodeDescription, SceneDescription,
+};
+use crate::window::frame_capture::FrameCapture;
+use crate::window::frame_clock::FrameClock;
+use crate::window::model_loader::{LoadStatus, ModelHandle, ModelLoader};
 use crate::window::state::State;
 use image::imageops;
 use image::{GenericImage, Pixel};
 use image::{ImageBuffer, Rgb};
 use ncollide3d::procedural::TriMesh;
+use ncollide3d::query::Ray;
 
 use super::window_cache::WindowCache;
 
 static DEFAULT_WIDTH: u32 = 800u32;
 static DEFAULT_HEIGHT: u32 = 600u32;
+static SIDEBAR_WIDTH: i32 = 200;
 
 pub struct Window {
     events: Rc<Receiver<WindowEvent>>,
+    event_sender: Sender<WindowEvent>,
     unhandled_events: Rc<RefCell<Vec<WindowEvent>>>,
     min_dur_per_frame: Option<Duration>,
+    clock: FrameClock,
+    input_map: InputMap,
+    load_dropped_files: bool,
+    dropped_models: Vec<ModelHandle>,
+    model_loader: ModelLoader,
+    recorder: Option<EventRecorder>,
+    // The error that stopped the current recording early, returned when it is stopped.
+    recorder_error: Option<Error>,
+    frame_capture: Option<FrameCapture>,
+    // The error that stopped the current frame capture early, returned when it is stopped.
+    frame_capture_error: Option<Error>,
+    replay: Option<EventReplay>,
     scene: SceneNode,
     scene2: PlanarSceneNode,
     light_mode: Light,
@@ -56,9 +87,11 @@ pub struct Window {
     line_renderer: LineRenderer,
     planar_line_renderer: PlanarLineRenderer,
     point_renderer: PointRenderer,
+    outline_renderer: OutlineRenderer,
     text_renderer: TextRenderer,
     framebuffer_manager: FramebufferManager,
     post_process_render_target: RenderTarget,
+    id_picker: Option<IdPicker>,
     #[cfg(not(target_arch = "wasm32"))]
     curr_time: std::time::Instant,
     planar_camera: Rc<RefCell<FixedView>>,
@@ -106,6 +139,310 @@ impl Window {
         })
     }
 
+    /// The frame counter and clock of this window, advanced after each rendered frame.
+    #[inline]
+    pub fn clock(&self) -> &FrameClock {
+        &self.clock
+    }
+
+    /// Sets the duration every frame advances the window clock by, or `None` to follow the
+    /// wall-clock time.
+    ///
+    /// This does not limit the framerate, see `set_framerate_limit` for this.
+    pub fn set_fixed_timestep(&mut self, timestep: Option<Duration>) {
+        self.clock.set_fixed_timestep(timestep)
+    }
+
+    /// The bindings used by default: `quit` with the escape key.
+    pub fn default_input_map() -> InputMap {
+        let mut input_map = InputMap::new();
+        input_map.bind("quit", Binding::key(Key::Escape));
+        input_map
+    }
+
+    /// The action bindings of this window, updated with the events of each frame.
+    ///
+    /// Applications can add their own actions and query them after each call to `render`.
+    #[inline]
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    #[inline]
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    /// Reads action bindings from a JSON file, see `InputMap::from_file`.
+    ///
+    /// The bindings are applied to both this window and its default camera.
+    pub fn load_input_bindings<P: AsRef<Path>>(&mut self, path: P) -> IoResult<()> {
+        let path = path.as_ref();
+        self.input_map.load_bindings(path)?;
+        self.camera.borrow_mut().input_map_mut().load_bindings(path)
+    }
+
+    /// Sets whether files dropped on this window are added to the scene.
+    ///
+    /// Files are loaded in the background with `add_model_async`, and the camera being rendered is
+    /// moved to frame them once loaded, see `Camera::frame_sphere`. Disabled by default; `WindowEvent::FileDropped` events are sent
+    /// either way.
+    pub fn set_load_dropped_files(&mut self, enabled: bool) {
+        self.load_dropped_files = enabled
+    }
+
+    pub fn load_dropped_files(&self) -> bool {
+        self.load_dropped_files
+    }
+
+    /// Sets whether shaders, textures and models are reloaded when their files change.
+    ///
+    /// Files are checked a few times per second for the shaders of effects created with
+    /// `Effect::new`, the textures added with `TextureManager::add` and the models added with
+    /// `add_obj` or `add_glb`. They are reloaded in place, so the handles to them stay valid.
+    /// Reload errors are drawn on the window until fixed, and the previous version is kept in the
+    /// meantime. Disabled by default.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        hot_reload::set_enabled(enabled)
+    }
+
+    pub fn hot_reload(&self) -> bool {
+        hot_reload::is_enabled()
+    }
+
+    /// The errors of the resources that failed to reload, as `<file>: <error>`.
+    pub fn hot_reload_errors(&self) -> Vec<String> {
+        hot_reload::errors()
+    }
+
+    // Draws the hot reload errors at the top left of the window.
+    fn draw_hot_reload_errors(&mut self) {
+        let font = Font::default();
+        let color = Point3::new(1.0, 0.3, 0.3);
+        let mut y = 10.0;
+
+        for error in hot_reload::errors() {
+            for line in error.lines() {
+                self.text_renderer
+                    .draw_text(line, &Point2::new(10.0, y), 30.0, &font, &color);
+                y += 30.0;
+            }
+        }
+    }
+
+    fn load_dropped_file(&mut self, path: &Path) {
+        let handle = self.add_model_async(path);
+        self.dropped_models.push(handle);
+    }
+
+    // Frames the camera being rendered on the dropped models that finished loading, and reports
+    // those that failed.
+    fn frame_dropped_models(&mut self, camera: &mut dyn Camera) {
+        self.dropped_models.retain(|handle| {
+            match handle.status() {
+                LoadStatus::Loaded => {
+                    if let Some(sphere) = handle.node().world_bounding_sphere() {
+                        camera.frame_sphere(*sphere.center(), sphere.radius());
+                    }
+                }
+                LoadStatus::Failed(e) => {
+                    let name = handle.node().name().unwrap_or_default();
+                    println!("Failed to load the dropped file {}: {}", name, e)
+                }
+                _ => {}
+            }
+
+            !handle.is_finished()
+        });
+    }
+
+    /// Adds a model file to the scene without blocking, see `SceneNode::add_model`.
+    ///
+    /// The file is read on a worker thread, and its meshes and textures are uploaded to the GPU
+    /// over the following frames. The returned handle reports the progress, and holds the node
+    /// receiving the model, which stays hidden until the model is loaded.
+    pub fn add_model_async<P: AsRef<Path>>(&mut self, path: P) -> ModelHandle {
+        self.model_loader.load(&mut self.scene, path.as_ref())
+    }
+
+    /// Sets the number of bytes of models uploaded to the GPU at each frame by `add_model_async`.
+    pub fn set_model_upload_budget(&mut self, bytes: usize) {
+        self.model_loader.set_upload_budget(bytes)
+    }
+
+    /// The number of models added with `add_model_async` that are still loading.
+    pub fn num_loading_models(&self) -> usize {
+        self.model_loader.num_pending()
+    }
+
+    /// Starts writing every event received by this window to a file.
+    ///
+    /// Each event is stored with the index of the frame it was delivered at so that it can be
+    /// fed back with `start_replay`.
+    pub fn start_recording<P: AsRef<Path>>(&mut self, path: P) -> IoResult<()> {
+        let time = self.clock.elapsed().as_secs_f64();
+        self.recorder = Some(EventRecorder::create(path, self.clock.frame(), time)?);
+        self.recorder_error = None;
+        Ok(())
+    }
+
+    /// Stops the current recording and flushes its file.
+    ///
+    /// If writing an event failed, the recording stopped at that event and its error is returned.
+    pub fn stop_recording(&mut self) -> IoResult<()> {
+        let result = match self.recorder.take() {
+            Some(mut recorder) => recorder.flush(),
+            None => Ok(()),
+        };
+
+        match self.recorder_error.take() {
+            Some(e) => Err(e),
+            None => result,
+        }
+    }
+
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Replaces the live keyboard, mouse and touch input by the events recorded in a file.
+    ///
+    /// Recorded events are delivered at the same frame offsets as during the recording, both to
+    /// `events()` and to the cameras. Other live events, e.g., window resizes, are still
+    /// delivered. The live input is restored once every recorded event has been replayed.
+    pub fn start_replay<P: AsRef<Path>>(&mut self, path: P) -> IoResult<()> {
+        self.replay_events(EventReplay::open(path)?);
+        Ok(())
+    }
+
+    /// Replaces the live input by an already loaded stream of events, see `start_replay`.
+    pub fn replay_events(&mut self, replay: EventReplay) {
+        self.canvas.set_replaying(true);
+        self.replay = Some(replay);
+    }
+
+    pub fn stop_replay(&mut self) {
+        self.canvas.set_replaying(false);
+        self.replay = None;
+    }
+
+    #[inline]
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Writes the scene, its light, background and the state of the default camera to a JSON
+    /// file.
+    ///
+    /// Meshes loaded with `SceneNode::add_model` are referenced by the path of their file,
+    /// relative to the scene file so that both can be moved together, and meshes registered in the `MeshManager` by their name; other meshes are written inline.
+    pub fn save_scene<P: AsRef<Path>>(&self, path: P) -> IoResult<()> {
+        let path = path.as_ref();
+        let camera = self.camera.borrow();
+        let description = SceneDescription {
+            background: self.background.into(),
+            light: LightDescription::from(&self.light_mode),
+            camera: CameraDescription {
+                eye: camera.eye().coords.into(),
+                at: camera.at().coords.into(),
+            },
+            root: NodeDescription::from_node(&self.scene, scene_dir(path)),
+        };
+
+        let out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(out, &description)?;
+        Ok(())
+    }
+
+    /// Replaces the scene, light, background and default camera state with the content of a
+    /// file written by `save_scene`.
+    ///
+    /// Nothing is changed if the file or any of the assets it references cannot be loaded.
+    pub fn load_scene<P: AsRef<Path>>(&mut self, path: P) -> IoResult<()> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        let description: SceneDescription = serde_json::from_reader(reader)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        description
+            .root
+            .apply_to(&mut self.scene, scene_dir(path))
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let [r, g, b] = description.background;
+        self.set_background_color(r, g, b);
+        self.set_light(description.light.into());
+        self.camera.borrow_mut().look_at(
+            description.camera.eye.into(),
+            description.camera.at.into(),
+        );
+
+        Ok(())
+    }
+
+    /// Starts writing every rendered frame to the given capture.
+    ///
+    /// Only the area where the scene is rendered is captured, see `snap_scene_image`.
+    pub fn start_frame_capture(&mut self, capture: FrameCapture) {
+        self.frame_capture = Some(capture);
+        self.frame_capture_error = None;
+    }
+
+    /// Stops the current frame capture and completes its files.
+    ///
+    /// If writing a frame failed, the capture stopped at that frame and its error is returned.
+    pub fn stop_frame_capture(&mut self) -> IoResult<()> {
+        let result = match self.frame_capture.take() {
+            Some(capture) => capture.finish(),
+            None => Ok(()),
+        };
+
+        match self.frame_capture_error.take() {
+            Some(e) => Err(e),
+            None => result,
+        }
+    }
+
+    #[inline]
+    pub fn is_capturing_frames(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    /// Renders `num_frames` frames while the default camera orbits once around the scene, and
+    /// writes them to `capture`.
+    ///
+    /// The camera is first moved to frame the bounding box of the scene. Returns early, with the
+    /// frames rendered so far, if the window is closed, and with an error if a frame could not be
+    /// written.
+    pub fn record_turntable(
+        &mut self,
+        num_frames: usize,
+        mut capture: FrameCapture,
+    ) -> IoResult<()> {
+        if let Some(aabb) = self.scene.world_aabb() {
+            self.camera
+                .borrow_mut()
+                .frame_sphere(aabb.center(), aabb.half_extents().norm());
+        }
+
+        let start_yaw = self.camera.borrow().yaw();
+        capture.set_expected_frames(num_frames);
+        self.start_frame_capture(capture);
+
+        for i in 0..num_frames {
+            let angle = std::f32::consts::PI * 2.0 * i as f32 / num_frames as f32;
+            self.camera.borrow_mut().set_yaw(start_yaw + angle);
+
+            if !self.render() || !self.is_capturing_frames() {
+                break;
+            }
+        }
+
+        self.camera.borrow_mut().set_yaw(start_yaw);
+        self.stop_frame_capture()
+    }
+
     pub fn set_title(&mut self, title: &str) {
         self.canvas.set_title(title)
     }
@@ -345,6 +682,10 @@ impl Window {
         Window::do_new(title, true, DEFAULT_WIDTH, DEFAULT_HEIGHT, None)
     }
 
+    pub fn new_hidden_with_size(title: &str, width: u32, height: u32) -> Window {
+        Window::do_new(title, true, width, height, None)
+    }
+
     pub fn new(title: &str) -> Window {
         Window::do_new(title, false, DEFAULT_WIDTH, DEFAULT_HEIGHT, None)
     }
@@ -365,7 +706,7 @@ impl Window {
         setup: Option<CanvasSetup>,
     ) -> Window {
         let (event_send, event_receive) = mpsc::channel();
-        let canvas = Canvas::open(title, hide, width, height, setup, event_send);
+        let canvas = Canvas::open(title, hide, width, height, setup, event_send.clone());
 
         init_gl();
         WindowCache::populate();
@@ -380,8 +721,19 @@ impl Window {
         let mut usr_window = Window {
             should_close: false,
             min_dur_per_frame: None,
+            clock: FrameClock::new(),
+            input_map: Window::default_input_map(),
+            load_dropped_files: false,
+            dropped_models: Vec::new(),
+            model_loader: ModelLoader::new(),
+            recorder: None,
+            recorder_error: None,
+            frame_capture: None,
+            frame_capture_error: None,
+            replay: None,
             canvas,
             events: Rc::new(event_receive),
+            event_sender: event_send,
             unhandled_events: Rc::new(RefCell::new(Vec::new())),
             scene: SceneNode::new_empty(),
             scene2: PlanarSceneNode::new_empty(),
@@ -390,6 +742,7 @@ impl Window {
             line_renderer: LineRenderer::new(),
             planar_line_renderer: PlanarLineRenderer::new(),
             point_renderer: PointRenderer::new(),
+            outline_renderer: OutlineRenderer::new(),
             text_renderer: TextRenderer::new(),
             post_process_render_target: FramebufferManager::new_render_target(
                 width as usize,
@@ -397,6 +750,7 @@ impl Window {
                 true,
             ),
             framebuffer_manager: FramebufferManager::new(),
+            id_picker: None,
             #[cfg(not(target_arch = "wasm32"))]
             curr_time: std::time::Instant::now(),
             planar_camera: Rc::new(RefCell::new(FixedView::new())),
@@ -465,6 +819,103 @@ impl Window {
         imageops::flip_vertical(&img)
     }
 
+    /// The width of the panels displayed on both sides of the scene.
+    pub fn sidebar_width() -> u32 {
+        SIDEBAR_WIDTH as u32
+    }
+
+    /// Captures the area of the window where the scene is rendered, without the side panels.
+    pub fn snap_scene_image(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let (width, height) = self.canvas.size();
+        let central_width = (width as i32 - 2 * SIDEBAR_WIDTH).max(1) as usize;
+        let mut buf = Vec::new();
+        self.snap_rect(
+            &mut buf,
+            SIDEBAR_WIDTH as usize,
+            0,
+            central_width,
+            height as usize,
+        );
+        let img_opt = ImageBuffer::from_vec(central_width as u32, height, buf);
+        let img = img_opt.expect("Buffer created from window was not big enough for image.");
+        imageops::flip_vertical(&img)
+    }
+
+    /// Computes the world-space ray passing through the given cursor position.
+    ///
+    /// Uses the window default camera if `camera` is `None`.
+    pub fn cursor_ray(&self, camera: Option<&dyn Camera>, x: f64, y: f64) -> Ray<f32> {
+        let (width, height) = self.canvas.size();
+        let central_width = width as i32 - 2 * SIDEBAR_WIDTH;
+        let coord = Point2::new(x as f32 - SIDEBAR_WIDTH as f32, y as f32);
+        let size = Vector2::new(central_width as f32, height as f32);
+
+        let (origin, dir) = match camera {
+            Some(cam) => cam.unproject(&coord, &size),
+            None => self.camera.borrow().unproject(&coord, &size),
+        };
+
+        Ray::new(origin, dir)
+    }
+
+    /// Returns the closest object of the scene under the given cursor position.
+    pub fn pick(&self, camera: Option<&dyn Camera>, x: f64, y: f64) -> Option<PickHit> {
+        let ray = self.cursor_ray(camera, x, y);
+        self.scene.cast_ray(&ray)
+    }
+
+    /// Returns the object of the scene visible at the given cursor position.
+    ///
+    /// Unlike `pick`, this renders the scene with one flat color per object and reads back the
+    /// pixel under the cursor, so the result matches exactly what is displayed.
+    pub fn pick_gpu(
+        &mut self,
+        camera: Option<&mut dyn Camera>,
+        x: f64,
+        y: f64,
+    ) -> Option<SceneNode> {
+        self.pick_rect(camera, x, y, x + 1.0, y + 1.0).into_iter().next()
+    }
+
+    /// Returns every object of the scene visible inside the rectangle delimited by two cursor
+    /// positions.
+    pub fn pick_rect(
+        &mut self,
+        camera: Option<&mut dyn Camera>,
+        x0: f64,
+        y0: f64,
+        x1: f64,
+        y1: f64,
+    ) -> Vec<SceneNode> {
+        let (width, height) = self.canvas.size();
+        let central_width = (width as i32 - 2 * SIDEBAR_WIDTH).max(1) as usize;
+        let left = x0.min(x1).floor() as i32 - SIDEBAR_WIDTH;
+        let top = y0.min(y1).floor() as i32;
+        let right = x0.max(x1).ceil() as i32 - SIDEBAR_WIDTH;
+        let bottom = y0.max(y1).ceil() as i32;
+        let rect = (left, top, (right - left).max(1), (bottom - top).max(1));
+
+        let picker = self.id_picker.get_or_insert_with(IdPicker::new);
+        let viewport_size = (central_width, height as usize);
+
+        match camera {
+            Some(cam) => picker.pick_rect(
+                &mut self.framebuffer_manager,
+                &self.scene,
+                cam,
+                viewport_size,
+                rect,
+            ),
+            None => picker.pick_rect(
+                &mut self.framebuffer_manager,
+                &self.scene,
+                &mut *self.camera.borrow_mut(),
+                viewport_size,
+                rect,
+            ),
+        }
+    }
+
     pub fn events(&self) -> EventManager {
         EventManager::new(self.events.clone(), self.unhandled_events.clone())
     }
@@ -486,6 +937,8 @@ impl Window {
         let unhandled_events = self.unhandled_events.clone();
         let events = self.events.clone();
 
+        self.input_map.new_frame();
+
         for event in unhandled_events.borrow().iter() {
             self.handle_event(camera, planar_camera, event)
         }
@@ -496,6 +949,47 @@ impl Window {
 
         unhandled_events.borrow_mut().clear();
         self.canvas.poll_events();
+        self.record_and_replay_events();
+    }
+
+    /// Records the events just polled, and substitutes replayed events for the live input.
+    fn record_and_replay_events(&mut self) {
+        if self.recorder.is_none() && self.replay.is_none() {
+            return;
+        }
+
+        let frame = self.clock.frame();
+        let time = self.clock.elapsed().as_secs_f64();
+        let mut events: Vec<WindowEvent> = self.events.try_iter().collect();
+
+        if let Some(ref mut replay) = self.replay {
+            events.retain(|e| {
+                !e.is_keyboard_event() && !e.is_mouse_event() && !e.is_touch_event()
+            });
+
+            for event in replay.events_for_frame(frame) {
+                self.canvas.replay_event(&event);
+                events.push(event);
+            }
+
+            if replay.is_finished() {
+                self.stop_replay();
+            }
+        }
+
+        if let Some(ref mut recorder) = self.recorder {
+            for event in events.iter() {
+                if let Err(e) = recorder.record(frame, time, event) {
+                    self.recorder = None;
+                    self.recorder_error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        for event in events {
+            let _ = self.event_sender.send(event);
+        }
     }
 
     fn handle_event(
@@ -504,13 +998,26 @@ impl Window {
         planar_camera: &mut Option<&mut dyn PlanarCamera>,
         event: &WindowEvent,
     ) {
+        let was_quitting = self.input_map.is_pressed("quit");
+        self.input_map.handle_event(event);
+
+        // Close when the binding is released rather than pressed. Losing the focus forgets the
+        // held keys without releasing them.
+        let focus_lost = matches!(*event, WindowEvent::Focus(false));
+        if was_quitting && !focus_lost && !self.input_map.is_pressed("quit") {
+            self.close();
+        }
+
         match *event {
-            WindowEvent::Key(Key::Escape, Action::Release, _) | WindowEvent::Close => {
+            WindowEvent::Close => {
                 self.close();
             }
             WindowEvent::FramebufferSize(w, h) => {
                 self.update_viewport(w as f32, h as f32);
             }
+            WindowEvent::FileDropped(ref path) if self.load_dropped_files => {
+                self.load_dropped_file(path);
+            }
             _ => {}
         }
 
@@ -730,16 +1237,24 @@ impl Window {
     ) -> bool {
         let window_width = self.width() as i32;
         let window_height = self.height() as i32;
-        let sidebar_width: i32 = 200;
+        let sidebar_width = SIDEBAR_WIDTH;
         let central_width = window_width - 2 * sidebar_width;
 
+        self.model_loader.update();
+        self.frame_dropped_models(camera);
+        TextureManager::get_global_manager(|tm| tm.next_frame());
+        MeshManager::get_global_manager(|mm| mm.next_frame());
+        MaterialManager::get_global_manager(|mm| mm.next_frame());
+        hot_reload::poll();
+
         planar_camera.handle_event(
             &self.canvas,
             &WindowEvent::FramebufferSize(window_width as u32, window_height as u32),
         );
+        // The 3D scene is only rendered between the side panels.
         camera.handle_event(
             &self.canvas,
-            &WindowEvent::FramebufferSize(window_width as u32, window_height as u32),
+            &WindowEvent::FramebufferSize(central_width.max(1) as u32, window_height as u32),
         );
         planar_camera.update(&self.canvas);
         camera.update(&self.canvas);
@@ -788,6 +1303,14 @@ impl Window {
             p.draw(&self.post_process_render_target);
         }
 
+        // Outlines are drawn after post-processing so that they are not affected by it.
+        self.outline_renderer.render(
+            &mut self.framebuffer_manager,
+            &self.scene,
+            camera,
+            (sidebar_width, 0, central_width, window_height),
+        );
+
         {
             Context::get().viewport(0, 0, sidebar_width, window_height);
             Context::get().scissor(0, 0, sidebar_width, window_height);
@@ -809,13 +1332,30 @@ impl Window {
             self.render_ui_right(sidebar_width, window_height);
         }
 
+        if hot_reload::is_enabled() {
+            self.draw_hot_reload_errors();
+        }
+
         self.text_renderer
             .render(window_width as f32, window_height as f32);
 
         Context::get().viewport(0, 0, window_width, window_height);
         Context::get().scissor(0, 0, window_width, window_height);
 
+        // Read the frame before the back buffer is swapped and its content becomes undefined.
+        if self.frame_capture.is_some() {
+            let image = self.snap_scene_image();
+
+            if let Some(capture) = self.frame_capture.as_mut() {
+                if let Err(e) = capture.add_frame(image) {
+                    self.frame_capture = None;
+                    self.frame_capture_error = Some(e);
+                }
+            }
+        }
+
         self.canvas.swap_buffers();
+        self.clock.tick();
 
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -879,3 +1419,11 @@ fn init_gl() {
     verify!(ctxt.enable(Context::CULL_FACE));
     verify!(ctxt.cull_face(Context::BACK));
 }
+
+// The directory the paths of a scene file are relative to.
+fn scene_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}